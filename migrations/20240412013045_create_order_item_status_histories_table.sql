@@ -0,0 +1,15 @@
+-- Add migration script here
+
+create table order_item_status_histories
+(
+    id              bigint      not null,
+    order_item_id   bigint      not null,
+    previous_status smallint,
+    next_status     smallint    not null,
+    changed_by      uuid        not null,
+    created_at      TIMESTAMPTZ not null,
+    primary key (id)
+);
+
+create index order_item_status_histories_order_item_id_idx
+    on order_item_status_histories (order_item_id, created_at);
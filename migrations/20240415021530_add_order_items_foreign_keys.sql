@@ -0,0 +1,16 @@
+-- Add migration script here
+
+alter table order_items
+    add constraint order_items_customer_id_fkey
+        foreign key (customer_id) references customers (id),
+    add constraint order_items_product_id_fkey
+        foreign key (product_id) references products (id);
+
+alter table order_item_status_histories
+    add constraint order_item_status_histories_order_item_id_fkey
+        foreign key (order_item_id) references order_items (id),
+    add constraint order_item_status_histories_changed_by_fkey
+        foreign key (changed_by) references users (id);
+
+create index order_items_customer_id_idx on order_items (customer_id);
+create index order_items_product_id_idx on order_items (product_id);
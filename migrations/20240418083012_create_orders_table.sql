@@ -0,0 +1,23 @@
+-- Add migration script here
+
+create table orders
+(
+    id          bigint      not null,
+    customer_id bigint      not null,
+    created_by  uuid        not null,
+    note        text,
+    created_at  TIMESTAMPTZ not null,
+    updated_at  TIMESTAMPTZ,
+    deleted_at  TIMESTAMPTZ,
+    primary key (id),
+    constraint orders_customer_id_fkey foreign key (customer_id) references customers (id),
+    constraint orders_created_by_fkey foreign key (created_by) references users (id)
+);
+
+create index orders_customer_id_idx on orders (customer_id);
+
+alter table order_items
+    add column order_id bigint,
+    add constraint order_items_order_id_fkey foreign key (order_id) references orders (id);
+
+create index order_items_order_id_idx on order_items (order_id);
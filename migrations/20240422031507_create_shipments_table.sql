@@ -0,0 +1,25 @@
+-- Add migration script here
+
+create table shipments
+(
+    id                  bigint      not null,
+    carrier             text        not null,
+    tracking_number     text,
+    shipped_at          TIMESTAMPTZ,
+    expected_arrival_at TIMESTAMPTZ,
+    arrived_at          TIMESTAMPTZ,
+    created_by          uuid        not null,
+    created_at          TIMESTAMPTZ not null,
+    updated_at          TIMESTAMPTZ,
+    deleted_at          TIMESTAMPTZ,
+    primary key (id),
+    constraint shipments_created_by_fkey foreign key (created_by) references users (id)
+);
+
+create index shipments_tracking_number_idx on shipments (tracking_number);
+
+alter table order_items
+    add column shipment_id bigint,
+    add constraint order_items_shipment_id_fkey foreign key (shipment_id) references shipments (id);
+
+create index order_items_shipment_id_idx on order_items (shipment_id);
@@ -0,0 +1,34 @@
+-- Add migration script here
+
+create table payments
+(
+    id          bigint         not null,
+    customer_id bigint         not null,
+    amount      decimal(12, 2) not null,
+    currency    smallint       not null,
+    method      smallint       not null,
+    note        text,
+    recorded_by uuid           not null,
+    created_at  TIMESTAMPTZ    not null,
+    voided_at   TIMESTAMPTZ,
+    voided_by   uuid,
+    primary key (id),
+    constraint payments_amount_check check (amount > 0),
+    constraint payments_customer_id_fkey foreign key (customer_id) references customers (id),
+    constraint payments_currency_fkey foreign key (currency) references currencies (id),
+    constraint payments_recorded_by_fkey foreign key (recorded_by) references users (id),
+    constraint payments_voided_by_fkey foreign key (voided_by) references users (id)
+);
+
+create index payments_customer_id_idx on payments (customer_id, created_at);
+
+create table payment_order_items
+(
+    payment_id    bigint not null,
+    order_item_id bigint not null,
+    primary key (payment_id, order_item_id),
+    constraint payment_order_items_payment_id_fkey foreign key (payment_id) references payments (id),
+    constraint payment_order_items_order_item_id_fkey foreign key (order_item_id) references order_items (id)
+);
+
+create index payment_order_items_order_item_id_idx on payment_order_items (order_item_id);
@@ -0,0 +1,16 @@
+-- Add migration script here
+
+alter table order_items
+    add column unit_price decimal(12, 2),
+    add column currency   smallint;
+
+update order_items
+set unit_price = products.price,
+    currency   = products.currency
+from products
+where order_items.product_id = products.id;
+
+alter table order_items
+    alter column unit_price set not null,
+    alter column currency set not null,
+    add constraint order_items_currency_fkey foreign key (currency) references currencies (id);
@@ -0,0 +1,31 @@
+-- Add migration script here
+
+alter table products
+    add column on_hand  integer not null default 0,
+    add column reserved integer not null default 0;
+
+-- Reserve the stock for the order items which are still waiting for the customers.
+update products
+set reserved = reservations.quantity
+from (select product_id, sum(quantity) as quantity
+      from order_items
+      where deleted_at is null
+        and status not in (4, 5, 6)
+      group by product_id) as reservations
+where products.id = reservations.product_id;
+
+create table stock_adjustments
+(
+    id         bigint      not null,
+    product_id bigint      not null,
+    kind       smallint    not null,
+    quantity   integer     not null,
+    reason     text        not null,
+    created_by uuid        not null,
+    created_at TIMESTAMPTZ not null,
+    primary key (id),
+    constraint stock_adjustments_product_id_fkey foreign key (product_id) references products (id),
+    constraint stock_adjustments_created_by_fkey foreign key (created_by) references users (id)
+);
+
+create index stock_adjustments_product_id_idx on stock_adjustments (product_id, created_at);
@@ -0,0 +1,28 @@
+-- Add migration script here
+
+create table product_variants
+(
+    id         bigint         not null,
+    product_id bigint         not null,
+    sku        varchar(64)    not null,
+    options    jsonb          not null,
+    price      decimal(12, 2),
+    on_hand    integer        not null default 0,
+    reserved   integer        not null default 0,
+    created_at TIMESTAMPTZ    not null,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    primary key (id),
+    constraint product_variants_sku_key unique (sku),
+    constraint product_variants_product_id_fkey foreign key (product_id) references products (id)
+);
+
+create index product_variants_product_id_idx on product_variants (product_id);
+
+alter table order_items
+    add column variant_id bigint,
+    add constraint order_items_variant_id_fkey foreign key (variant_id) references product_variants (id);
+
+alter table stock_adjustments
+    add column variant_id bigint,
+    add constraint stock_adjustments_variant_id_fkey foreign key (variant_id) references product_variants (id);
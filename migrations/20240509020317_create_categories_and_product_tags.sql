@@ -0,0 +1,32 @@
+-- Add migration script here
+
+create table categories
+(
+    id         bigint       not null,
+    parent_id  bigint,
+    name       varchar(128) not null,
+    created_at TIMESTAMPTZ  not null,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    primary key (id),
+    constraint categories_parent_id_fkey foreign key (parent_id) references categories (id)
+);
+
+-- The root categories have no parent, so the names are compared under a zero parent.
+create unique index categories_parent_id_name_key on categories (coalesce(parent_id, 0), name) where deleted_at is null;
+
+alter table products
+    add column category_id bigint,
+    add constraint products_category_id_fkey foreign key (category_id) references categories (id);
+
+create index products_category_id_idx on products (category_id);
+
+create table product_tags
+(
+    product_id bigint      not null,
+    tag        varchar(64) not null,
+    primary key (product_id, tag),
+    constraint product_tags_product_id_fkey foreign key (product_id) references products (id)
+);
+
+create index product_tags_tag_idx on product_tags (tag);
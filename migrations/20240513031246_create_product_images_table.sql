@@ -0,0 +1,22 @@
+-- Add migration script here
+
+create table product_images
+(
+    id            bigint       not null,
+    product_id    bigint       not null,
+    storage_key   varchar(255) not null,
+    thumbnail_key varchar(255) not null,
+    url           text         not null,
+    thumbnail_url text         not null,
+    content_type  varchar(64)  not null,
+    width         integer      not null,
+    height        integer      not null,
+    created_by    uuid         not null,
+    created_at    TIMESTAMPTZ  not null,
+    deleted_at    TIMESTAMPTZ,
+    primary key (id),
+    constraint product_images_product_id_fkey foreign key (product_id) references products (id),
+    constraint product_images_created_by_fkey foreign key (created_by) references users (id)
+);
+
+create index product_images_product_id_idx on product_images (product_id);
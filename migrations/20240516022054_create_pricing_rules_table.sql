@@ -0,0 +1,25 @@
+-- Add migration script here
+
+create table pricing_rules
+(
+    id            bigint         not null,
+    name          varchar(128)   not null,
+    currency      smallint       not null,
+    exchange_rate decimal(12, 6) not null check (exchange_rate > 0),
+    markup_rate   decimal(6, 4)  not null check (markup_rate >= 0),
+    handling_fee  decimal(12, 2) not null check (handling_fee >= 0),
+    created_at    TIMESTAMPTZ    not null,
+    updated_at    TIMESTAMPTZ,
+    deleted_at    TIMESTAMPTZ,
+    primary key (id),
+    constraint pricing_rules_currency_fkey foreign key (currency) references currencies (id)
+);
+
+-- The cost price is the shelf price in JPY, and the cost is converted into the currency of the
+-- product by the pricing rule.
+alter table products
+    add column cost_price      decimal(12, 2),
+    add column cost_tax_rate   decimal(5, 4),
+    add column pricing_rule_id bigint,
+    add column cost            decimal(12, 2),
+    add constraint products_pricing_rule_id_fkey foreign key (pricing_rule_id) references pricing_rules (id);
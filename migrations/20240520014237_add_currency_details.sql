@@ -0,0 +1,21 @@
+-- Add migration script here
+
+alter table currencies
+    rename column name to code;
+
+alter table currencies
+    alter column code type varchar(3),
+    add column symbol      varchar(8) not null default '',
+    add column minor_units smallint   not null default 2 check (minor_units between 0 and 4),
+    add constraint currencies_code_key unique (code);
+
+update currencies
+set symbol = 'MOP$'
+where id = 446;
+
+update currencies
+set symbol = 'HK$'
+where id = 344;
+
+insert into currencies (id, code, symbol, minor_units)
+values (392, 'JPY', '¥', 0);
@@ -0,0 +1,22 @@
+-- Add migration script here
+
+-- One unit of the base currency is worth `rate` units of the quote currency from `valid_from`
+-- until the next rate of the same currencies.
+create table exchange_rates
+(
+    id             bigint         not null,
+    base_currency  smallint       not null,
+    quote_currency smallint       not null,
+    rate           decimal(18, 8) not null,
+    valid_from     date           not null,
+    created_by     uuid           not null,
+    created_at     TIMESTAMPTZ    not null,
+    updated_at     TIMESTAMPTZ,
+    primary key (id),
+    constraint exchange_rates_rate_check check (rate > 0),
+    constraint exchange_rates_currencies_check check (base_currency <> quote_currency),
+    constraint exchange_rates_base_currency_fkey foreign key (base_currency) references currencies (id),
+    constraint exchange_rates_quote_currency_fkey foreign key (quote_currency) references currencies (id),
+    constraint exchange_rates_created_by_fkey foreign key (created_by) references users (id),
+    constraint exchange_rates_currencies_valid_from_key unique (base_currency, quote_currency, valid_from)
+);
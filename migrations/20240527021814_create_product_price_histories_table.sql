@@ -0,0 +1,20 @@
+-- Add migration script here
+
+-- Every change of the price or currency of a product, by an update or a repricing.
+create table product_price_histories
+(
+    id                bigint         not null,
+    product_id        bigint         not null,
+    previous_currency smallint       not null,
+    previous_price    decimal(12, 2) not null,
+    currency          smallint       not null,
+    price             decimal(12, 2) not null,
+    changed_by        uuid           not null,
+    created_at        TIMESTAMPTZ    not null,
+    primary key (id),
+    constraint product_price_histories_product_id_fkey foreign key (product_id) references products (id),
+    constraint product_price_histories_changed_by_fkey foreign key (changed_by) references users (id)
+);
+
+create index product_price_histories_product_id_idx
+    on product_price_histories (product_id, created_at);
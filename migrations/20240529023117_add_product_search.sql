@@ -0,0 +1,14 @@
+-- Add migration script here
+
+create extension if not exists pg_trgm;
+
+-- The name in the normalized and romanized forms, which is computed by the application. The
+-- existing products have no search text until they are indexed by the application once.
+alter table products
+    add column search_text text;
+
+create index products_search_text_trgm_idx
+    on products using gin (search_text gin_trgm_ops);
+
+create index products_search_text_tsv_idx
+    on products using gin (to_tsvector('simple', search_text));
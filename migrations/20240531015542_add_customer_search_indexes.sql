@@ -0,0 +1,16 @@
+-- Add migration script here
+
+create extension if not exists pg_trgm;
+
+-- The free-text search compares the lowercased texts and the digits of the phone numbers.
+create index customers_name_trgm_idx
+    on customers using gin (lower(name) gin_trgm_ops);
+
+create index customers_email_trgm_idx
+    on customers using gin (lower(email) gin_trgm_ops);
+
+create index customers_remark_trgm_idx
+    on customers using gin (lower(remark) gin_trgm_ops);
+
+create index customers_phone_digits_trgm_idx
+    on customers using gin (regexp_replace(coalesce(phone, ''), '[^0-9]', '', 'g') gin_trgm_ops);
@@ -0,0 +1,30 @@
+-- Add migration script here
+
+-- A customer merged into another one. The references of the merged customer are moved to the
+-- other one, and the merged customer is soft-deleted.
+create table customer_merges
+(
+    id                    bigint      not null,
+    from_customer_id      bigint      not null,
+    into_customer_id      bigint      not null,
+    order_items_moved     integer     not null,
+    orders_moved          integer     not null,
+    payments_moved        integer     not null,
+    contact_methods_moved integer     not null,
+    merged_by             uuid        not null,
+    created_at            TIMESTAMPTZ not null,
+    primary key (id),
+    constraint customer_merges_from_customer_id_fkey foreign key (from_customer_id) references customers (id),
+    constraint customer_merges_into_customer_id_fkey foreign key (into_customer_id) references customers (id),
+    constraint customer_merges_merged_by_fkey foreign key (merged_by) references users (id)
+);
+
+create index customer_merges_into_customer_id_idx
+    on customer_merges (into_customer_id);
+
+-- The duplicates are suggested by the same phone number or email.
+create index customers_phone_idx
+    on customers (phone);
+
+create index customers_email_idx
+    on customers (lower(email));
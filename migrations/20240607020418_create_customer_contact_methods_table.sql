@@ -0,0 +1,31 @@
+-- Add migration script here
+
+-- The channels through which a customer can be reached, e.g. WhatsApp or WeChat. A value
+-- belongs to one customer per kind, and a customer prefers at most one contact method.
+create table customer_contact_methods
+(
+    id          bigint       not null,
+    customer_id bigint       not null,
+    kind        smallint     not null,
+    value       varchar(256) not null,
+    preferred   boolean      not null default false,
+    verified    boolean      not null default false,
+    created_at  TIMESTAMPTZ  not null,
+    updated_at  TIMESTAMPTZ,
+    primary key (id),
+    constraint customer_contact_methods_customer_id_fkey foreign key (customer_id) references customers (id),
+    constraint customer_contact_methods_kind_value_key unique (kind, value)
+);
+
+create index customer_contact_methods_customer_id_idx
+    on customer_contact_methods (customer_id);
+
+create unique index customer_contact_methods_preferred_key
+    on customer_contact_methods (customer_id)
+    where preferred;
+
+create index customer_contact_methods_value_trgm_idx
+    on customer_contact_methods using gin (lower(value) gin_trgm_ops);
+
+create index customer_contact_methods_value_digits_trgm_idx
+    on customer_contact_methods using gin (regexp_replace(value, '[^0-9]', '', 'g') gin_trgm_ops);
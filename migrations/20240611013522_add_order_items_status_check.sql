@@ -0,0 +1,10 @@
+-- Add migration script here
+
+-- The statuses saved before they were typed could be any number. The unknown ones are mapped to
+-- requested, which keeps the stock they already reserve, so they can be moved on from there.
+update order_items
+set status = 0
+where status not between 0 and 6;
+
+alter table order_items
+    add constraint order_items_status_check check (status between 0 and 6);
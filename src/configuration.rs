@@ -8,6 +8,9 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub jwt: JwtSettings,
     pub redis_uri: Secret<String>,
+    pub storage: StorageSettings,
+    pub reporting: ReportingSettings,
+    pub phone: PhoneSettings,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -49,6 +52,41 @@ impl DatabaseSettings {
     }
 }
 
+/// Where the uploaded files are kept, `public_url` is the prefix of the URLs returned to the
+/// clients.
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StorageSettings {
+    Local {
+        root: String,
+        public_url: String,
+    },
+    /// Any S3-compatible service, e.g. AWS S3 or MinIO.
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        access_key_id: String,
+        secret_access_key: Secret<String>,
+        public_url: String,
+    },
+}
+
+/// The summaries present a total of all the currencies in the base currency.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ReportingSettings {
+    pub base_currency: i16,
+    /// The IANA time zone of the business, e.g. "Asia/Macau", the amounts are converted with the
+    /// rates of their local dates.
+    pub time_zone: String,
+}
+
+/// The phone numbers without a country code are in the default country, e.g. 853 for Macau.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct PhoneSettings {
+    pub default_country_code: u16,
+}
+
 #[derive(serde::Deserialize, Debug)]
 pub struct JwtSettings {
     pub secret_key: String,
@@ -2,6 +2,9 @@ use axum::extract::rejection::JsonRejection;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use sqlx::error::ErrorKind;
+
+use crate::routes::OrderItemStatus;
 
 #[derive(thiserror::Error, Debug)]
 pub enum AppError {
@@ -12,6 +15,22 @@ pub enum AppError {
     #[error(transparent)]
     Customer(#[from] CustomerError),
     #[error(transparent)]
+    Product(#[from] ProductError),
+    #[error(transparent)]
+    Category(#[from] CategoryError),
+    #[error(transparent)]
+    PricingRule(#[from] PricingRuleError),
+    #[error(transparent)]
+    Order(#[from] OrderError),
+    #[error(transparent)]
+    OrderItem(#[from] OrderItemError),
+    #[error(transparent)]
+    Shipment(#[from] ShipmentError),
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+    #[error(transparent)]
+    Constraint(#[from] ConstraintError),
+    #[error(transparent)]
     Auth(#[from] AuthError),
     #[error(transparent)]
     ChangePassword(#[from] ChangePasswordError),
@@ -28,6 +47,73 @@ impl IntoResponse for AppError {
             AppError::Customer(CustomerError::CustomerIsExist) => {
                 (StatusCode::CONFLICT, self.to_string())
             }
+            AppError::Customer(CustomerError::CustomerNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Customer(CustomerError::ContactMethodNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Product(ProductError::ProductNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Product(ProductError::NegativeOnHand) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
+            AppError::Product(ProductError::VariantNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Category(CategoryError::CategoryNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Category(CategoryError::CyclicParent) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            AppError::Category(CategoryError::CategoryIsNotEmpty) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
+            AppError::PricingRule(PricingRuleError::PricingRuleNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Order(OrderError::OrderNotFound) => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::Order(OrderError::OrderItemNotAttachable(_)) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            AppError::Order(OrderError::CustomerMismatch) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            AppError::OrderItem(OrderItemError::OrderItemNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::OrderItem(OrderItemError::InvalidStatusTransition(_, _)) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
+            AppError::OrderItem(OrderItemError::OutOfStock) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
+            AppError::Shipment(ShipmentError::ShipmentNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Shipment(ShipmentError::OrderItemNotShippable(_)) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            AppError::Shipment(ShipmentError::ShipmentIsArrived) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
+            AppError::Payment(PaymentError::PaymentNotFound) => {
+                (StatusCode::NOT_FOUND, self.to_string())
+            }
+            AppError::Payment(PaymentError::PaymentIsVoided) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
+            AppError::Payment(PaymentError::OrderItemNotPayable(_)) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            AppError::Constraint(ConstraintError::ReferenceNotExist(_)) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            AppError::Constraint(ConstraintError::Duplicate(_)) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
             AppError::DecodeSearchParameterFailed => (StatusCode::BAD_REQUEST, self.to_string()),
             AppError::JsonExtractorRejection(ref e) => match e {
                 JsonRejection::JsonDataError(_) => (StatusCode::BAD_REQUEST, self.to_string()),
@@ -60,6 +146,167 @@ impl IntoResponse for AppError {
 pub enum CustomerError {
     #[error("customer is exist.")]
     CustomerIsExist,
+    #[error("customer is not found.")]
+    CustomerNotFound,
+    #[error("contact method is not found.")]
+    ContactMethodNotFound,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProductError {
+    #[error("product is not found.")]
+    ProductNotFound,
+    #[error("the on-hand quantity of the product can't be negative.")]
+    NegativeOnHand,
+    #[error("product variant is not found.")]
+    VariantNotFound,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CategoryError {
+    #[error("category is not found.")]
+    CategoryNotFound,
+    #[error("a category can't be moved under its own descendant.")]
+    CyclicParent,
+    #[error("category still has subcategories or products.")]
+    CategoryIsNotEmpty,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PricingRuleError {
+    #[error("pricing rule is not found.")]
+    PricingRuleNotFound,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OrderError {
+    #[error("order is not found.")]
+    OrderNotFound,
+    #[error("order item {0} can't be attached to the order.")]
+    OrderItemNotAttachable(i64),
+    #[error("the customer doesn't match the order.")]
+    CustomerMismatch,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OrderItemError {
+    #[error("order item is not found.")]
+    OrderItemNotFound,
+    #[error("can't change the order item status from {0} to {1}.")]
+    InvalidStatusTransition(OrderItemStatus, OrderItemStatus),
+    #[error("product is out of stock.")]
+    OutOfStock,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ShipmentError {
+    #[error("shipment is not found.")]
+    ShipmentNotFound,
+    #[error("order item {0} can't be shipped.")]
+    OrderItemNotShippable(i64),
+    #[error("shipment is arrived.")]
+    ShipmentIsArrived,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PaymentError {
+    #[error("payment is not found.")]
+    PaymentNotFound,
+    #[error("payment is voided.")]
+    PaymentIsVoided,
+    #[error("order item {0} doesn't belong to the customer of the payment.")]
+    OrderItemNotPayable(i64),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConstraintError {
+    #[error("{0} is not exist.")]
+    ReferenceNotExist(&'static str),
+    #[error("{0} is duplicate.")]
+    Duplicate(&'static str),
+}
+
+impl ConstraintError {
+    /// Convert a constraint violation reported by Postgres into a typed error.
+    /// Return `None` when the error isn't a known constraint violation.
+    pub fn from_sqlx(e: &sqlx::Error) -> Option<Self> {
+        let db_error = e.as_database_error()?;
+        let constraint = db_error.constraint()?;
+
+        match db_error.kind() {
+            ErrorKind::ForeignKeyViolation => Some(ConstraintError::ReferenceNotExist(
+                referenced_name(constraint),
+            )),
+            ErrorKind::UniqueViolation => {
+                Some(ConstraintError::Duplicate(referenced_name(constraint)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn referenced_name(constraint: &str) -> &'static str {
+    match constraint {
+        "order_items_customer_id_fkey" => "customer",
+        "order_items_product_id_fkey" => "product",
+        "order_item_status_histories_order_item_id_fkey" => "order item",
+        "order_item_status_histories_changed_by_fkey" => "user",
+        "product_price_histories_product_id_fkey" => "product",
+        "product_price_histories_changed_by_fkey" => "user",
+        "orders_customer_id_fkey" => "customer",
+        "orders_created_by_fkey" => "user",
+        "order_items_order_id_fkey" => "order",
+        "shipments_created_by_fkey" => "user",
+        "order_items_shipment_id_fkey" => "shipment",
+        "payments_customer_id_fkey" => "customer",
+        "payments_currency_fkey" => "currency",
+        "payments_recorded_by_fkey" => "user",
+        "payments_voided_by_fkey" => "user",
+        "payment_order_items_payment_id_fkey" => "payment",
+        "payment_order_items_order_item_id_fkey" => "order item",
+        "stock_adjustments_product_id_fkey" => "product",
+        "stock_adjustments_created_by_fkey" => "user",
+        "product_variants_product_id_fkey" => "product",
+        "product_variants_sku_key" => "sku",
+        "order_items_variant_id_fkey" => "product variant",
+        "order_items_currency_fkey" => "currency",
+        "stock_adjustments_variant_id_fkey" => "product variant",
+        "categories_parent_id_fkey" => "category",
+        "categories_parent_id_name_key" => "category name",
+        "products_category_id_fkey" => "category",
+        "product_tags_product_id_fkey" => "product",
+        "product_images_product_id_fkey" => "product",
+        "product_images_created_by_fkey" => "user",
+        "pricing_rules_currency_fkey" => "currency",
+        "currencies_pkey" => "currency",
+        "currencies_code_key" => "currency code",
+        "exchange_rates_base_currency_fkey" => "currency",
+        "exchange_rates_quote_currency_fkey" => "currency",
+        "exchange_rates_created_by_fkey" => "user",
+        "products_pricing_rule_id_fkey" => "pricing rule",
+        "customer_merges_from_customer_id_fkey" => "customer",
+        "customer_merges_into_customer_id_fkey" => "customer",
+        "customer_merges_merged_by_fkey" => "user",
+        "customer_contact_methods_customer_id_fkey" => "customer",
+        "customer_contact_methods_kind_value_key" => "contact method",
+        "customer_contact_methods_preferred_key" => "preferred contact method",
+        _ => "record",
+    }
+}
+
+pub trait DatabaseResultExt<T> {
+    /// Map constraint violations into `AppError::Constraint`, and wrap the other database
+    /// errors with the context as an unexpected error.
+    fn db_context(self, context: &'static str) -> Result<T, AppError>;
+}
+
+impl<T> DatabaseResultExt<T> for Result<T, sqlx::Error> {
+    fn db_context(self, context: &'static str) -> Result<T, AppError> {
+        self.map_err(|e| match ConstraintError::from_sqlx(&e) {
+            Some(constraint_error) => AppError::Constraint(constraint_error),
+            None => AppError::UnexpectedError(anyhow::Error::new(e).context(context)),
+        })
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
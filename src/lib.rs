@@ -4,5 +4,6 @@ pub mod errors;
 pub mod repositories;
 pub mod routes;
 pub mod startup;
+pub mod storage;
 pub mod telemetry;
 pub mod utils;
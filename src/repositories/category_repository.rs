@@ -0,0 +1,241 @@
+use chrono::Utc;
+use sea_query::{Expr, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sqlx::{Connection, Error, PgConnection, Row};
+
+use crate::errors::CategoryError;
+use crate::repositories::Products;
+use crate::routes::{CategoryJson, NewCategory, UpdateCategory};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum Categories {
+    Table,
+    Id,
+    ParentId,
+    Name,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
+
+/// Build a select statement of the categories.
+/// The column order must be kept in sync with the `FromRow` implementation of `CategoryJson`.
+fn select_categories() -> SelectStatement {
+    Query::select()
+        .columns([
+            (Categories::Table, Categories::Id),
+            (Categories::Table, Categories::ParentId),
+            (Categories::Table, Categories::Name),
+            (Categories::Table, Categories::CreatedAt),
+            (Categories::Table, Categories::UpdatedAt),
+            (Categories::Table, Categories::DeletedAt),
+        ])
+        .from(Categories::Table)
+        .to_owned()
+}
+
+/// Return the id of the category and the ids of all its non-deleted descendants.
+pub(crate) async fn category_subtree(conn: &mut PgConnection, id: i64) -> Result<Vec<i64>, Error> {
+    // sea-query can't express a recursive common table expression inside a select statement
+    // nicely, so it's written by hand.
+    let query = r#"
+        with recursive subtree(id) as (
+            select id from categories where id = $1 and deleted_at is null
+            union
+            select categories.id from categories join subtree on categories.parent_id = subtree.id
+            where categories.deleted_at is null
+        )
+        select id from subtree"#;
+
+    let rows = sqlx::query(query).bind(id).fetch_all(&mut *conn).await?;
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+#[async_trait::async_trait]
+pub trait CategoryRepo {
+    async fn get(&self, id: i64) -> Result<Option<CategoryJson>, Error>;
+
+    async fn create(&self, new_category: NewCategory) -> Result<i64, Error>;
+
+    /// Fail with `CyclicParent` when the new parent is the category itself or its descendant.
+    async fn update(
+        &self,
+        update_category: UpdateCategory,
+    ) -> Result<Result<(), CategoryError>, Error>;
+
+    /// Fail with `CategoryIsNotEmpty` when the category still has subcategories or products.
+    async fn delete(&self, id: i64) -> Result<Result<(), CategoryError>, Error>;
+
+    /// List all the non-deleted categories, the client builds the tree by `parent_id`.
+    async fn list(&self) -> Result<Vec<CategoryJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresCategoryRepo {
+    session: PostgresSession,
+}
+
+impl PostgresCategoryRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl CategoryRepo for PostgresCategoryRepo {
+    #[tracing::instrument(name = "Get a category from database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<CategoryJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_categories()
+            .and_where(Expr::col((Categories::Table, Categories::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, CategoryJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Save a new category into database", skip(self))]
+    async fn create(&self, new_category: NewCategory) -> Result<i64, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let query = Query::insert()
+            .into_table(Categories::Table)
+            .columns([
+                Categories::Id,
+                Categories::ParentId,
+                Categories::Name,
+                Categories::CreatedAt,
+                Categories::UpdatedAt,
+            ])
+            .values_panic([
+                new_category.id.into(),
+                new_category.parent_id.into(),
+                new_category.name.0.into(),
+                now.into(),
+                now.into(),
+            ])
+            .returning(Query::returning().column(Categories::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).fetch_one(conn.as_mut()).await?;
+
+        Ok(res.get(0))
+    }
+
+    #[tracing::instrument(name = "Update a category in database", skip(self))]
+    async fn update(
+        &self,
+        update_category: UpdateCategory,
+    ) -> Result<Result<(), CategoryError>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let mut tx = conn.as_mut().begin().await?;
+
+        // Moving and deleting categories are serialized, otherwise two concurrent moves could
+        // each pass the cycle check and make a loop together.
+        let _ = sqlx::query("lock table categories in share row exclusive mode")
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(Some(parent_id)) = update_category.parent_id {
+            let subtree = category_subtree(&mut tx, update_category.id).await?;
+
+            if subtree.contains(&parent_id) {
+                return Ok(Err(CategoryError::CyclicParent));
+            }
+        }
+
+        let query = {
+            let mut update_data = vec![];
+
+            if let Some(parent_id) = update_category.parent_id {
+                update_data.push((Categories::ParentId, parent_id.into()));
+            }
+
+            if let Some(name) = update_category.name {
+                update_data.push((Categories::Name, name.0.into()));
+            }
+
+            update_data.push((Categories::UpdatedAt, Utc::now().into()));
+
+            Query::update()
+                .table(Categories::Table)
+                .values(update_data)
+                .and_where(Expr::col((Categories::Table, Categories::Id)).eq(update_category.id))
+                .to_string(PostgresQueryBuilder)
+        };
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(Ok(()))
+    }
+
+    #[tracing::instrument(name = "Delete a category from database", skip(self))]
+    async fn delete(&self, id: i64) -> Result<Result<(), CategoryError>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let mut tx = conn.as_mut().begin().await?;
+
+        let _ = sqlx::query("lock table categories in share row exclusive mode")
+            .execute(&mut *tx)
+            .await?;
+
+        let query = Query::select()
+            .expr(Expr::exists(
+                Query::select()
+                    .column(Categories::Id)
+                    .from(Categories::Table)
+                    .and_where(Expr::col(Categories::ParentId).eq(id))
+                    .and_where(Expr::col(Categories::DeletedAt).is_null())
+                    .to_owned(),
+            ))
+            .expr(Expr::exists(
+                Query::select()
+                    .column(Products::Id)
+                    .from(Products::Table)
+                    .and_where(Expr::col(Products::CategoryId).eq(id))
+                    .and_where(Expr::col(Products::DeletedAt).is_null())
+                    .to_owned(),
+            ))
+            .to_string(PostgresQueryBuilder);
+
+        let row = sqlx::query(query.as_str()).fetch_one(&mut *tx).await?;
+        let (has_children, has_products): (bool, bool) = (row.try_get(0)?, row.try_get(1)?);
+
+        if has_children || has_products {
+            return Ok(Err(CategoryError::CategoryIsNotEmpty));
+        }
+
+        let query = Query::update()
+            .table(Categories::Table)
+            .values([(Categories::DeletedAt, Utc::now().into())])
+            .and_where(Expr::col((Categories::Table, Categories::Id)).eq(id))
+            .and_where(Expr::col((Categories::Table, Categories::DeletedAt)).is_null())
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(Ok(()))
+    }
+
+    #[tracing::instrument(name = "List categories from database", skip(self))]
+    async fn list(&self) -> Result<Vec<CategoryJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_categories()
+            .and_where(Expr::col((Categories::Table, Categories::DeletedAt)).is_null())
+            .order_by((Categories::Table, Categories::Name), Order::Asc)
+            .order_by((Categories::Table, Categories::Id), Order::Asc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, CategoryJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
@@ -1 +1,83 @@
+use sea_query::{Order, PostgresQueryBuilder, Query};
+use sqlx::{Error, Row};
 
+use crate::routes::{CurrencyJson, NewCurrency};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum Currencies {
+    Table,
+    Id,
+    Code,
+    Symbol,
+    MinorUnits,
+}
+
+#[async_trait::async_trait]
+pub trait CurrencyRepo {
+    async fn create(&self, new_currency: NewCurrency) -> Result<i16, Error>;
+
+    async fn list(&self) -> Result<Vec<CurrencyJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresCurrencyRepo {
+    session: PostgresSession,
+}
+
+impl PostgresCurrencyRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl CurrencyRepo for PostgresCurrencyRepo {
+    #[tracing::instrument(name = "Save a new currency into database", skip(self))]
+    async fn create(&self, new_currency: NewCurrency) -> Result<i16, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::insert()
+            .into_table(Currencies::Table)
+            .columns([
+                Currencies::Id,
+                Currencies::Code,
+                Currencies::Symbol,
+                Currencies::MinorUnits,
+            ])
+            .values_panic([
+                new_currency.id.into(),
+                new_currency.code.0.into(),
+                new_currency.symbol.into(),
+                new_currency.minor_units.into(),
+            ])
+            .returning(Query::returning().column(Currencies::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).fetch_one(conn.as_mut()).await?;
+
+        Ok(res.get(0))
+    }
+
+    #[tracing::instrument(name = "List currencies from database", skip(self))]
+    async fn list(&self) -> Result<Vec<CurrencyJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        // The column order must be kept in sync with the `FromRow` implementation of
+        // `CurrencyJson`.
+        let query = Query::select()
+            .columns([
+                Currencies::Id,
+                Currencies::Code,
+                Currencies::Symbol,
+                Currencies::MinorUnits,
+            ])
+            .from(Currencies::Table)
+            .order_by(Currencies::Code, Order::Asc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, CurrencyJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
@@ -0,0 +1,234 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_query::{Expr, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sqlx::{Connection, Error, PgConnection, Row};
+
+use crate::routes::{ContactMethodJson, NewContactMethod, UpdateContactMethod};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum CustomerContactMethods {
+    Table,
+    Id,
+    CustomerId,
+    Kind,
+    Value,
+    Preferred,
+    Verified,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Build a select statement of the contact methods, the preferred one comes first.
+/// The column order must be kept in sync with the `FromRow` implementation of
+/// `ContactMethodJson`.
+fn select_contact_methods() -> SelectStatement {
+    Query::select()
+        .columns([
+            CustomerContactMethods::Id,
+            CustomerContactMethods::CustomerId,
+            CustomerContactMethods::Kind,
+            CustomerContactMethods::Value,
+            CustomerContactMethods::Preferred,
+            CustomerContactMethods::Verified,
+            CustomerContactMethods::CreatedAt,
+            CustomerContactMethods::UpdatedAt,
+        ])
+        .from(CustomerContactMethods::Table)
+        .order_by(CustomerContactMethods::Preferred, Order::Desc)
+        .order_by(CustomerContactMethods::Id, Order::Asc)
+        .to_owned()
+}
+
+/// List the contact methods of the customers with the given connection, so that they can be
+/// filled into the customers in one query.
+pub(crate) async fn list_contact_methods_of_customers(
+    conn: &mut PgConnection,
+    customer_ids: &[i64],
+) -> Result<Vec<ContactMethodJson>, Error> {
+    if customer_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let query = select_contact_methods()
+        .and_where(Expr::col(CustomerContactMethods::CustomerId).is_in(customer_ids.to_vec()))
+        .to_string(PostgresQueryBuilder);
+
+    sqlx::query_as::<_, ContactMethodJson>(query.as_str())
+        .fetch_all(conn)
+        .await
+}
+
+/// Unset the preferred flag of the other contact methods of the customer which owns the contact
+/// method, so that the customer prefers at most one of them.
+async fn unset_other_preferred(
+    conn: &mut PgConnection,
+    customer_id: i64,
+    id: i64,
+) -> Result<(), Error> {
+    let query = Query::update()
+        .table(CustomerContactMethods::Table)
+        .values([
+            (CustomerContactMethods::Preferred, false.into()),
+            (CustomerContactMethods::UpdatedAt, Utc::now().into()),
+        ])
+        .and_where(Expr::col(CustomerContactMethods::CustomerId).eq(customer_id))
+        .and_where(Expr::col(CustomerContactMethods::Id).ne(id))
+        .and_where(Expr::col(CustomerContactMethods::Preferred).eq(true))
+        .to_string(PostgresQueryBuilder);
+
+    let _ = sqlx::query(query.as_str()).execute(conn).await?;
+
+    Ok(())
+}
+
+#[async_trait]
+pub trait CustomerContactMethodRepo {
+    async fn get(&self, id: i64) -> Result<Option<ContactMethodJson>, Error>;
+
+    async fn list(&self, customer_id: i64) -> Result<Vec<ContactMethodJson>, Error>;
+
+    async fn create(&self, contact_method: NewContactMethod) -> Result<i64, Error>;
+
+    async fn update(&self, contact_method: UpdateContactMethod) -> Result<(), Error>;
+
+    /// Return whether the contact method existed.
+    async fn delete(&self, id: i64) -> Result<bool, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresCustomerContactMethodRepo {
+    session: PostgresSession,
+}
+
+impl PostgresCustomerContactMethodRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait]
+impl CustomerContactMethodRepo for PostgresCustomerContactMethodRepo {
+    #[tracing::instrument(name = "Get a contact method from database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<ContactMethodJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_contact_methods()
+            .and_where(Expr::col(CustomerContactMethods::Id).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, ContactMethodJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "List the contact methods of a customer", skip(self))]
+    async fn list(&self, customer_id: i64) -> Result<Vec<ContactMethodJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        list_contact_methods_of_customers(conn.as_mut(), &[customer_id]).await
+    }
+
+    #[tracing::instrument(name = "Save a new contact method into database", skip(self))]
+    async fn create(&self, contact_method: NewContactMethod) -> Result<i64, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        if contact_method.preferred {
+            unset_other_preferred(&mut tx, contact_method.customer_id, contact_method.id).await?;
+        }
+
+        let query = Query::insert()
+            .into_table(CustomerContactMethods::Table)
+            .columns([
+                CustomerContactMethods::Id,
+                CustomerContactMethods::CustomerId,
+                CustomerContactMethods::Kind,
+                CustomerContactMethods::Value,
+                CustomerContactMethods::Preferred,
+                CustomerContactMethods::Verified,
+                CustomerContactMethods::CreatedAt,
+                CustomerContactMethods::UpdatedAt,
+            ])
+            .values_panic([
+                contact_method.id.into(),
+                contact_method.customer_id.into(),
+                contact_method.kind.code().into(),
+                contact_method.value.0.into(),
+                contact_method.preferred.into(),
+                contact_method.verified.into(),
+                now.into(),
+                now.into(),
+            ])
+            .returning(Query::returning().column(CustomerContactMethods::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).fetch_one(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(res.get(0))
+    }
+
+    #[tracing::instrument(name = "Update a contact method in database", skip(self))]
+    async fn update(&self, contact_method: UpdateContactMethod) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        if contact_method.preferred == Some(true) {
+            let query = Query::select()
+                .column(CustomerContactMethods::CustomerId)
+                .from(CustomerContactMethods::Table)
+                .and_where(Expr::col(CustomerContactMethods::Id).eq(contact_method.id))
+                .to_string(PostgresQueryBuilder);
+
+            if let Some(row) = sqlx::query(query.as_str()).fetch_optional(&mut *tx).await? {
+                unset_other_preferred(&mut tx, row.get(0), contact_method.id).await?;
+            }
+        }
+
+        let mut update_data = vec![];
+        if let Some(value) = contact_method.value {
+            update_data.push((CustomerContactMethods::Value, value.0.into()));
+        }
+
+        if let Some(preferred) = contact_method.preferred {
+            update_data.push((CustomerContactMethods::Preferred, preferred.into()));
+        }
+
+        if let Some(verified) = contact_method.verified {
+            update_data.push((CustomerContactMethods::Verified, verified.into()));
+        }
+
+        update_data.push((CustomerContactMethods::UpdatedAt, Utc::now().into()));
+
+        let query = Query::update()
+            .table(CustomerContactMethods::Table)
+            .values(update_data)
+            .and_where(Expr::col(CustomerContactMethods::Id).eq(contact_method.id))
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Delete a contact method from database", skip(self))]
+    async fn delete(&self, id: i64) -> Result<bool, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::delete()
+            .from_table(CustomerContactMethods::Table)
+            .and_where(Expr::col(CustomerContactMethods::Id).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).execute(conn.as_mut()).await?;
+
+        Ok(res.rows_affected() == 1)
+    }
+}
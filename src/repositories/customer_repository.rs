@@ -1,13 +1,21 @@
 use async_trait::async_trait;
 use chrono::Utc;
-use sea_query::{Expr, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query::extension::postgres::PgExpr;
+use sea_query::{
+    Alias, Cond, Expr, IntoIden, JoinType, LockType, Order, PostgresQueryBuilder, Query, SimpleExpr,
+};
 
-use sqlx::{Error, Row};
+use sqlx::{Connection, Error, PgConnection, Row};
 
+use crate::repositories::{
+    list_contact_methods_of_customers, CustomerContactMethods, OrderItems, Orders, Payments,
+};
 use crate::routes::{
-    CustomerJson, CustomerSearchParameters, NewCustomer, UpdateCustomer, ValidEmail, ValidPhone,
+    ContactMethodKind, CustomerDuplicateJson, CustomerJson, CustomerMergeJson, CustomerQuery,
+    CustomerQueryTerm, CustomerSearchParameters, NewCustomer, NewCustomerMerge,
+    NormalizePhonesResponse, UnnormalizedPhoneJson, UpdateCustomer, ValidEmail, ValidPhone,
 };
-use crate::utils::PostgresSession;
+use crate::utils::{contains_pattern, escape_like, PostgresSession};
 
 #[derive(sea_query::Iden)]
 pub(crate) enum Customers {
@@ -45,6 +53,105 @@ pub trait CustomerRepo {
         email: &Option<ValidEmail>,
         phone: &Option<ValidPhone>,
     ) -> Result<bool, Error>;
+
+    /// Suggest the pairs of non-deleted customers which have the same phone number or email, or
+    /// similar names.
+    async fn list_duplicates(
+        &self,
+        customer_id: Option<i64>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<CustomerDuplicateJson>, Error>;
+
+    /// Move the order items, orders, payments and contact methods of a customer to another one,
+    /// combine their remarks and soft-delete the merged customer in one transaction, then record
+    /// the merge. Return `None` when either customer doesn't exist or is deleted.
+    async fn merge(
+        &self,
+        merge: NewCustomerMerge,
+        merged_by: uuid::Uuid,
+    ) -> Result<Option<CustomerMergeJson>, Error>;
+
+    /// Store the phone numbers which aren't in E.164 yet in E.164, e.g. the ones saved before
+    /// the numbers were normalized. The numbers which can't be parsed are left unchanged.
+    async fn normalize_phones(
+        &self,
+        default_country_code: u16,
+    ) -> Result<NormalizePhonesResponse, Error>;
+}
+
+#[derive(sea_query::Iden)]
+pub(crate) enum CustomerMerges {
+    Table,
+    Id,
+    FromCustomerId,
+    IntoCustomerId,
+    OrderItemsMoved,
+    OrdersMoved,
+    PaymentsMoved,
+    ContactMethodsMoved,
+    MergedBy,
+    CreatedAt,
+}
+
+/// The columns of the customers under the alias.
+/// The column order must be kept in sync with the `FromRow` implementation of `CustomerJson`.
+fn customer_columns(alias: &str) -> Vec<(Alias, Customers)> {
+    [
+        Customers::Id,
+        Customers::Name,
+        Customers::Email,
+        Customers::Phone,
+        Customers::Remark,
+        Customers::CreatedAt,
+        Customers::UpdatedAt,
+        Customers::DeletedAt,
+    ]
+    .into_iter()
+    .map(|e| (Alias::new(alias), e))
+    .collect()
+}
+
+/// Point the references of a customer to another one, return the number of the moved rows.
+async fn move_customer_references(
+    conn: &mut PgConnection,
+    table: impl IntoIden + 'static,
+    customer_id: impl IntoIden + 'static,
+    merge: &NewCustomerMerge,
+) -> Result<i32, Error> {
+    let customer_id = customer_id.into_iden();
+
+    let query = Query::update()
+        .table(table)
+        .value(customer_id.clone(), merge.into_customer_id)
+        .and_where(Expr::col(customer_id).eq(merge.from_customer_id))
+        .to_string(PostgresQueryBuilder);
+
+    let res = sqlx::query(query.as_str()).execute(conn).await?;
+
+    Ok(res.rows_affected() as i32)
+}
+
+/// Fill the contact methods into the customers.
+async fn fill_contact_methods(
+    conn: &mut PgConnection,
+    customers: &mut [CustomerJson],
+) -> Result<(), Error> {
+    let ids = customers
+        .iter()
+        .filter_map(|e| e.id.parse::<i64>().ok())
+        .collect::<Vec<_>>();
+
+    for contact_method in list_contact_methods_of_customers(conn, &ids).await? {
+        if let Some(customer) = customers
+            .iter_mut()
+            .find(|e| e.id == contact_method.customer_id)
+        {
+            customer.contact_methods.push(contact_method);
+        }
+    }
+
+    Ok(())
 }
 
 #[derive(Clone, Debug)]
@@ -61,7 +168,7 @@ impl PostgresCustomerRepoImpl {
 impl CustomerRepo for PostgresCustomerRepoImpl {
     #[tracing::instrument(name = "get a customer from database", skip(self))]
     async fn get(&self, customer_id: i64) -> Result<Option<CustomerJson>, Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
         let query = Query::select()
             .from(Customers::Table)
             .columns([
@@ -77,14 +184,22 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
             .and_where(Expr::col((Customers::Table, Customers::Id)).eq(customer_id))
             .to_string(PostgresQueryBuilder);
 
-        sqlx::query_as::<_, CustomerJson>(dbg!(&query))
+        let customer = sqlx::query_as::<_, CustomerJson>(dbg!(&query))
             .fetch_optional(conn.as_mut())
-            .await
+            .await?;
+
+        match customer {
+            Some(mut customer) => {
+                fill_contact_methods(conn.as_mut(), std::slice::from_mut(&mut customer)).await?;
+                Ok(Some(customer))
+            }
+            None => Ok(None),
+        }
     }
 
     #[tracing::instrument(name = "Save a new customer into database", skip(self, customer))]
     async fn create(&self, customer: NewCustomer) -> Result<i64, sqlx::Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = {
             let id = customer.id.into();
@@ -119,7 +234,7 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
 
     #[tracing::instrument(name = "update a customer in database", skip(self, customer))]
     async fn update(&self, customer: UpdateCustomer) -> Result<(), Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
         let query = {
             let mut update_data = vec![];
             if let Some(name) = customer.name {
@@ -156,7 +271,7 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
 
     #[tracing::instrument(name = "mark a customer deleted_at in database", skip(self))]
     async fn delete(&self, id: i64) -> Result<(), Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = Query::update()
             .table(Customers::Table)
@@ -175,15 +290,103 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
         page: u64,
         page_size: u64,
     ) -> Result<Vec<CustomerJson>, Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
         let offset = page * page_size;
 
         fn format_like_string(tbl: Customers, col: Customers, value: &str) -> SimpleExpr {
-            let formatted_string = format!(r#"%{value}%"#);
-            Expr::col((tbl, col)).like(formatted_string.as_str())
+            Expr::col((tbl, col)).ilike(contains_pattern(value))
         }
 
-        let query = Query::select()
+        // The expressions must be kept in sync with the indexes of the customers.
+        const PHONE_DIGITS: &str =
+            r#"regexp_replace(coalesce("customers"."phone", ''), '[^0-9]', '', 'g')"#;
+
+        // A customer matches when any of the contact methods of the kind contains the term, or
+        // when the kind is given without a term and the customer has any contact method of it.
+        fn contact_condition(
+            term: Option<&CustomerQueryTerm>,
+            kind: Option<ContactMethodKind>,
+        ) -> SimpleExpr {
+            let mut query = Query::select()
+                .expr(Expr::val(1))
+                .from(CustomerContactMethods::Table)
+                .and_where(
+                    Expr::col((
+                        CustomerContactMethods::Table,
+                        CustomerContactMethods::CustomerId,
+                    ))
+                    .equals((Customers::Table, Customers::Id)),
+                )
+                .and_where_option(kind.map(|e| {
+                    Expr::col((CustomerContactMethods::Table, CustomerContactMethods::Kind))
+                        .eq(e.code())
+                }))
+                .to_owned();
+
+            if let Some(term) = term {
+                let value = Expr::cust_with_values(
+                    r#"lower("customer_contact_methods"."value") LIKE $1"#,
+                    [contains_pattern(&term.text)],
+                );
+
+                query.and_where(match &term.phone_digits {
+                    Some(digits) => value.or(Expr::cust_with_values(
+                        r#"regexp_replace("customer_contact_methods"."value", '[^0-9]', '', 'g') LIKE $1"#,
+                        [contains_pattern(digits)],
+                    )),
+                    None => value,
+                });
+            }
+
+            Expr::exists(query)
+        }
+
+        // A term matches when any of the texts or contact methods contains it, or when the
+        // phone number contains its digits. A term with a country prefix, e.g.
+        // "+853 6123 5678", also matches the phone number without the prefix.
+        fn term_condition(term: &CustomerQueryTerm) -> SimpleExpr {
+            let pattern = contains_pattern(&term.text);
+            let texts = Expr::cust_with_values(
+                r#"(lower("customers"."name") LIKE $1 OR lower("customers"."email") LIKE $1 OR lower("customers"."remark") LIKE $1)"#,
+                [pattern],
+            );
+
+            let texts = texts.or(contact_condition(Some(term), None));
+
+            match &term.phone_digits {
+                Some(digits) => texts.or(Expr::cust_with_values(
+                    format!(
+                        r#"({PHONE_DIGITS} LIKE $1 OR (length({PHONE_DIGITS}) >= 7 AND $2 LIKE '%' || {PHONE_DIGITS}))"#
+                    ),
+                    [contains_pattern(digits), digits.clone()],
+                )),
+                None => texts,
+            }
+        }
+
+        // The exact matches of the whole text come first, then the names starting with it.
+        fn query_rank(query: &CustomerQuery) -> SimpleExpr {
+            let digits = query
+                .terms
+                .iter()
+                .find_map(|e| e.phone_digits.clone())
+                .unwrap_or_default();
+
+            Expr::cust_with_values(
+                format!(
+                    r#"CASE WHEN lower("customers"."name") = $1 OR lower("customers"."email") = $1 OR ($2 <> '' AND {PHONE_DIGITS} = $2) THEN 0 WHEN lower("customers"."name") LIKE $3 THEN 1 ELSE 2 END"#
+                ),
+                [
+                    query.text.clone(),
+                    digits,
+                    format!("{}%", escape_like(&query.text)),
+                ],
+            )
+        }
+
+        let customer_query = keyword.q.as_deref().and_then(CustomerQuery::parse);
+
+        let mut query = Query::select()
             .from(Customers::Table)
             .columns([
                 Customers::Id,
@@ -225,13 +428,44 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
                     .as_ref()
                     .map(|e| format_like_string(Customers::Table, Customers::Remark, e)),
             )
+            .to_owned();
+
+        match keyword.contact.as_deref().and_then(CustomerQuery::parse) {
+            Some(contact_query) => {
+                for term in contact_query.terms.iter() {
+                    query.and_where(contact_condition(Some(term), keyword.contact_kind));
+                }
+            }
+            None => {
+                if let Some(kind) = keyword.contact_kind {
+                    query.and_where(contact_condition(None, Some(kind)));
+                }
+            }
+        }
+
+        if let Some(customer_query) = customer_query {
+            for term in customer_query.terms.iter() {
+                query.and_where(term_condition(term));
+            }
+
+            query
+                .order_by_expr(query_rank(&customer_query), Order::Asc)
+                .order_by((Customers::Table, Customers::Name), Order::Asc);
+        }
+
+        let query = query
+            .order_by((Customers::Table, Customers::Id), Order::Asc)
             .offset(offset)
             .limit(page_size)
             .to_string(PostgresQueryBuilder);
 
-        sqlx::query_as::<_, CustomerJson>(dbg!(&query))
+        let mut customers = sqlx::query_as::<_, CustomerJson>(dbg!(&query))
             .fetch_all(conn.as_mut())
-            .await
+            .await?;
+
+        fill_contact_methods(conn.as_mut(), &mut customers).await?;
+
+        Ok(customers)
     }
 
     #[tracing::instrument(
@@ -244,7 +478,7 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
         email: &Option<ValidEmail>,
         phone: &Option<ValidPhone>,
     ) -> Result<bool, sqlx::Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = Query::select()
             .column(Customers::Id)
@@ -267,4 +501,257 @@ impl CustomerRepo for PostgresCustomerRepoImpl {
             .await
             .map(|row| row.map_or_else(|| false, |e| e.len() > 0))
     }
+
+    #[tracing::instrument(name = "List duplicate customers from database", skip(self))]
+    async fn list_duplicates(
+        &self,
+        customer_id: Option<i64>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<CustomerDuplicateJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let (a, b) = (Alias::new("a"), Alias::new("b"));
+
+        let same_phone =
+            Expr::col((a.clone(), Customers::Phone)).equals((b.clone(), Customers::Phone));
+        let same_email = Expr::cust(r#"lower("a"."email") = lower("b"."email")"#);
+        let similar_name = Expr::cust(
+            r#"(lower("a"."name") = lower("b"."name") OR lower("a"."name") % lower("b"."name"))"#,
+        );
+
+        // The column order must be kept in sync with the `FromRow` implementation of
+        // `CustomerDuplicateJson`.
+        let query = Query::select()
+            .columns(customer_columns("a"))
+            .columns(customer_columns("b"))
+            .expr(same_phone.clone())
+            .expr(same_email.clone())
+            .expr(similar_name.clone())
+            .from_as(Customers::Table, a.clone())
+            .join_as(
+                JoinType::InnerJoin,
+                Customers::Table,
+                b.clone(),
+                Expr::col((a.clone(), Customers::Id)).lt(Expr::col((b.clone(), Customers::Id))),
+            )
+            .and_where(Expr::col((a.clone(), Customers::DeletedAt)).is_null())
+            .and_where(Expr::col((b.clone(), Customers::DeletedAt)).is_null())
+            .cond_where(
+                Cond::any()
+                    .add(same_phone)
+                    .add(same_email)
+                    .add(similar_name),
+            )
+            .and_where_option(customer_id.map(|e| {
+                Expr::col((a.clone(), Customers::Id)).eq(e).or(Expr::col((
+                    b.clone(),
+                    Customers::Id,
+                ))
+                .eq(e))
+            }))
+            .order_by((a.clone(), Customers::Id), Order::Asc)
+            .order_by((b, Customers::Id), Order::Asc)
+            .offset(page * page_size)
+            .limit(page_size)
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        // Raise the threshold of `%` from 0.3, so that only the names which differ by a typo or
+        // the order of the words are similar.
+        let _ = sqlx::query("SET LOCAL pg_trgm.similarity_threshold = 0.6")
+            .execute(&mut *tx)
+            .await?;
+
+        let duplicates = sqlx::query_as::<_, CustomerDuplicateJson>(query.as_str())
+            .fetch_all(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(duplicates)
+    }
+
+    #[tracing::instrument(name = "Merge customers in database", skip(self))]
+    async fn merge(
+        &self,
+        merge: NewCustomerMerge,
+        merged_by: uuid::Uuid,
+    ) -> Result<Option<CustomerMergeJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        // Lock both customers in the order of the ids, so that concurrent merges don't deadlock.
+        let query = Query::select()
+            .columns([Customers::Id, Customers::Remark])
+            .from(Customers::Table)
+            .and_where(
+                Expr::col(Customers::Id).is_in([merge.from_customer_id, merge.into_customer_id]),
+            )
+            .and_where(Expr::col(Customers::DeletedAt).is_null())
+            .order_by(Customers::Id, Order::Asc)
+            .lock(LockType::Update)
+            .to_string(PostgresQueryBuilder);
+
+        let customers = sqlx::query(query.as_str()).fetch_all(&mut *tx).await?;
+
+        if customers.len() != 2 {
+            return Ok(None);
+        }
+
+        let remark_of = |id: i64| {
+            customers
+                .iter()
+                .find(|e| e.get::<i64, _>(0) == id)
+                .and_then(|e| e.get::<Option<String>, _>(1))
+        };
+        let remark = NewCustomerMerge::combine_remarks(
+            remark_of(merge.into_customer_id),
+            remark_of(merge.from_customer_id),
+        );
+
+        let order_items_moved =
+            move_customer_references(&mut tx, OrderItems::Table, OrderItems::CustomerId, &merge)
+                .await?;
+        let orders_moved =
+            move_customer_references(&mut tx, Orders::Table, Orders::CustomerId, &merge).await?;
+        let payments_moved =
+            move_customer_references(&mut tx, Payments::Table, Payments::CustomerId, &merge)
+                .await?;
+
+        // The moved contact methods are not preferred, so that the customer keeps its own one.
+        let query = Query::update()
+            .table(CustomerContactMethods::Table)
+            .values([(CustomerContactMethods::Preferred, false.into())])
+            .and_where(Expr::col(CustomerContactMethods::CustomerId).eq(merge.from_customer_id))
+            .and_where(Expr::col(CustomerContactMethods::Preferred).eq(true))
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        let contact_methods_moved = move_customer_references(
+            &mut tx,
+            CustomerContactMethods::Table,
+            CustomerContactMethods::CustomerId,
+            &merge,
+        )
+        .await?;
+
+        let query = Query::update()
+            .table(Customers::Table)
+            .values([
+                (Customers::Remark, remark.into()),
+                (Customers::UpdatedAt, now.into()),
+            ])
+            .and_where(Expr::col(Customers::Id).eq(merge.into_customer_id))
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        let query = Query::update()
+            .table(Customers::Table)
+            .values([
+                (Customers::DeletedAt, now.into()),
+                (Customers::UpdatedAt, now.into()),
+            ])
+            .and_where(Expr::col(Customers::Id).eq(merge.from_customer_id))
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        // The column order must be kept in sync with the `FromRow` implementation of
+        // `CustomerMergeJson`.
+        let columns = || {
+            [
+                CustomerMerges::Id,
+                CustomerMerges::FromCustomerId,
+                CustomerMerges::IntoCustomerId,
+                CustomerMerges::OrderItemsMoved,
+                CustomerMerges::OrdersMoved,
+                CustomerMerges::PaymentsMoved,
+                CustomerMerges::ContactMethodsMoved,
+                CustomerMerges::MergedBy,
+                CustomerMerges::CreatedAt,
+            ]
+        };
+        let query = Query::insert()
+            .into_table(CustomerMerges::Table)
+            .columns(columns())
+            .values_panic([
+                merge.id.into(),
+                merge.from_customer_id.into(),
+                merge.into_customer_id.into(),
+                order_items_moved.into(),
+                orders_moved.into(),
+                payments_moved.into(),
+                contact_methods_moved.into(),
+                merged_by.to_string().into(),
+                now.into(),
+            ])
+            .returning(Query::returning().columns(columns()))
+            .to_string(PostgresQueryBuilder);
+
+        let record = sqlx::query_as::<_, CustomerMergeJson>(query.as_str())
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(record))
+    }
+
+    #[tracing::instrument(name = "Normalize the phone numbers of customers", skip(self))]
+    async fn normalize_phones(
+        &self,
+        default_country_code: u16,
+    ) -> Result<NormalizePhonesResponse, Error> {
+        let mut conn = self.session.get_session().await?;
+        let mut tx = conn.as_mut().begin().await?;
+
+        // The rows are locked, so that the concurrent calls don't normalize them twice.
+        let query = Query::select()
+            .columns([Customers::Id, Customers::Phone])
+            .from(Customers::Table)
+            .and_where(Expr::col(Customers::Phone).is_not_null())
+            .and_where(Expr::cust(r"phone !~ '^\+[0-9]+$'"))
+            .order_by(Customers::Id, Order::Asc)
+            .lock(LockType::Update)
+            .to_string(PostgresQueryBuilder);
+
+        let rows = sqlx::query(query.as_str()).fetch_all(&mut *tx).await?;
+
+        let mut normalized = 0;
+        let mut unchanged = vec![];
+
+        for row in &rows {
+            let id: i64 = row.get(0);
+            let phone: String = row.get(1);
+
+            let Ok(valid) = ValidPhone::parse(phone.clone(), default_country_code) else {
+                unchanged.push(UnnormalizedPhoneJson {
+                    customer_id: id.to_string(),
+                    phone,
+                });
+                continue;
+            };
+
+            let query = Query::update()
+                .table(Customers::Table)
+                .value(Customers::Phone, valid.0)
+                .and_where(Expr::col(Customers::Id).eq(id))
+                .to_string(PostgresQueryBuilder);
+
+            let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+            normalized += 1;
+        }
+
+        tx.commit().await?;
+
+        Ok(NormalizePhonesResponse {
+            normalized,
+            unchanged,
+        })
+    }
 }
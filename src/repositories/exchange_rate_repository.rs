@@ -0,0 +1,197 @@
+use chrono::{NaiveDate, Utc};
+use sea_query::{Expr, Func, OnConflict, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sqlx::{Connection, Error, Row};
+
+use crate::routes::{ExchangeRateJson, ExchangeRateSearchParameters, NewExchangeRate};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum ExchangeRates {
+    Table,
+    Id,
+    BaseCurrency,
+    QuoteCurrency,
+    Rate,
+    ValidFrom,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Build a select statement of the exchange rates.
+/// The column order must be kept in sync with the `FromRow` implementation of
+/// `ExchangeRateJson`.
+fn select_exchange_rates() -> SelectStatement {
+    Query::select()
+        .columns([
+            ExchangeRates::Id,
+            ExchangeRates::BaseCurrency,
+            ExchangeRates::QuoteCurrency,
+            ExchangeRates::Rate,
+            ExchangeRates::ValidFrom,
+            ExchangeRates::CreatedBy,
+            ExchangeRates::CreatedAt,
+            ExchangeRates::UpdatedAt,
+        ])
+        .from(ExchangeRates::Table)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+pub trait ExchangeRateRepo {
+    /// Save the rates, a rate replaces the existing one of the same currencies and date.
+    /// Return the ids of the saved rates.
+    async fn save(
+        &self,
+        new_rates: Vec<NewExchangeRate>,
+        created_by: uuid::Uuid,
+    ) -> Result<Vec<i64>, Error>;
+
+    async fn list(
+        &self,
+        param: ExchangeRateSearchParameters,
+    ) -> Result<Vec<ExchangeRateJson>, Error>;
+
+    /// The rates which are valid on any day between the dates, i.e. the latest rate of each
+    /// currency pair on `from` and the rates which start after it until `to`.
+    async fn list_valid_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<ExchangeRateJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresExchangeRateRepo {
+    session: PostgresSession,
+}
+
+impl PostgresExchangeRateRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExchangeRateRepo for PostgresExchangeRateRepo {
+    #[tracing::instrument(name = "Save exchange rates into database", skip(self, new_rates))]
+    async fn save(
+        &self,
+        new_rates: Vec<NewExchangeRate>,
+        created_by: uuid::Uuid,
+    ) -> Result<Vec<i64>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let mut tx = conn.as_mut().begin().await?;
+        let mut ids = vec![];
+
+        for new_rate in new_rates {
+            let query = Query::insert()
+                .into_table(ExchangeRates::Table)
+                .columns([
+                    ExchangeRates::Id,
+                    ExchangeRates::BaseCurrency,
+                    ExchangeRates::QuoteCurrency,
+                    ExchangeRates::Rate,
+                    ExchangeRates::ValidFrom,
+                    ExchangeRates::CreatedBy,
+                    ExchangeRates::CreatedAt,
+                ])
+                .values_panic([
+                    new_rate.id.into(),
+                    new_rate.base_currency.into(),
+                    new_rate.quote_currency.into(),
+                    new_rate.rate.0.into(),
+                    new_rate.valid_from.into(),
+                    created_by.to_string().into(),
+                    now.into(),
+                ])
+                .on_conflict(
+                    OnConflict::columns([
+                        ExchangeRates::BaseCurrency,
+                        ExchangeRates::QuoteCurrency,
+                        ExchangeRates::ValidFrom,
+                    ])
+                    .value(ExchangeRates::Rate, new_rate.rate.0)
+                    .value(ExchangeRates::UpdatedAt, now)
+                    .to_owned(),
+                )
+                .returning(Query::returning().column(ExchangeRates::Id))
+                .to_string(PostgresQueryBuilder);
+
+            let res = sqlx::query(query.as_str()).fetch_one(&mut *tx).await?;
+            ids.push(res.get(0));
+        }
+
+        tx.commit().await?;
+
+        Ok(ids)
+    }
+
+    #[tracing::instrument(name = "List exchange rates from database", skip(self))]
+    async fn list(
+        &self,
+        param: ExchangeRateSearchParameters,
+    ) -> Result<Vec<ExchangeRateJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_exchange_rates()
+            .and_where_option(
+                param
+                    .base_currency
+                    .map(|e| Expr::col(ExchangeRates::BaseCurrency).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .quote_currency
+                    .map(|e| Expr::col(ExchangeRates::QuoteCurrency).eq(e)),
+            )
+            .order_by(ExchangeRates::BaseCurrency, Order::Asc)
+            .order_by(ExchangeRates::QuoteCurrency, Order::Asc)
+            .order_by(ExchangeRates::ValidFrom, Order::Desc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, ExchangeRateJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "List the exchange rates valid between dates", skip(self))]
+    async fn list_valid_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<ExchangeRateJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let latest_on_from = Query::select()
+            .columns([ExchangeRates::BaseCurrency, ExchangeRates::QuoteCurrency])
+            .expr(Func::max(Expr::col(ExchangeRates::ValidFrom)))
+            .from(ExchangeRates::Table)
+            .and_where(Expr::col(ExchangeRates::ValidFrom).lte(from))
+            .group_by_columns([ExchangeRates::BaseCurrency, ExchangeRates::QuoteCurrency])
+            .to_owned();
+
+        let query = select_exchange_rates()
+            .and_where(Expr::col(ExchangeRates::ValidFrom).lte(to))
+            .cond_where(
+                Expr::col(ExchangeRates::ValidFrom)
+                    .gt(from)
+                    .or(Expr::tuple([
+                        Expr::col(ExchangeRates::BaseCurrency).into(),
+                        Expr::col(ExchangeRates::QuoteCurrency).into(),
+                        Expr::col(ExchangeRates::ValidFrom).into(),
+                    ])
+                    .in_subquery(latest_on_from)),
+            )
+            .order_by(ExchangeRates::BaseCurrency, Order::Asc)
+            .order_by(ExchangeRates::QuoteCurrency, Order::Asc)
+            .order_by(ExchangeRates::ValidFrom, Order::Desc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, ExchangeRateJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
@@ -1,11 +1,31 @@
+pub use category_repository::*;
 pub use currency_repository::*;
+pub use customer_contact_method_repository::*;
 pub use customer_repository::*;
+pub use exchange_rate_repository::*;
 pub use order_item_repository::*;
+pub use order_item_status_history_repository::*;
+pub use order_repository::*;
+pub use payment_repository::*;
+pub use pricing_rule_repository::*;
+pub use product_price_history_repository::*;
 pub use product_repository::*;
+pub use product_variant_repository::*;
+pub use shipment_repository::*;
 pub use user_repository::*;
 
+mod category_repository;
 mod currency_repository;
+mod customer_contact_method_repository;
 mod customer_repository;
+mod exchange_rate_repository;
 mod order_item_repository;
+mod order_item_status_history_repository;
+mod order_repository;
+mod payment_repository;
+mod pricing_rule_repository;
+mod product_price_history_repository;
 mod product_repository;
+mod product_variant_repository;
+mod shipment_repository;
 mod user_repository;
@@ -1,29 +1,423 @@
-use crate::repositories::{Customers, Products};
+use crate::repositories::currency_repository::Currencies;
+use crate::repositories::{
+    change_product_stock, change_variant_stock, insert_order_item_status_history, Customers,
+    ProductVariants, Products,
+};
 use chrono::Utc;
-use sea_query::{Expr, JoinType, PostgresQueryBuilder, Query};
-use sqlx::Error;
+use sea_query::{
+    Expr, Func, JoinType, LockType, Order, PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
+};
+use sqlx::{Connection, Error, PgConnection, Row};
 
-use crate::routes::{NewOrderItem, OrderItemJson};
+use crate::errors::OrderItemError;
+use crate::routes::{
+    CurrencyTotalJson, DatedAmountsJson, NewOrderItem, NewOrderItemStatusHistory, OrderItemJson,
+    OrderItemSearchParameters, OrderItemSelector, OrderItemStatus, OrderItemStatusChangeJson,
+    UpdateOrderItem, UpdateOrderItemStatuses,
+};
 use crate::utils::PostgresSession;
 
 #[derive(sea_query::Iden)]
 pub(crate) enum OrderItems {
     Table,
     Id,
+    OrderId,
+    ShipmentId,
     CustomerId,
     ProductId,
+    VariantId,
     Quantity,
     Status,
+    UnitPrice,
+    Currency,
     CreatedAt,
     UpdatedAt,
     DeletedAt,
 }
 
+/// Build a select statement which joins the order items with their customers and products.
+/// The column order must be kept in sync with the `FromRow` implementation of `OrderItemJson`.
+pub(crate) fn select_order_items() -> SelectStatement {
+    let order_item_cols = vec![
+        (OrderItems::Table, OrderItems::Id),
+        (OrderItems::Table, OrderItems::Quantity),
+        (OrderItems::Table, OrderItems::Status),
+        (OrderItems::Table, OrderItems::CreatedAt),
+        (OrderItems::Table, OrderItems::UpdatedAt),
+        (OrderItems::Table, OrderItems::DeletedAt),
+        (OrderItems::Table, OrderItems::CustomerId),
+        (OrderItems::Table, OrderItems::ProductId),
+    ];
+
+    let customer_cols = vec![
+        (Customers::Table, Customers::Name),
+        (Customers::Table, Customers::CreatedAt),
+    ];
+
+    let product_cols = vec![
+        (Products::Table, Products::Name),
+        (Products::Table, Products::Currency),
+        (Products::Table, Products::Price),
+        (Products::Table, Products::CreatedAt),
+    ];
+
+    Query::select()
+        .columns(order_item_cols)
+        .columns(customer_cols)
+        .columns(product_cols)
+        .column((OrderItems::Table, OrderItems::OrderId))
+        .column((OrderItems::Table, OrderItems::ShipmentId))
+        .column((OrderItems::Table, OrderItems::UnitPrice))
+        .column((OrderItems::Table, OrderItems::Currency))
+        .column((Products::Table, Products::OnHand))
+        .column((Products::Table, Products::Reserved))
+        .column((OrderItems::Table, OrderItems::VariantId))
+        .from(OrderItems::Table)
+        .join(
+            JoinType::InnerJoin,
+            Products::Table,
+            Expr::col((OrderItems::Table, OrderItems::ProductId))
+                .equals((Products::Table, Products::Id)),
+        )
+        .join(
+            JoinType::InnerJoin,
+            Customers::Table,
+            Expr::col((OrderItems::Table, OrderItems::CustomerId))
+                .equals((Customers::Table, Customers::Id)),
+        )
+        .to_owned()
+}
+
+/// Take a column of the product as a snapshot when inserting an order item.
+fn product_column(product_id: i64, column: Products) -> SimpleExpr {
+    SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            Query::select()
+                .column(column)
+                .from(Products::Table)
+                .and_where(Expr::col(Products::Id).eq(product_id))
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    )
+}
+
+/// Take the price of the variant as a snapshot, fall back to the price of the product when the
+/// variant doesn't override it.
+fn variant_price(variant_id: i64, product_id: i64) -> SimpleExpr {
+    let price = SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            Query::select()
+                .column(ProductVariants::Price)
+                .from(ProductVariants::Table)
+                .and_where(Expr::col(ProductVariants::Id).eq(variant_id))
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    );
+
+    Func::coalesce([price, product_column(product_id, Products::Price)]).into()
+}
+
+/// The ordered, arrived and picked-up values of the order items in a group.
+fn order_item_values() -> [SimpleExpr; 3] {
+    use OrderItemStatus::*;
+
+    fn sum_of(statuses: &[OrderItemStatus]) -> SimpleExpr {
+        let amount = Expr::col((OrderItems::Table, OrderItems::UnitPrice))
+            .mul(Expr::col((OrderItems::Table, OrderItems::Quantity)));
+        let is_matched = Expr::col((OrderItems::Table, OrderItems::Status))
+            .is_in(statuses.iter().map(|e| e.code()));
+
+        Func::sum(Expr::case(is_matched, amount).finally(0)).into()
+    }
+
+    [
+        sum_of(&[Requested, PurchasedInJapan, InTransit, Arrived, PickedUp]),
+        sum_of(&[Arrived]),
+        sum_of(&[PickedUp]),
+    ]
+}
+
+/// Build a select statement which sums the non-deleted order items matched by the condition
+/// by their currencies.
+/// The column order must be kept in sync with the `FromRow` implementation of `CurrencyTotalJson`.
+pub(crate) fn sum_order_items_by_currency(condition: SimpleExpr) -> SelectStatement {
+    Query::select()
+        .column((OrderItems::Table, OrderItems::Currency))
+        .column((Currencies::Table, Currencies::Code))
+        .exprs(order_item_values())
+        .from(OrderItems::Table)
+        .join(
+            JoinType::LeftJoin,
+            Currencies::Table,
+            Expr::col((OrderItems::Table, OrderItems::Currency))
+                .equals((Currencies::Table, Currencies::Id)),
+        )
+        .and_where(condition)
+        .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+        .group_by_col((OrderItems::Table, OrderItems::Currency))
+        .group_by_col((Currencies::Table, Currencies::Code))
+        .order_by((OrderItems::Table, OrderItems::Currency), Order::Asc)
+        .to_owned()
+}
+
+/// The day of the timestamp in the time zone, e.g. "Asia/Macau".
+pub(crate) fn local_date(timestamp: SimpleExpr, time_zone: &str) -> SimpleExpr {
+    Expr::cust_with_exprs("($1 at time zone $2)::date", [timestamp, time_zone.into()])
+}
+
+/// Build a select statement which sums the non-deleted order items matched by the condition
+/// by their currencies and the days they are created in the time zone.
+/// The column order must be kept in sync with the `FromRow` implementation of `DatedAmountsJson`.
+fn sum_order_items_by_currency_and_date(condition: SimpleExpr, time_zone: &str) -> SelectStatement {
+    let date = local_date(
+        Expr::col((OrderItems::Table, OrderItems::CreatedAt)).into(),
+        time_zone,
+    );
+
+    Query::select()
+        .column((OrderItems::Table, OrderItems::Currency))
+        .expr(date.clone())
+        .exprs(order_item_values())
+        .from(OrderItems::Table)
+        .and_where(condition)
+        .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+        .group_by_col((OrderItems::Table, OrderItems::Currency))
+        .add_group_by([date.clone()])
+        .order_by((OrderItems::Table, OrderItems::Currency), Order::Asc)
+        .order_by_expr(date, Order::Asc)
+        .to_owned()
+}
+
+/// The stock of the product taken by an order item as `(on_hand, reserved)`. The order item
+/// reserves the stock until it's picked up, which consumes the on-hand stock instead, so the
+/// stock has to be received before the order item can be picked up.
+fn stock_taken_by(status: OrderItemStatus, quantity: i32) -> (i32, i32) {
+    use OrderItemStatus::*;
+
+    match status {
+        Requested | PurchasedInJapan | InTransit | Arrived => (0, quantity),
+        PickedUp => (-quantity, 0),
+        Cancelled | Refunded => (0, 0),
+    }
+}
+
+/// Move the stock of the product, or of its variant if any, when an order item changes from the
+/// previous state to the next one, `None` means the order item doesn't exist, i.e. it's created
+/// or deleted. Return `false` when there isn't enough stock and nothing is moved.
+async fn move_order_item_stock(
+    conn: &mut PgConnection,
+    (product_id, variant_id): (i64, Option<i64>),
+    previous: Option<(OrderItemStatus, i32)>,
+    next: Option<(OrderItemStatus, i32)>,
+    reject_oversell: bool,
+) -> Result<bool, Error> {
+    let (previous_on_hand, previous_reserved) = previous.map_or((0, 0), |(status, quantity)| {
+        stock_taken_by(status, quantity)
+    });
+    let (next_on_hand, next_reserved) = next.map_or((0, 0), |(status, quantity)| {
+        stock_taken_by(status, quantity)
+    });
+
+    let on_hand_delta = next_on_hand - previous_on_hand;
+    let reserved_delta = next_reserved - previous_reserved;
+    let reject_oversell = reject_oversell && reserved_delta > 0;
+
+    match variant_id {
+        Some(variant_id) => {
+            change_variant_stock(
+                conn,
+                variant_id,
+                on_hand_delta,
+                reserved_delta,
+                reject_oversell,
+            )
+            .await
+        }
+        None => {
+            change_product_stock(
+                conn,
+                product_id,
+                on_hand_delta,
+                reserved_delta,
+                reject_oversell,
+            )
+            .await
+        }
+    }
+}
+
+/// Lock the order item and return its product and variant ids, status, quantity and whether
+/// it's deleted.
+async fn lock_order_item(
+    conn: &mut PgConnection,
+    id: i64,
+) -> Result<Option<((i64, Option<i64>), OrderItemStatus, i32, bool)>, Error> {
+    let query = Query::select()
+        .columns([
+            OrderItems::ProductId,
+            OrderItems::Status,
+            OrderItems::Quantity,
+        ])
+        .expr(Expr::col(OrderItems::DeletedAt).is_not_null())
+        .column(OrderItems::VariantId)
+        .from(OrderItems::Table)
+        .and_where(Expr::col((OrderItems::Table, OrderItems::Id)).eq(id))
+        .lock(LockType::Update)
+        .to_string(PostgresQueryBuilder);
+
+    let row = sqlx::query(query.as_str())
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    row.map(|row| {
+        let product_id: i64 = row.get(0);
+        let status: i16 = row.get(1);
+        let status = OrderItemStatus::try_from(status).map_err(|e| Error::Decode(e.into()))?;
+        let quantity: i16 = row.get(2);
+        let is_deleted: bool = row.get(3);
+        let variant_id: Option<i64> = row.get(4);
+        Ok((
+            (product_id, variant_id),
+            status,
+            quantity as i32,
+            is_deleted,
+        ))
+    })
+    .transpose()
+}
+
+/// Move every order item matched by the condition to the next status with the given
+/// connection, so that the caller decides the transaction boundary.
+/// The order items which can't reach the next status are reported and left untouched.
+pub(crate) async fn change_order_item_statuses(
+    conn: &mut PgConnection,
+    condition: SimpleExpr,
+    next_status: OrderItemStatus,
+    changed_by: uuid::Uuid,
+) -> Result<Vec<OrderItemStatusChangeJson>, Error> {
+    let query = Query::select()
+        .columns([
+            OrderItems::Id,
+            OrderItems::Status,
+            OrderItems::ProductId,
+            OrderItems::Quantity,
+            OrderItems::VariantId,
+        ])
+        .from(OrderItems::Table)
+        .and_where(condition)
+        .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+        .order_by((OrderItems::Table, OrderItems::Id), Order::Asc)
+        .lock(LockType::Update)
+        .to_string(PostgresQueryBuilder);
+
+    let rows = sqlx::query(query.as_str()).fetch_all(&mut *conn).await?;
+
+    let mut results = vec![];
+    let mut changed_ids = vec![];
+
+    for row in rows {
+        let id: i64 = row.get(0);
+        let previous_status: i16 = row.get(1);
+        let previous_status =
+            OrderItemStatus::try_from(previous_status).map_err(|e| Error::Decode(e.into()))?;
+        let product_id: i64 = row.get(2);
+        let quantity: i16 = row.get(3);
+        let quantity = quantity as i32;
+        let variant_id: Option<i64> = row.get(4);
+
+        let error_message = if !previous_status.can_transition_to(next_status) {
+            Some(OrderItemError::InvalidStatusTransition(previous_status, next_status).to_string())
+        } else if !move_order_item_stock(
+            &mut *conn,
+            (product_id, variant_id),
+            Some((previous_status, quantity)),
+            Some((next_status, quantity)),
+            false,
+        )
+        .await?
+        {
+            Some(OrderItemError::OutOfStock.to_string())
+        } else {
+            let history =
+                NewOrderItemStatusHistory::new(id, Some(previous_status), next_status, changed_by);
+            insert_order_item_status_history(&mut *conn, history).await?;
+            changed_ids.push(id);
+            None
+        };
+
+        results.push(OrderItemStatusChangeJson {
+            id: id.to_string(),
+            success: error_message.is_none(),
+            previous_status: Some(previous_status),
+            error_message,
+        });
+    }
+
+    if !changed_ids.is_empty() {
+        let query = Query::update()
+            .table(OrderItems::Table)
+            .values([
+                (OrderItems::Status, next_status.code().into()),
+                (OrderItems::UpdatedAt, Utc::now().into()),
+            ])
+            .and_where(Expr::col((OrderItems::Table, OrderItems::Id)).is_in(changed_ids))
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *conn).await?;
+    }
+
+    Ok(results)
+}
+
 #[async_trait::async_trait]
 pub trait OrderItemRepo {
     async fn get(&self, id: i64) -> Result<Option<OrderItemJson>, Error>;
 
-    async fn create(&self, new_order_item: NewOrderItem) -> Result<i64, Error>;
+    /// Return `OutOfStock` when the order item refuses to oversell and the stock is taken, or
+    /// when it's picked up without enough on-hand stock.
+    async fn create(
+        &self,
+        new_order_item: NewOrderItem,
+        created_by: uuid::Uuid,
+    ) -> Result<Result<i64, OrderItemError>, Error>;
+
+    /// Check the status transition against the locked order item, so that concurrent updates
+    /// can't make a transition which the state machine forbids. Return `OutOfStock` when it's
+    /// picked up without enough on-hand stock.
+    async fn update(
+        &self,
+        update_order_item: UpdateOrderItem,
+        updated_by: uuid::Uuid,
+    ) -> Result<Result<(), OrderItemError>, Error>;
+
+    async fn update_statuses(
+        &self,
+        update_order_item_statuses: UpdateOrderItemStatuses,
+        updated_by: uuid::Uuid,
+    ) -> Result<Vec<OrderItemStatusChangeJson>, Error>;
+
+    async fn delete(&self, id: i64) -> Result<(), Error>;
+
+    async fn list(
+        &self,
+        param: OrderItemSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<OrderItemJson>, Error>;
+
+    async fn totals_by_currency(&self, customer_id: i64) -> Result<Vec<CurrencyTotalJson>, Error>;
+
+    /// Sum the order items of a customer by currency and date in the time zone, so that they can
+    /// be converted with the rates valid on the dates.
+    async fn dated_totals(
+        &self,
+        customer_id: i64,
+        time_zone: &str,
+    ) -> Result<Vec<DatedAmountsJson>, Error>;
 }
 
 #[derive(Clone, Debug)]
@@ -41,48 +435,9 @@ impl PostgresOrderItemRepo {
 impl OrderItemRepo for PostgresOrderItemRepo {
     #[tracing::instrument(name = "Get the order item from the database", skip(self))]
     async fn get(&self, id: i64) -> Result<Option<OrderItemJson>, Error> {
-        let mut conn = self.session.get_session().await;
-
-        let order_item_cols = vec![
-            (OrderItems::Table, OrderItems::Id),
-            (OrderItems::Table, OrderItems::Quantity),
-            (OrderItems::Table, OrderItems::Status),
-            (OrderItems::Table, OrderItems::CreatedAt),
-            (OrderItems::Table, OrderItems::UpdatedAt),
-            (OrderItems::Table, OrderItems::DeletedAt),
-            (OrderItems::Table, OrderItems::CustomerId),
-            (OrderItems::Table, OrderItems::ProductId),
-        ];
-
-        let customer_cols = vec![
-            (Customers::Table, Customers::Name),
-            (Customers::Table, Customers::CreatedAt),
-        ];
-
-        let product_cols = vec![
-            (Products::Table, Products::Name),
-            (Products::Table, Products::Currency),
-            (Products::Table, Products::Price),
-            (Products::Table, Products::CreatedAt),
-        ];
-
-        let query = Query::select()
-            .columns(order_item_cols)
-            .columns(customer_cols)
-            .columns(product_cols)
-            .from(OrderItems::Table)
-            .join(
-                JoinType::InnerJoin,
-                Products::Table,
-                Expr::col((OrderItems::Table, OrderItems::ProductId))
-                    .equals((Products::Table, Products::Id)),
-            )
-            .join(
-                JoinType::InnerJoin,
-                Customers::Table,
-                Expr::col((OrderItems::Table, OrderItems::CustomerId))
-                    .equals((Customers::Table, Customers::Id)),
-            )
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_order_items()
             .and_where(Expr::col((OrderItems::Table, OrderItems::Id)).eq(id))
             .to_string(PostgresQueryBuilder);
 
@@ -92,34 +447,346 @@ impl OrderItemRepo for PostgresOrderItemRepo {
     }
 
     #[tracing::instrument(name = "Save a new order item into database", skip(self))]
-    async fn create(&self, new_order_item: NewOrderItem) -> Result<i64, Error> {
-        use sqlx::Row;
-
-        let mut conn = self.session.get_session().await;
+    async fn create(
+        &self,
+        new_order_item: NewOrderItem,
+        created_by: uuid::Uuid,
+    ) -> Result<Result<i64, OrderItemError>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let status = new_order_item.status;
+        let product_id = new_order_item.product_id.0;
+        let variant_id = new_order_item.variant_id;
+        let quantity = new_order_item.quantity.0 as i32;
+        let reject_oversell = new_order_item.reject_oversell;
 
         let query = Query::insert()
             .into_table(OrderItems::Table)
             .columns(vec![
                 OrderItems::Id,
+                OrderItems::OrderId,
                 OrderItems::CustomerId,
                 OrderItems::ProductId,
                 OrderItems::Quantity,
                 OrderItems::Status,
+                OrderItems::UnitPrice,
+                OrderItems::Currency,
                 OrderItems::CreatedAt,
+                OrderItems::VariantId,
             ])
             .values_panic(vec![
                 new_order_item.id.into(),
+                new_order_item.order_id.into(),
                 new_order_item.customer_id.0.into(),
                 new_order_item.product_id.0.into(),
                 new_order_item.quantity.0.into(),
-                new_order_item.status.0.into(),
+                new_order_item.status.code().into(),
+                match (new_order_item.unit_price, variant_id) {
+                    (Some(unit_price), _) => unit_price.0.amount.into(),
+                    (None, Some(variant_id)) => variant_price(variant_id, product_id),
+                    (None, None) => product_column(product_id, Products::Price),
+                },
+                product_column(product_id, Products::Currency),
                 Utc::now().into(),
+                variant_id.into(),
             ])
             .returning(Query::returning().column(OrderItems::Id))
             .to_string(PostgresQueryBuilder);
 
-        let res = sqlx::query(dbg!(&query)).fetch_one(conn.as_mut()).await?;
+        let mut tx = conn.as_mut().begin().await?;
+
+        let res = sqlx::query(dbg!(&query)).fetch_one(&mut *tx).await?;
+        let id: i64 = res.get(0);
 
-        Ok(res.get(0))
+        let history = NewOrderItemStatusHistory::new(id, None, status, created_by);
+        insert_order_item_status_history(&mut tx, history).await?;
+
+        let is_reserved = move_order_item_stock(
+            &mut tx,
+            (product_id, variant_id),
+            None,
+            Some((status, quantity)),
+            reject_oversell,
+        )
+        .await?;
+
+        if !is_reserved {
+            return Ok(Err(OrderItemError::OutOfStock));
+        }
+
+        tx.commit().await?;
+
+        Ok(Ok(id))
+    }
+
+    #[tracing::instrument(name = "Update an order item in database", skip(self))]
+    async fn update(
+        &self,
+        update_order_item: UpdateOrderItem,
+        updated_by: uuid::Uuid,
+    ) -> Result<Result<(), OrderItemError>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let id = update_order_item.id;
+        let status = update_order_item.status;
+        let quantity = update_order_item.quantity.as_ref().map(|e| e.0 as i32);
+
+        let query = {
+            let mut update_data = vec![];
+
+            if let Some(quantity) = update_order_item.quantity {
+                update_data.push((OrderItems::Quantity, quantity.0.into()));
+            }
+
+            if let Some(unit_price) = update_order_item.unit_price {
+                update_data.push((OrderItems::UnitPrice, unit_price.0.amount.into()));
+            }
+
+            if let Some(status) = update_order_item.status {
+                update_data.push((OrderItems::Status, status.code().into()));
+            }
+
+            update_data.push((OrderItems::UpdatedAt, Utc::now().into()));
+
+            Query::update()
+                .table(OrderItems::Table)
+                .values(update_data)
+                .and_where(Expr::col((OrderItems::Table, OrderItems::Id)).eq(update_order_item.id))
+                .to_string(PostgresQueryBuilder)
+        };
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let Some((stock_holder, previous_status, previous_quantity, false)) =
+            lock_order_item(&mut tx, id).await?
+        else {
+            return Ok(Err(OrderItemError::OrderItemNotFound));
+        };
+
+        if let Some(status) = status {
+            if !previous_status.can_transition_to(status) {
+                return Ok(Err(OrderItemError::InvalidStatusTransition(
+                    previous_status,
+                    status,
+                )));
+            }
+        }
+
+        let next_status = status.unwrap_or(previous_status);
+        let next_quantity = quantity.unwrap_or(previous_quantity);
+
+        if previous_status != next_status {
+            let history =
+                NewOrderItemStatusHistory::new(id, Some(previous_status), next_status, updated_by);
+            insert_order_item_status_history(&mut tx, history).await?;
+        }
+
+        let is_moved = move_order_item_stock(
+            &mut tx,
+            stock_holder,
+            Some((previous_status, previous_quantity)),
+            Some((next_status, next_quantity)),
+            false,
+        )
+        .await?;
+
+        if !is_moved {
+            return Ok(Err(OrderItemError::OutOfStock));
+        }
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(Ok(()))
+    }
+
+    #[tracing::instrument(name = "Update the statuses of order items in database", skip(self))]
+    async fn update_statuses(
+        &self,
+        update_order_item_statuses: UpdateOrderItemStatuses,
+        updated_by: uuid::Uuid,
+    ) -> Result<Vec<OrderItemStatusChangeJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let condition = match &update_order_item_statuses.selector {
+            OrderItemSelector::Ids(ids) => {
+                Expr::col((OrderItems::Table, OrderItems::Id)).is_in(ids.clone())
+            }
+            OrderItemSelector::Filter {
+                product_id,
+                current_status,
+            } => {
+                let mut condition = Expr::value(true);
+                if let Some(product_id) = product_id {
+                    condition = condition
+                        .and(Expr::col((OrderItems::Table, OrderItems::ProductId)).eq(*product_id));
+                }
+                if let Some(current_status) = current_status {
+                    condition = condition.and(
+                        Expr::col((OrderItems::Table, OrderItems::Status))
+                            .eq(current_status.code()),
+                    );
+                }
+                condition
+            }
+        };
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let mut results = change_order_item_statuses(
+            &mut tx,
+            condition,
+            update_order_item_statuses.status,
+            updated_by,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        // Report the ids which don't match any order item as well.
+        if let OrderItemSelector::Ids(ids) = update_order_item_statuses.selector {
+            for id in ids {
+                let id = id.to_string();
+                if results.iter().all(|e| e.id != id) {
+                    results.push(OrderItemStatusChangeJson {
+                        id,
+                        success: false,
+                        previous_status: None,
+                        error_message: Some(OrderItemError::OrderItemNotFound.to_string()),
+                    });
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    #[tracing::instrument(name = "Delete an order item from database", skip(self))]
+    async fn delete(&self, id: i64) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::update()
+            .table(OrderItems::Table)
+            .values([(OrderItems::DeletedAt, Utc::now().into())])
+            .and_where(Expr::col((OrderItems::Table, OrderItems::Id)).eq(id))
+            .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        if let Some((stock_holder, status, quantity, false)) = lock_order_item(&mut tx, id).await? {
+            move_order_item_stock(&mut tx, stock_holder, Some((status, quantity)), None, false)
+                .await?;
+        }
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "List order items from database", skip(self))]
+    async fn list(
+        &self,
+        param: OrderItemSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<OrderItemJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let offset = page * page_size;
+
+        let query = select_order_items()
+            .and_where_option(
+                param
+                    .id
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::Id)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .order_id
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::OrderId)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .shipment_id
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::ShipmentId)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .customer_id
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::CustomerId)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .product_id
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::ProductId)).eq(e)),
+            )
+            .and_where_option(param.statuses.filter(|e| !e.is_empty()).map(|e| {
+                Expr::col((OrderItems::Table, OrderItems::Status))
+                    .is_in(e.iter().map(|status| status.code()))
+            }))
+            .and_where_option(
+                param
+                    .created_from
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::CreatedAt)).gte(e)),
+            )
+            .and_where_option(
+                param
+                    .created_to
+                    .map(|e| Expr::col((OrderItems::Table, OrderItems::CreatedAt)).lt(e)),
+            )
+            .and_where({
+                let col = Expr::col((OrderItems::Table, OrderItems::DeletedAt));
+                if param.deleted.unwrap_or(false) {
+                    col.is_not_null()
+                } else {
+                    col.is_null()
+                }
+            })
+            .order_by((OrderItems::Table, OrderItems::CreatedAt), Order::Desc)
+            .order_by((OrderItems::Table, OrderItems::Id), Order::Desc)
+            .offset(offset)
+            .limit(page_size)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, OrderItemJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Sum the order items of a customer by currency", skip(self))]
+    async fn totals_by_currency(&self, customer_id: i64) -> Result<Vec<CurrencyTotalJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = sum_order_items_by_currency(
+            Expr::col((OrderItems::Table, OrderItems::CustomerId)).eq(customer_id),
+        )
+        .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, CurrencyTotalJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Sum the order items of a customer by currency and date",
+        skip(self)
+    )]
+    async fn dated_totals(
+        &self,
+        customer_id: i64,
+        time_zone: &str,
+    ) -> Result<Vec<DatedAmountsJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = sum_order_items_by_currency_and_date(
+            Expr::col((OrderItems::Table, OrderItems::CustomerId)).eq(customer_id),
+            time_zone,
+        )
+        .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, DatedAmountsJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
     }
 }
@@ -0,0 +1,138 @@
+use chrono::Utc;
+use sea_query::{Expr, JoinType, Order, PostgresQueryBuilder, Query};
+use sqlx::{Error, PgConnection};
+
+use crate::repositories::Users;
+use crate::routes::{NewOrderItemStatusHistory, OrderItemStatusHistoryJson};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum OrderItemStatusHistories {
+    Table,
+    Id,
+    OrderItemId,
+    PreviousStatus,
+    NextStatus,
+    ChangedBy,
+    CreatedAt,
+}
+
+/// Insert a status history record with the given connection, so that it can share the
+/// transaction which changes the status of the order item.
+pub(crate) async fn insert_order_item_status_history(
+    conn: &mut PgConnection,
+    history: NewOrderItemStatusHistory,
+) -> Result<(), Error> {
+    let query = Query::insert()
+        .into_table(OrderItemStatusHistories::Table)
+        .columns([
+            OrderItemStatusHistories::Id,
+            OrderItemStatusHistories::OrderItemId,
+            OrderItemStatusHistories::PreviousStatus,
+            OrderItemStatusHistories::NextStatus,
+            OrderItemStatusHistories::ChangedBy,
+            OrderItemStatusHistories::CreatedAt,
+        ])
+        .values_panic([
+            history.id.into(),
+            history.order_item_id.into(),
+            history.previous_status.map(|e| e.code()).into(),
+            history.next_status.code().into(),
+            history.changed_by.to_string().into(),
+            Utc::now().into(),
+        ])
+        .to_string(PostgresQueryBuilder);
+
+    let _ = sqlx::query(query.as_str()).execute(conn).await?;
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+pub trait OrderItemStatusHistoryRepo {
+    async fn list(&self, order_item_id: i64) -> Result<Vec<OrderItemStatusHistoryJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresOrderItemStatusHistoryRepo {
+    session: PostgresSession,
+}
+
+impl PostgresOrderItemStatusHistoryRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl OrderItemStatusHistoryRepo for PostgresOrderItemStatusHistoryRepo {
+    #[tracing::instrument(name = "List the status histories of an order item", skip(self))]
+    async fn list(&self, order_item_id: i64) -> Result<Vec<OrderItemStatusHistoryJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::select()
+            .columns([
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::Id,
+                ),
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::OrderItemId,
+                ),
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::PreviousStatus,
+                ),
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::NextStatus,
+                ),
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::ChangedBy,
+                ),
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::CreatedAt,
+                ),
+            ])
+            .column((Users::Table, Users::Username))
+            .from(OrderItemStatusHistories::Table)
+            .join(
+                JoinType::LeftJoin,
+                Users::Table,
+                Expr::col((
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::ChangedBy,
+                ))
+                .equals((Users::Table, Users::Id)),
+            )
+            .and_where(
+                Expr::col((
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::OrderItemId,
+                ))
+                .eq(order_item_id),
+            )
+            .order_by(
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::CreatedAt,
+                ),
+                Order::Asc,
+            )
+            .order_by(
+                (
+                    OrderItemStatusHistories::Table,
+                    OrderItemStatusHistories::Id,
+                ),
+                Order::Asc,
+            )
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, OrderItemStatusHistoryJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::Utc;
+use sea_query::{Expr, JoinType, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sqlx::{Connection, Error, FromRow, PgConnection, Row};
+
+use crate::errors::OrderError;
+use crate::repositories::{
+    select_order_items, sum_order_items_by_currency, Customers, OrderItems, Users,
+};
+use crate::routes::{
+    CurrencyTotalJson, NewOrder, OrderItemJson, OrderItemStatus, OrderJson, OrderSearchParameters,
+};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum Orders {
+    Table,
+    Id,
+    CustomerId,
+    CreatedBy,
+    Note,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
+
+/// Build a select statement which joins the orders with their customers and creators.
+/// The column order must be kept in sync with the `FromRow` implementation of `OrderJson`.
+fn select_orders() -> SelectStatement {
+    Query::select()
+        .columns([
+            (Orders::Table, Orders::Id),
+            (Orders::Table, Orders::CustomerId),
+            (Orders::Table, Orders::CreatedBy),
+            (Orders::Table, Orders::Note),
+            (Orders::Table, Orders::CreatedAt),
+            (Orders::Table, Orders::UpdatedAt),
+            (Orders::Table, Orders::DeletedAt),
+        ])
+        .columns([
+            (Customers::Table, Customers::Name),
+            (Customers::Table, Customers::CreatedAt),
+        ])
+        .column((Users::Table, Users::Username))
+        .from(Orders::Table)
+        .join(
+            JoinType::InnerJoin,
+            Customers::Table,
+            Expr::col((Orders::Table, Orders::CustomerId))
+                .equals((Customers::Table, Customers::Id)),
+        )
+        .join(
+            JoinType::LeftJoin,
+            Users::Table,
+            Expr::col((Orders::Table, Orders::CreatedBy)).equals((Users::Table, Users::Id)),
+        )
+        .to_owned()
+}
+
+/// Fill the non-deleted order items, the totals and the derived status of the orders.
+async fn fill_order_items(conn: &mut PgConnection, orders: &mut [OrderJson]) -> Result<(), Error> {
+    if orders.is_empty() {
+        return Ok(());
+    }
+
+    let order_ids = orders
+        .iter()
+        .map(|e| e.id.parse::<i64>().map_err(|e| Error::Decode(e.into())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let query = select_order_items()
+        .and_where(Expr::col((OrderItems::Table, OrderItems::OrderId)).is_in(order_ids.clone()))
+        .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+        .order_by((OrderItems::Table, OrderItems::CreatedAt), Order::Asc)
+        .order_by((OrderItems::Table, OrderItems::Id), Order::Asc)
+        .to_string(PostgresQueryBuilder);
+
+    let mut order_items = sqlx::query_as::<_, OrderItemJson>(query.as_str())
+        .fetch_all(&mut *conn)
+        .await?;
+
+    // The order id comes after the columns of `CurrencyTotalJson`.
+    let query = sum_order_items_by_currency(
+        Expr::col((OrderItems::Table, OrderItems::OrderId)).is_in(order_ids.clone()),
+    )
+    .column((OrderItems::Table, OrderItems::OrderId))
+    .group_by_col((OrderItems::Table, OrderItems::OrderId))
+    .to_string(PostgresQueryBuilder);
+
+    let mut totals = sqlx::query(query.as_str())
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(|row| Ok((row.try_get::<i64, _>(5)?, CurrencyTotalJson::from_row(row)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for (order, order_id) in orders.iter_mut().zip(order_ids) {
+        let (order_totals, rest): (Vec<_>, Vec<_>) =
+            totals.into_iter().partition(|(id, _)| *id == order_id);
+        totals = rest;
+        order.totals = order_totals.into_iter().map(|(_, e)| e).collect();
+
+        let (items, rest) = order_items
+            .into_iter()
+            .partition(|e| e.order_id.as_ref() == Some(&order.id));
+        order_items = rest;
+        order.order_items = items;
+        order.status = OrderItemStatus::aggregate(order.order_items.iter().map(|e| e.status));
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+pub trait OrderRepo {
+    async fn get(&self, id: i64) -> Result<Option<OrderJson>, Error>;
+
+    /// Return `OrderItemNotAttachable` when an order item is attached or deleted by others
+    /// meanwhile.
+    async fn create(
+        &self,
+        new_order: NewOrder,
+        created_by: uuid::Uuid,
+    ) -> Result<Result<i64, OrderError>, Error>;
+
+    async fn list(
+        &self,
+        param: OrderSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<OrderJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresOrderRepo {
+    session: PostgresSession,
+}
+
+impl PostgresOrderRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl OrderRepo for PostgresOrderRepo {
+    #[tracing::instrument(name = "Get the order from the database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<OrderJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_orders()
+            .and_where(Expr::col((Orders::Table, Orders::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        let order = sqlx::query_as::<_, OrderJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await?;
+
+        match order {
+            None => Ok(None),
+            Some(order) => {
+                let mut orders = [order];
+                fill_order_items(conn.as_mut(), &mut orders).await?;
+                let [order] = orders;
+                Ok(Some(order))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Save a new order into database", skip(self))]
+    async fn create(
+        &self,
+        new_order: NewOrder,
+        created_by: uuid::Uuid,
+    ) -> Result<Result<i64, OrderError>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let query = Query::insert()
+            .into_table(Orders::Table)
+            .columns([
+                Orders::Id,
+                Orders::CustomerId,
+                Orders::CreatedBy,
+                Orders::Note,
+                Orders::CreatedAt,
+            ])
+            .values_panic([
+                new_order.id.into(),
+                new_order.customer_id.0.into(),
+                created_by.to_string().into(),
+                new_order.note.into(),
+                now.into(),
+            ])
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        if !new_order.order_item_ids.is_empty() {
+            // Only the unattached order items of the same customer can join the order.
+            let query = Query::update()
+                .table(OrderItems::Table)
+                .values([
+                    (OrderItems::OrderId, new_order.id.into()),
+                    (OrderItems::UpdatedAt, now.into()),
+                ])
+                .and_where(Expr::col(OrderItems::Id).is_in(new_order.order_item_ids.clone()))
+                .and_where(Expr::col(OrderItems::CustomerId).eq(new_order.customer_id.0))
+                .and_where(Expr::col(OrderItems::OrderId).is_null())
+                .and_where(Expr::col(OrderItems::DeletedAt).is_null())
+                .returning(Query::returning().column(OrderItems::Id))
+                .to_string(PostgresQueryBuilder);
+
+            let attached_ids = sqlx::query_scalar::<_, i64>(query.as_str())
+                .fetch_all(&mut *tx)
+                .await?;
+
+            if let Some(id) = new_order
+                .order_item_ids
+                .iter()
+                .find(|e| !attached_ids.contains(e))
+            {
+                return Ok(Err(OrderError::OrderItemNotAttachable(*id)));
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(Ok(new_order.id))
+    }
+
+    #[tracing::instrument(name = "List orders from database", skip(self))]
+    async fn list(
+        &self,
+        param: OrderSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<OrderJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let offset = page * page_size;
+
+        let query = select_orders()
+            .and_where_option(
+                param
+                    .customer_id
+                    .map(|e| Expr::col((Orders::Table, Orders::CustomerId)).eq(e)),
+            )
+            .and_where_option(param.deleted.map(|deleted| {
+                let col = Expr::col((Orders::Table, Orders::DeletedAt));
+                if deleted {
+                    col.is_not_null()
+                } else {
+                    col.is_null()
+                }
+            }))
+            .order_by((Orders::Table, Orders::CreatedAt), Order::Desc)
+            .order_by((Orders::Table, Orders::Id), Order::Desc)
+            .offset(offset)
+            .limit(page_size)
+            .to_string(PostgresQueryBuilder);
+
+        let mut orders = sqlx::query_as::<_, OrderJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await?;
+
+        fill_order_items(conn.as_mut(), &mut orders).await?;
+
+        Ok(orders)
+    }
+}
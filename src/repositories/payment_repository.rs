@@ -0,0 +1,356 @@
+use chrono::Utc;
+use sea_query::{
+    Alias, Expr, Func, JoinType, Order, PostgresQueryBuilder, Query, SelectStatement, UnionType,
+};
+use sqlx::{Connection, Error};
+
+use crate::repositories::currency_repository::Currencies;
+use crate::repositories::{local_date, OrderItems, Users};
+use crate::routes::{
+    BalanceJson, DatedAmountsJson, NewPayment, OrderItemStatus, PaymentJson,
+    PaymentSearchParameters,
+};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum Payments {
+    Table,
+    Id,
+    CustomerId,
+    Amount,
+    Currency,
+    Method,
+    Note,
+    RecordedBy,
+    CreatedAt,
+    VoidedAt,
+    VoidedBy,
+}
+
+#[derive(sea_query::Iden)]
+pub(crate) enum PaymentOrderItems {
+    Table,
+    PaymentId,
+    OrderItemId,
+}
+
+/// Build a select statement which joins the payments with their recorders.
+/// The column order must be kept in sync with the `FromRow` implementation of `PaymentJson`.
+fn select_payments() -> SelectStatement {
+    Query::select()
+        .columns([
+            (Payments::Table, Payments::Id),
+            (Payments::Table, Payments::CustomerId),
+            (Payments::Table, Payments::Amount),
+            (Payments::Table, Payments::Currency),
+            (Payments::Table, Payments::Method),
+            (Payments::Table, Payments::Note),
+            (Payments::Table, Payments::RecordedBy),
+            (Payments::Table, Payments::CreatedAt),
+            (Payments::Table, Payments::VoidedAt),
+            (Payments::Table, Payments::VoidedBy),
+        ])
+        .column((Users::Table, Users::Username))
+        .expr(Expr::cust(
+            r#"array(select "order_item_id" from "payment_order_items" where "payment_id" = "payments"."id" order by "order_item_id")"#,
+        ))
+        .from(Payments::Table)
+        .join(
+            JoinType::LeftJoin,
+            Users::Table,
+            Expr::col((Payments::Table, Payments::RecordedBy)).equals((Users::Table, Users::Id)),
+        )
+        .to_owned()
+}
+
+/// Build a select statement of the charged and paid amounts of a customer with the columns
+/// `currency`, `date`, `charged` and `paid`, the date is the day of the order item or payment
+/// in the time zone.
+fn balance_entries(customer_id: i64, time_zone: &str) -> SelectStatement {
+    let currency = Alias::new("currency");
+    let date = Alias::new("date");
+    let charged = Alias::new("charged");
+    let paid = Alias::new("paid");
+
+    // The cancelled and refunded order items aren't charged.
+    let mut charges = Query::select()
+        .expr_as(
+            Expr::col((OrderItems::Table, OrderItems::Currency)),
+            currency.clone(),
+        )
+        .expr_as(
+            local_date(
+                Expr::col((OrderItems::Table, OrderItems::CreatedAt)).into(),
+                time_zone,
+            ),
+            date.clone(),
+        )
+        .expr_as(
+            Expr::col((OrderItems::Table, OrderItems::UnitPrice))
+                .mul(Expr::col((OrderItems::Table, OrderItems::Quantity))),
+            charged.clone(),
+        )
+        .expr_as(Expr::val(0), paid.clone())
+        .from(OrderItems::Table)
+        .and_where(Expr::col((OrderItems::Table, OrderItems::CustomerId)).eq(customer_id))
+        .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+        .and_where(
+            Expr::col((OrderItems::Table, OrderItems::Status)).is_not_in([
+                OrderItemStatus::Cancelled.code(),
+                OrderItemStatus::Refunded.code(),
+            ]),
+        )
+        .to_owned();
+
+    let payments = Query::select()
+        .expr_as(Expr::col((Payments::Table, Payments::Currency)), currency)
+        .expr_as(
+            local_date(
+                Expr::col((Payments::Table, Payments::CreatedAt)).into(),
+                time_zone,
+            ),
+            date,
+        )
+        .expr_as(Expr::val(0), charged)
+        .expr_as(Expr::col((Payments::Table, Payments::Amount)), paid)
+        .from(Payments::Table)
+        .and_where(Expr::col((Payments::Table, Payments::CustomerId)).eq(customer_id))
+        .and_where(Expr::col((Payments::Table, Payments::VoidedAt)).is_null())
+        .to_owned();
+
+    charges.union(UnionType::All, payments).to_owned()
+}
+
+#[async_trait::async_trait]
+pub trait PaymentRepo {
+    async fn get(&self, id: i64) -> Result<Option<PaymentJson>, Error>;
+
+    async fn create(&self, new_payment: NewPayment, recorded_by: uuid::Uuid) -> Result<i64, Error>;
+
+    async fn void(&self, id: i64, voided_by: uuid::Uuid) -> Result<(), Error>;
+
+    async fn list(
+        &self,
+        param: PaymentSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<PaymentJson>, Error>;
+
+    /// Net the payments of a customer against the order items valued by their unit prices.
+    async fn balances(&self, customer_id: i64) -> Result<Vec<BalanceJson>, Error>;
+
+    /// The charged and paid amounts of a customer by currency and date in the time zone, so that
+    /// they can be converted with the rates valid on the dates.
+    async fn dated_balances(
+        &self,
+        customer_id: i64,
+        time_zone: &str,
+    ) -> Result<Vec<DatedAmountsJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresPaymentRepo {
+    session: PostgresSession,
+}
+
+impl PostgresPaymentRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl PaymentRepo for PostgresPaymentRepo {
+    #[tracing::instrument(name = "Get the payment from the database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<PaymentJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_payments()
+            .and_where(Expr::col((Payments::Table, Payments::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, PaymentJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Save a new payment into database", skip(self))]
+    async fn create(&self, new_payment: NewPayment, recorded_by: uuid::Uuid) -> Result<i64, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::insert()
+            .into_table(Payments::Table)
+            .columns([
+                Payments::Id,
+                Payments::CustomerId,
+                Payments::Amount,
+                Payments::Currency,
+                Payments::Method,
+                Payments::Note,
+                Payments::RecordedBy,
+                Payments::CreatedAt,
+            ])
+            .values_panic([
+                new_payment.id.into(),
+                new_payment.customer_id.0.into(),
+                new_payment.amount.0.amount.into(),
+                new_payment.amount.0.currency.into(),
+                new_payment.method.code().into(),
+                new_payment.note.into(),
+                recorded_by.to_string().into(),
+                Utc::now().into(),
+            ])
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        if !new_payment.order_item_ids.is_empty() {
+            let mut query = Query::insert()
+                .into_table(PaymentOrderItems::Table)
+                .columns([PaymentOrderItems::PaymentId, PaymentOrderItems::OrderItemId])
+                .to_owned();
+
+            for order_item_id in new_payment.order_item_ids {
+                query.values_panic([new_payment.id.into(), order_item_id.into()]);
+            }
+
+            let query = query.to_string(PostgresQueryBuilder);
+            let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(new_payment.id)
+    }
+
+    #[tracing::instrument(name = "Void a payment in database", skip(self))]
+    async fn void(&self, id: i64, voided_by: uuid::Uuid) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::update()
+            .table(Payments::Table)
+            .values([
+                (Payments::VoidedAt, Utc::now().into()),
+                (Payments::VoidedBy, voided_by.to_string().into()),
+            ])
+            .and_where(Expr::col(Payments::Id).eq(id))
+            .and_where(Expr::col(Payments::VoidedAt).is_null())
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(conn.as_mut()).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "List payments from database", skip(self))]
+    async fn list(
+        &self,
+        param: PaymentSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<PaymentJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let offset = page * page_size;
+
+        let query = select_payments()
+            .and_where_option(
+                param
+                    .customer_id
+                    .map(|e| Expr::col((Payments::Table, Payments::CustomerId)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .currency
+                    .map(|e| Expr::col((Payments::Table, Payments::Currency)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .method
+                    .map(|e| Expr::col((Payments::Table, Payments::Method)).eq(e.code())),
+            )
+            .and_where_option(param.voided.map(|voided| {
+                let col = Expr::col((Payments::Table, Payments::VoidedAt));
+                if voided {
+                    col.is_not_null()
+                } else {
+                    col.is_null()
+                }
+            }))
+            .order_by((Payments::Table, Payments::CreatedAt), Order::Desc)
+            .order_by((Payments::Table, Payments::Id), Order::Desc)
+            .offset(offset)
+            .limit(page_size)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, PaymentJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Compute the balances of a customer", skip(self))]
+    async fn balances(&self, customer_id: i64) -> Result<Vec<BalanceJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let entries = Alias::new("entries");
+        let currency = Alias::new("currency");
+
+        let query = Query::select()
+            .column((entries.clone(), currency.clone()))
+            .column((Currencies::Table, Currencies::Code))
+            .expr(Func::sum(Expr::col((
+                entries.clone(),
+                Alias::new("charged"),
+            ))))
+            .expr(Func::sum(Expr::col((entries.clone(), Alias::new("paid")))))
+            // The dates aren't summed, so their time zone doesn't matter.
+            .from_subquery(balance_entries(customer_id, "UTC"), entries.clone())
+            .join(
+                JoinType::LeftJoin,
+                Currencies::Table,
+                Expr::col((entries.clone(), currency.clone()))
+                    .equals((Currencies::Table, Currencies::Id)),
+            )
+            .group_by_col((entries.clone(), currency.clone()))
+            .group_by_col((Currencies::Table, Currencies::Code))
+            .order_by((entries, currency), Order::Asc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, BalanceJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Compute the daily balances of a customer", skip(self))]
+    async fn dated_balances(
+        &self,
+        customer_id: i64,
+        time_zone: &str,
+    ) -> Result<Vec<DatedAmountsJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let entries = Alias::new("entries");
+        let currency = Alias::new("currency");
+        let date = Alias::new("date");
+
+        let query = Query::select()
+            .column((entries.clone(), currency.clone()))
+            .column((entries.clone(), date.clone()))
+            .expr(Func::sum(Expr::col((
+                entries.clone(),
+                Alias::new("charged"),
+            ))))
+            .expr(Func::sum(Expr::col((entries.clone(), Alias::new("paid")))))
+            .from_subquery(balance_entries(customer_id, time_zone), entries.clone())
+            .group_by_col((entries.clone(), currency.clone()))
+            .group_by_col((entries.clone(), date.clone()))
+            .order_by((entries.clone(), currency), Order::Asc)
+            .order_by((entries, date), Order::Asc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, DatedAmountsJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
@@ -0,0 +1,166 @@
+use chrono::Utc;
+use sea_query::{Expr, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sqlx::{Error, Row};
+
+use crate::routes::{NewPricingRule, PricingRuleJson, UpdatePricingRule};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum PricingRules {
+    Table,
+    Id,
+    Name,
+    Currency,
+    ExchangeRate,
+    MarkupRate,
+    HandlingFee,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
+
+/// Build a select statement of the pricing rules.
+/// The column order must be kept in sync with the `FromRow` implementation of `PricingRuleJson`.
+fn select_pricing_rules() -> SelectStatement {
+    Query::select()
+        .columns([
+            (PricingRules::Table, PricingRules::Id),
+            (PricingRules::Table, PricingRules::Name),
+            (PricingRules::Table, PricingRules::Currency),
+            (PricingRules::Table, PricingRules::ExchangeRate),
+            (PricingRules::Table, PricingRules::MarkupRate),
+            (PricingRules::Table, PricingRules::HandlingFee),
+            (PricingRules::Table, PricingRules::CreatedAt),
+            (PricingRules::Table, PricingRules::UpdatedAt),
+            (PricingRules::Table, PricingRules::DeletedAt),
+        ])
+        .from(PricingRules::Table)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+pub trait PricingRuleRepo {
+    async fn get(&self, id: i64) -> Result<Option<PricingRuleJson>, Error>;
+
+    async fn create(&self, new_pricing_rule: NewPricingRule) -> Result<i64, Error>;
+
+    async fn update(&self, update_pricing_rule: UpdatePricingRule) -> Result<(), Error>;
+
+    async fn list(&self) -> Result<Vec<PricingRuleJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresPricingRuleRepo {
+    session: PostgresSession,
+}
+
+impl PostgresPricingRuleRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl PricingRuleRepo for PostgresPricingRuleRepo {
+    #[tracing::instrument(name = "Get a pricing rule from database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<PricingRuleJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_pricing_rules()
+            .and_where(Expr::col((PricingRules::Table, PricingRules::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, PricingRuleJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Save a new pricing rule into database", skip(self))]
+    async fn create(&self, new_pricing_rule: NewPricingRule) -> Result<i64, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let query = Query::insert()
+            .into_table(PricingRules::Table)
+            .columns([
+                PricingRules::Id,
+                PricingRules::Name,
+                PricingRules::Currency,
+                PricingRules::ExchangeRate,
+                PricingRules::MarkupRate,
+                PricingRules::HandlingFee,
+                PricingRules::CreatedAt,
+                PricingRules::UpdatedAt,
+            ])
+            .values_panic([
+                new_pricing_rule.id.into(),
+                new_pricing_rule.name.into(),
+                new_pricing_rule.currency.into(),
+                new_pricing_rule.exchange_rate.0.into(),
+                new_pricing_rule.markup_rate.0.into(),
+                new_pricing_rule.handling_fee.into(),
+                now.into(),
+                now.into(),
+            ])
+            .returning(Query::returning().column(PricingRules::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).fetch_one(conn.as_mut()).await?;
+
+        Ok(res.get(0))
+    }
+
+    #[tracing::instrument(name = "Update a pricing rule in database", skip(self))]
+    async fn update(&self, update_pricing_rule: UpdatePricingRule) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = {
+            let mut update_data = vec![];
+
+            if let Some(name) = update_pricing_rule.name {
+                update_data.push((PricingRules::Name, name.into()));
+            }
+
+            if let Some(exchange_rate) = update_pricing_rule.exchange_rate {
+                update_data.push((PricingRules::ExchangeRate, exchange_rate.0.into()));
+            }
+
+            if let Some(markup_rate) = update_pricing_rule.markup_rate {
+                update_data.push((PricingRules::MarkupRate, markup_rate.0.into()));
+            }
+
+            if let Some(handling_fee) = update_pricing_rule.handling_fee {
+                update_data.push((PricingRules::HandlingFee, handling_fee.into()));
+            }
+
+            update_data.push((PricingRules::UpdatedAt, Utc::now().into()));
+
+            Query::update()
+                .table(PricingRules::Table)
+                .values(update_data)
+                .and_where(
+                    Expr::col((PricingRules::Table, PricingRules::Id)).eq(update_pricing_rule.id),
+                )
+                .to_string(PostgresQueryBuilder)
+        };
+
+        let _ = sqlx::query(query.as_str()).execute(conn.as_mut()).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "List pricing rules from database", skip(self))]
+    async fn list(&self) -> Result<Vec<PricingRuleJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_pricing_rules()
+            .and_where(Expr::col((PricingRules::Table, PricingRules::DeletedAt)).is_null())
+            .order_by((PricingRules::Table, PricingRules::Name), Order::Asc)
+            .order_by((PricingRules::Table, PricingRules::Id), Order::Asc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, PricingRuleJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
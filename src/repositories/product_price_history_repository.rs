@@ -0,0 +1,145 @@
+use chrono::Utc;
+use sea_query::{Expr, JoinType, Order, PostgresQueryBuilder, Query};
+use sqlx::{Error, PgConnection};
+
+use crate::repositories::Users;
+use crate::routes::{NewProductPriceHistory, ProductPriceHistoryJson};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum ProductPriceHistories {
+    Table,
+    Id,
+    ProductId,
+    PreviousCurrency,
+    PreviousPrice,
+    Currency,
+    Price,
+    ChangedBy,
+    CreatedAt,
+}
+
+/// Insert a price history record with the given connection, so that it can share the
+/// transaction which changes the price of the product.
+pub(crate) async fn insert_product_price_history(
+    conn: &mut PgConnection,
+    history: NewProductPriceHistory,
+) -> Result<(), Error> {
+    let query = Query::insert()
+        .into_table(ProductPriceHistories::Table)
+        .columns([
+            ProductPriceHistories::Id,
+            ProductPriceHistories::ProductId,
+            ProductPriceHistories::PreviousCurrency,
+            ProductPriceHistories::PreviousPrice,
+            ProductPriceHistories::Currency,
+            ProductPriceHistories::Price,
+            ProductPriceHistories::ChangedBy,
+            ProductPriceHistories::CreatedAt,
+        ])
+        .values_panic([
+            history.id.into(),
+            history.product_id.into(),
+            history.previous.currency.into(),
+            history.previous.amount.into(),
+            history.current.currency.into(),
+            history.current.amount.into(),
+            history.changed_by.to_string().into(),
+            Utc::now().into(),
+        ])
+        .to_string(PostgresQueryBuilder);
+
+    let _ = sqlx::query(query.as_str()).execute(conn).await?;
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+pub trait ProductPriceHistoryRepo {
+    async fn list(&self, product_id: i64) -> Result<Vec<ProductPriceHistoryJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresProductPriceHistoryRepo {
+    session: PostgresSession,
+}
+
+impl PostgresProductPriceHistoryRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProductPriceHistoryRepo for PostgresProductPriceHistoryRepo {
+    #[tracing::instrument(name = "List the price histories of a product", skip(self))]
+    async fn list(&self, product_id: i64) -> Result<Vec<ProductPriceHistoryJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        // The column order must be kept in sync with the `FromRow` implementation of
+        // `ProductPriceHistoryJson`.
+        let query = Query::select()
+            .columns([
+                (ProductPriceHistories::Table, ProductPriceHistories::Id),
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::ProductId,
+                ),
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::PreviousCurrency,
+                ),
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::PreviousPrice,
+                ),
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::Currency,
+                ),
+                (ProductPriceHistories::Table, ProductPriceHistories::Price),
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::ChangedBy,
+                ),
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::CreatedAt,
+                ),
+            ])
+            .column((Users::Table, Users::Username))
+            .from(ProductPriceHistories::Table)
+            .join(
+                JoinType::LeftJoin,
+                Users::Table,
+                Expr::col((
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::ChangedBy,
+                ))
+                .equals((Users::Table, Users::Id)),
+            )
+            .and_where(
+                Expr::col((
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::ProductId,
+                ))
+                .eq(product_id),
+            )
+            .order_by(
+                (
+                    ProductPriceHistories::Table,
+                    ProductPriceHistories::CreatedAt,
+                ),
+                Order::Asc,
+            )
+            .order_by(
+                (ProductPriceHistories::Table, ProductPriceHistories::Id),
+                Order::Asc,
+            )
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, ProductPriceHistoryJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+}
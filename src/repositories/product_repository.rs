@@ -1,9 +1,21 @@
 use chrono::Utc;
-use sea_query::{Expr, PostgresQueryBuilder, Query, SimpleExpr};
-use sqlx::{Error, Row};
-
-use crate::routes::{NewProduct, ProductJson, ProductSearchParameters, UpdateProduct};
-use crate::utils::PostgresSession;
+use rust_decimal::Decimal;
+use sea_query::extension::postgres::PgExpr;
+use sea_query::{
+    Expr, JoinType, LockType, Order, PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
+};
+use sqlx::{Connection, Error, PgConnection, Row};
+
+use crate::repositories::{
+    category_subtree, insert_product_price_history, list_variants_of_products, Currencies,
+    ProductVariants,
+};
+use crate::routes::{
+    Money, NewProduct, NewProductImage, NewProductPriceHistory, NewStockAdjustment, PriceQuote,
+    ProductImageJson, ProductJson, ProductSearchParameters, ProductSort, StockAdjustmentJson,
+    UpdateProduct,
+};
+use crate::utils::{contains_pattern, PostgresSession, SearchText};
 
 #[derive(sea_query::Iden)]
 pub(crate) enum Products {
@@ -15,6 +27,274 @@ pub(crate) enum Products {
     CreatedAt,
     UpdatedAt,
     DeletedAt,
+    OnHand,
+    Reserved,
+    CategoryId,
+    CostPrice,
+    CostTaxRate,
+    PricingRuleId,
+    Cost,
+    SearchText,
+}
+
+#[derive(sea_query::Iden)]
+pub(crate) enum ProductImages {
+    Table,
+    Id,
+    ProductId,
+    StorageKey,
+    ThumbnailKey,
+    Url,
+    ThumbnailUrl,
+    ContentType,
+    Width,
+    Height,
+    CreatedBy,
+    CreatedAt,
+    DeletedAt,
+}
+
+#[derive(sea_query::Iden)]
+pub(crate) enum ProductTags {
+    Table,
+    ProductId,
+    Tag,
+}
+
+#[derive(sea_query::Iden)]
+pub(crate) enum StockAdjustments {
+    Table,
+    Id,
+    ProductId,
+    Kind,
+    Quantity,
+    Reason,
+    CreatedBy,
+    CreatedAt,
+    VariantId,
+}
+
+/// Build a select statement of the products.
+/// The column order must be kept in sync with the `FromRow` implementation of `ProductJson`.
+fn select_products() -> SelectStatement {
+    Query::select()
+        .columns([
+            (Products::Table, Products::Id),
+            (Products::Table, Products::Name),
+            (Products::Table, Products::Currency),
+            (Products::Table, Products::Price),
+            (Products::Table, Products::CreatedAt),
+            (Products::Table, Products::UpdatedAt),
+            (Products::Table, Products::DeletedAt),
+            (Products::Table, Products::OnHand),
+            (Products::Table, Products::Reserved),
+            (Products::Table, Products::CategoryId),
+            (Products::Table, Products::CostPrice),
+            (Products::Table, Products::CostTaxRate),
+            (Products::Table, Products::PricingRuleId),
+            (Products::Table, Products::Cost),
+        ])
+        .columns([
+            (Currencies::Table, Currencies::Code),
+            (Currencies::Table, Currencies::Symbol),
+        ])
+        .from(Products::Table)
+        .join(
+            JoinType::LeftJoin,
+            Currencies::Table,
+            Expr::col((Products::Table, Products::Currency))
+                .equals((Currencies::Table, Currencies::Id)),
+        )
+        .to_owned()
+}
+
+/// Fill the variants, tags and images of the products.
+async fn fill_products(conn: &mut PgConnection, products: &mut [ProductJson]) -> Result<(), Error> {
+    if products.is_empty() {
+        return Ok(());
+    }
+
+    let product_ids = products
+        .iter()
+        .map(|e| e.id.parse::<i64>().map_err(|e| Error::Decode(e.into())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let query = Query::select()
+        .columns([ProductTags::ProductId, ProductTags::Tag])
+        .from(ProductTags::Table)
+        .and_where(Expr::col(ProductTags::ProductId).is_in(product_ids.clone()))
+        .order_by(ProductTags::Tag, Order::Asc)
+        .to_string(PostgresQueryBuilder);
+
+    let tags = sqlx::query(query.as_str()).fetch_all(&mut *conn).await?;
+
+    // The column order must be kept in sync with the `FromRow` implementation of
+    // `ProductImageJson`.
+    let query = Query::select()
+        .columns([
+            ProductImages::Id,
+            ProductImages::ProductId,
+            ProductImages::Url,
+            ProductImages::ThumbnailUrl,
+            ProductImages::ContentType,
+            ProductImages::Width,
+            ProductImages::Height,
+            ProductImages::CreatedAt,
+        ])
+        .from(ProductImages::Table)
+        .and_where(Expr::col(ProductImages::ProductId).is_in(product_ids.clone()))
+        .and_where(Expr::col(ProductImages::DeletedAt).is_null())
+        .order_by(ProductImages::CreatedAt, Order::Asc)
+        .order_by(ProductImages::Id, Order::Asc)
+        .to_string(PostgresQueryBuilder);
+
+    let mut images = sqlx::query_as::<_, ProductImageJson>(query.as_str())
+        .fetch_all(&mut *conn)
+        .await?;
+    let mut variants = list_variants_of_products(&mut *conn, product_ids).await?;
+
+    for product in products.iter_mut() {
+        product.tags = tags
+            .iter()
+            .filter(|row| row.get::<i64, _>(0).to_string() == product.id)
+            .map(|row| row.get(1))
+            .collect();
+
+        let (product_variants, others) = variants
+            .into_iter()
+            .partition(|e| e.product_id == product.id);
+        product.variants = product_variants;
+        variants = others;
+
+        let (product_images, others) = images.into_iter().partition(|e| e.product_id == product.id);
+        product.images = product_images;
+        images = others;
+    }
+
+    Ok(())
+}
+
+/// Replace the tags of a product with the given connection.
+async fn replace_product_tags(
+    conn: &mut PgConnection,
+    product_id: i64,
+    tags: Vec<String>,
+) -> Result<(), Error> {
+    let query = Query::delete()
+        .from_table(ProductTags::Table)
+        .and_where(Expr::col(ProductTags::ProductId).eq(product_id))
+        .to_string(PostgresQueryBuilder);
+
+    let _ = sqlx::query(query.as_str()).execute(&mut *conn).await?;
+
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let mut query = Query::insert()
+        .into_table(ProductTags::Table)
+        .columns([ProductTags::ProductId, ProductTags::Tag])
+        .to_owned();
+
+    for tag in tags {
+        query.values_panic([product_id.into(), tag.into()]);
+    }
+
+    let query = query.to_string(PostgresQueryBuilder);
+
+    let _ = sqlx::query(query.as_str()).execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+/// Move the on-hand and reserved quantities of a product with the given connection, so that the
+/// caller decides the transaction boundary.
+/// When `reject_oversell` is set, nothing is changed and `false` is returned if the available
+/// quantity would become negative. The same happens whenever the on-hand quantity would become
+/// negative.
+pub(crate) async fn change_product_stock(
+    conn: &mut PgConnection,
+    product_id: i64,
+    on_hand_delta: i32,
+    reserved_delta: i32,
+    reject_oversell: bool,
+) -> Result<bool, Error> {
+    if on_hand_delta == 0 && reserved_delta == 0 {
+        return Ok(true);
+    }
+
+    let on_hand = Expr::col(Products::OnHand).add(on_hand_delta);
+    let reserved = Expr::col(Products::Reserved).add(reserved_delta);
+
+    let mut query = Query::update()
+        .table(Products::Table)
+        .values([
+            (Products::OnHand, on_hand.clone()),
+            (Products::Reserved, reserved.clone()),
+        ])
+        .and_where(Expr::col(Products::Id).eq(product_id))
+        .to_owned();
+
+    if reject_oversell {
+        query.and_where(Expr::expr(on_hand.clone().sub(reserved)).gte(0));
+    }
+
+    // The on-hand stock is never taken below zero, whether overselling is allowed or not.
+    if on_hand_delta < 0 {
+        query.and_where(Expr::expr(on_hand).gte(0));
+    }
+
+    let query = query.to_string(PostgresQueryBuilder);
+
+    let res = sqlx::query(query.as_str()).execute(&mut *conn).await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+/// Lock the product and return its current price, so that the price can be compared after
+/// being changed in the same transaction.
+async fn lock_product_price(conn: &mut PgConnection, id: i64) -> Result<Option<Money>, Error> {
+    let query = Query::select()
+        .columns([Products::Currency, Products::Price])
+        .from(Products::Table)
+        .and_where(Expr::col(Products::Id).eq(id))
+        .lock(LockType::Update)
+        .to_string(PostgresQueryBuilder);
+
+    let row = sqlx::query(query.as_str()).fetch_optional(conn).await?;
+
+    Ok(row.map(|e| Money {
+        currency: e.get(0),
+        amount: e.get(1),
+    }))
+}
+
+/// Execute an update of a product which returns its currency and price, and record the change
+/// of the price when it's different from the previous one.
+async fn update_product_price(
+    conn: &mut PgConnection,
+    id: i64,
+    query: &str,
+    changed_by: uuid::Uuid,
+) -> Result<(), Error> {
+    let previous = lock_product_price(&mut *conn, id).await?;
+
+    let current = sqlx::query(query)
+        .fetch_optional(&mut *conn)
+        .await?
+        .map(|e| Money {
+            currency: e.get(0),
+            amount: e.get(1),
+        });
+
+    if let (Some(previous), Some(current)) = (previous, current) {
+        if previous != current {
+            let history = NewProductPriceHistory::new(id, previous, current, changed_by);
+            insert_product_price_history(conn, history).await?;
+        }
+    }
+
+    Ok(())
 }
 
 #[async_trait::async_trait]
@@ -23,7 +303,12 @@ pub trait ProductRepository {
 
     async fn create(&self, new_product: NewProduct) -> Result<i64, Error>;
 
-    async fn update(&self, update_product: UpdateProduct) -> Result<(), Error>;
+    /// The change of the price or currency is recorded in the price histories.
+    async fn update(
+        &self,
+        update_product: UpdateProduct,
+        updated_by: uuid::Uuid,
+    ) -> Result<(), Error>;
 
     async fn delete(&self, id: i64) -> Result<(), Error>;
 
@@ -33,6 +318,45 @@ pub trait ProductRepository {
         page: u64,
         page_size: u64,
     ) -> Result<Vec<ProductJson>, Error>;
+
+    /// Record the adjustment and apply it to the on-hand quantity, return `None` when the
+    /// on-hand quantity would become negative.
+    async fn adjust_stock(
+        &self,
+        adjustment: NewStockAdjustment,
+        created_by: uuid::Uuid,
+    ) -> Result<Option<i64>, Error>;
+
+    async fn list_stock_adjustments(
+        &self,
+        product_id: i64,
+    ) -> Result<Vec<StockAdjustmentJson>, Error>;
+
+    async fn add_image(&self, image: NewProductImage, created_by: uuid::Uuid)
+        -> Result<i64, Error>;
+
+    /// Soft-delete the image and return the keys of the image and its thumbnail, so that the
+    /// caller can remove them from the storage. Return `None` when there is no such image.
+    async fn delete_image(&self, id: i64) -> Result<Option<(String, String)>, Error>;
+
+    /// List the non-deleted products which are priced by the pricing rule, or by any pricing
+    /// rule when it's `None`.
+    async fn list_priced_products(
+        &self,
+        pricing_rule_id: Option<i64>,
+    ) -> Result<Vec<ProductJson>, Error>;
+
+    /// Save the recomputed currency, cost and price of the products in one transaction, the
+    /// changed prices are recorded in the price histories.
+    async fn reprice(
+        &self,
+        quotes: Vec<(i64, i16, PriceQuote)>,
+        repriced_by: uuid::Uuid,
+    ) -> Result<(), Error>;
+
+    /// Index the names of the products which don't have a search text, i.e. the ones saved
+    /// before the names were normalized and romanized, return the number of them.
+    async fn reindex_search_texts(&self) -> Result<usize, Error>;
 }
 
 pub struct PostgresProductRepoImpl {
@@ -49,37 +373,37 @@ impl PostgresProductRepoImpl {
 impl ProductRepository for PostgresProductRepoImpl {
     #[tracing::instrument(name = "get a product from database", skip(self))]
     async fn get(&self, id: i64) -> Result<Option<ProductJson>, Error> {
-        let mut conn = self.session.get_session().await;
-        let query = {
-            Query::select()
-                .from(Products::Table)
-                .columns([
-                    Products::Id,
-                    Products::Name,
-                    Products::Currency,
-                    Products::Price,
-                    Products::CreatedAt,
-                    Products::UpdatedAt,
-                    Products::DeletedAt,
-                ])
-                .and_where(Expr::col((Products::Table, Products::Id)).eq(id))
-                .to_string(PostgresQueryBuilder)
-        };
+        let mut conn = self.session.get_session().await?;
+        let query = select_products()
+            .and_where(Expr::col((Products::Table, Products::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
 
-        sqlx::query_as::<_, ProductJson>(query.as_str())
+        let mut product = sqlx::query_as::<_, ProductJson>(query.as_str())
             .fetch_optional(conn.as_mut())
-            .await
+            .await?;
+
+        if let Some(product) = product.as_mut() {
+            fill_products(conn.as_mut(), std::slice::from_mut(product)).await?;
+        }
+
+        Ok(product)
     }
 
     #[tracing::instrument(name = "Save a new product into database", skip(self, new_product))]
     async fn create(&self, new_product: NewProduct) -> Result<i64, Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = {
             let id = new_product.id.into();
+            let search_text = SearchText::new(&new_product.name.0).indexed().into();
             let name = new_product.name.0.into();
-            let currency = new_product.currency.0.into();
-            let price = new_product.price.into();
+            let currency = new_product.price.currency.into();
+            let price = new_product.price.amount.into();
+            let category_id = new_product.category_id.into();
+            let cost_price = new_product.cost_price.into();
+            let cost_tax_rate = new_product.cost_tax_rate.into();
+            let pricing_rule_id = new_product.pricing_rule_id.into();
+            let cost = new_product.cost.into();
             let now = Utc::now();
             let created_at = now.into();
             let updated_at = now.into();
@@ -93,25 +417,58 @@ impl ProductRepository for PostgresProductRepoImpl {
                     Products::Price,
                     Products::CreatedAt,
                     Products::UpdatedAt,
+                    Products::CategoryId,
+                    Products::CostPrice,
+                    Products::CostTaxRate,
+                    Products::PricingRuleId,
+                    Products::Cost,
+                    Products::SearchText,
+                ])
+                .values_panic([
+                    id,
+                    name,
+                    currency,
+                    price,
+                    created_at,
+                    updated_at,
+                    category_id,
+                    cost_price,
+                    cost_tax_rate,
+                    pricing_rule_id,
+                    cost,
+                    search_text,
                 ])
-                .values_panic([id, name, currency, price, created_at, updated_at])
                 .returning(Query::returning().column(Products::Id))
                 .to_string(PostgresQueryBuilder)
         };
 
-        let res = sqlx::query(dbg!(&query)).fetch_one(conn.as_mut()).await?;
+        let mut tx = conn.as_mut().begin().await?;
 
-        Ok(res.get(0))
+        let res = sqlx::query(dbg!(&query)).fetch_one(&mut *tx).await?;
+        let id: i64 = res.get(0);
+
+        replace_product_tags(&mut tx, id, new_product.tags.0).await?;
+
+        tx.commit().await?;
+
+        Ok(id)
     }
 
     #[tracing::instrument(name = "Update a product into database", skip(self, update_product))]
-    async fn update(&self, update_product: UpdateProduct) -> Result<(), Error> {
-        let mut conn = self.session.get_session().await;
+    async fn update(
+        &self,
+        update_product: UpdateProduct,
+        updated_by: uuid::Uuid,
+    ) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+        let changes_price = update_product.currency.is_some() || update_product.price.is_some();
 
         let query = {
             let mut update_date = vec![];
 
             if let Some(name) = update_product.name {
+                let search_text = SearchText::new(&name.0).indexed();
+                update_date.push((Products::SearchText, search_text.into()));
                 update_date.push((Products::Name, name.0.into()));
             }
 
@@ -120,7 +477,32 @@ impl ProductRepository for PostgresProductRepoImpl {
             }
 
             if let Some(price) = update_product.price {
-                update_date.push((Products::Price, price.into()));
+                update_date.push((Products::Price, price.amount.into()));
+            }
+
+            if let Some(category_id) = update_product.category_id {
+                update_date.push((Products::CategoryId, category_id.into()));
+            }
+
+            if let Some(cost_price) = update_product.cost_price {
+                update_date.push((Products::CostPrice, cost_price.into()));
+            }
+
+            if let Some(cost_tax_rate) = update_product.cost_tax_rate {
+                update_date.push((Products::CostTaxRate, cost_tax_rate.into()));
+            }
+
+            if let Some(pricing_rule_id) = update_product.pricing_rule_id {
+                update_date.push((Products::PricingRuleId, pricing_rule_id.into()));
+
+                // The cost is only known with a pricing rule.
+                if pricing_rule_id.is_none() {
+                    update_date.push((Products::Cost, Option::<Decimal>::None.into()));
+                }
+            }
+
+            if let Some(cost) = update_product.cost {
+                update_date.push((Products::Cost, cost.into()));
             }
 
             update_date.push((Products::UpdatedAt, Utc::now().into()));
@@ -129,17 +511,30 @@ impl ProductRepository for PostgresProductRepoImpl {
                 .table(Products::Table)
                 .values(update_date)
                 .and_where(Expr::col((Products::Table, Products::Id)).eq(update_product.id))
+                .returning(Query::returning().columns([Products::Currency, Products::Price]))
                 .to_string(PostgresQueryBuilder)
         };
 
-        let _ = sqlx::query(query.as_str()).execute(conn.as_mut()).await?;
+        let mut tx = conn.as_mut().begin().await?;
+
+        if changes_price {
+            update_product_price(&mut tx, update_product.id, query.as_str(), updated_by).await?;
+        } else {
+            let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+        }
+
+        if let Some(tags) = update_product.tags {
+            replace_product_tags(&mut tx, update_product.id, tags.0).await?;
+        }
+
+        tx.commit().await?;
 
         Ok(())
     }
 
     #[tracing::instrument(name = "Delete a product from database", skip(self, id))]
     async fn delete(&self, id: i64) -> Result<(), Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = Query::update()
             .table(Products::Table)
@@ -159,25 +554,80 @@ impl ProductRepository for PostgresProductRepoImpl {
         page: u64,
         page_size: u64,
     ) -> Result<Vec<ProductJson>, Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
         let offset = page * page_size;
 
         fn format_like_string(col: Products, value: &str) -> SimpleExpr {
-            let formatted_string = format!(r#"%{value}%"#);
-            Expr::col((Products::Table, col)).like(formatted_string.as_str())
+            Expr::col((Products::Table, col)).ilike(contains_pattern(value))
         }
 
-        let query = Query::select()
-            .from(Products::Table)
-            .columns([
-                Products::Id,
-                Products::Name,
-                Products::Currency,
-                Products::Price,
-                Products::CreatedAt,
-                Products::UpdatedAt,
-                Products::DeletedAt,
-            ])
+        // The tags are stored in lowercase.
+        fn normalize_tags(tags: Option<Vec<String>>) -> Option<Vec<String>> {
+            tags.map(|e| {
+                e.iter()
+                    .map(|tag| tag.trim().to_lowercase())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|e| !e.is_empty())
+        }
+
+        let any_tags = normalize_tags(keyboard.any_tags);
+        let all_tags = normalize_tags(keyboard.all_tags).map(|mut e| {
+            e.sort();
+            e.dedup();
+            e
+        });
+
+        let search = keyboard
+            .search
+            .as_deref()
+            .map(SearchText::new)
+            .filter(|e| !e.normalized.is_empty());
+
+        // A product matches when its search text contains the text in either form, when a word
+        // of it is similar to the romanized text, or when it has all the romanized words.
+        // The rank adds up the same measures, so that the exact matches come first.
+        let search_condition = search.as_ref().map(|e| {
+            Expr::cust_with_values(
+                r#"("products"."search_text" LIKE $1 OR "products"."search_text" LIKE $2 OR $3 <% "products"."search_text" OR to_tsvector('simple', "products"."search_text") @@ plainto_tsquery('simple', $3))"#,
+                [
+                    contains_pattern(&e.normalized),
+                    contains_pattern(&e.romanized),
+                    e.romanized.clone(),
+                ],
+            )
+        });
+        let search_rank = search.as_ref().map(|e| {
+            Expr::cust_with_values(
+                r#"("products"."search_text" LIKE $1 OR "products"."search_text" LIKE $2)::int + word_similarity($3, "products"."search_text") + ts_rank(to_tsvector('simple', "products"."search_text"), plainto_tsquery('simple', $3))"#,
+                [
+                    contains_pattern(&e.normalized),
+                    contains_pattern(&e.romanized),
+                    e.romanized.clone(),
+                ],
+            )
+        });
+
+        let sort = keyboard.sort.unwrap_or(match search {
+            Some(_) => ProductSort::Relevance,
+            None => ProductSort::Id,
+        });
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        if search.is_some() {
+            // Lower the threshold of `<%` from 0.6, so that a word with a typo is still similar.
+            let _ = sqlx::query("SET LOCAL pg_trgm.word_similarity_threshold = 0.4")
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let category_ids = match keyboard.category_id {
+            Some(category_id) => Some(category_subtree(&mut tx, category_id).await?),
+            None => None,
+        };
+
+        let mut query = select_products()
             .and_where_option(
                 keyboard
                     .id
@@ -190,12 +640,316 @@ impl ProductRepository for PostgresProductRepoImpl {
                     .as_ref()
                     .map(|e| format_like_string(Products::Name, e)),
             )
+            .and_where_option(
+                category_ids.map(|e| Expr::col((Products::Table, Products::CategoryId)).is_in(e)),
+            )
+            .and_where_option(any_tags.map(|tags| {
+                Expr::col((Products::Table, Products::Id)).in_subquery(
+                    Query::select()
+                        .column(ProductTags::ProductId)
+                        .from(ProductTags::Table)
+                        .and_where(Expr::col(ProductTags::Tag).is_in(tags))
+                        .to_owned(),
+                )
+            }))
+            .and_where_option(all_tags.map(|tags| {
+                let count = tags.len() as i64;
+                Expr::col((Products::Table, Products::Id)).in_subquery(
+                    Query::select()
+                        .column(ProductTags::ProductId)
+                        .from(ProductTags::Table)
+                        .and_where(Expr::col(ProductTags::Tag).is_in(tags))
+                        .group_by_col(ProductTags::ProductId)
+                        .and_having(Expr::col(ProductTags::Tag).count().eq(count))
+                        .to_owned(),
+                )
+            }))
+            .and_where_option(search_condition)
+            .to_owned();
+
+        if let (ProductSort::Relevance, Some(rank)) = (sort, search_rank) {
+            query.order_by_expr(rank, Order::Desc);
+        }
+
+        let query = query
+            .order_by((Products::Table, Products::Id), Order::Asc)
             .offset(offset)
             .limit(page_size)
             .to_string(PostgresQueryBuilder);
 
-        sqlx::query_as::<_, ProductJson>(dbg!(query.as_str()))
+        let mut products = sqlx::query_as::<_, ProductJson>(dbg!(query.as_str()))
+            .fetch_all(&mut *tx)
+            .await?;
+
+        fill_products(&mut tx, &mut products).await?;
+
+        tx.commit().await?;
+
+        Ok(products)
+    }
+
+    #[tracing::instrument(name = "Adjust the stock of a product in database", skip(self))]
+    async fn adjust_stock(
+        &self,
+        adjustment: NewStockAdjustment,
+        created_by: uuid::Uuid,
+    ) -> Result<Option<i64>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        // The variant holds its own stock, so the product isn't touched then.
+        let query = match adjustment.variant_id {
+            None => Query::update()
+                .table(Products::Table)
+                .values([
+                    (
+                        Products::OnHand,
+                        Expr::col(Products::OnHand).add(adjustment.quantity),
+                    ),
+                    (Products::UpdatedAt, Utc::now().into()),
+                ])
+                .and_where(Expr::col(Products::Id).eq(adjustment.product_id))
+                .and_where(Expr::expr(Expr::col(Products::OnHand).add(adjustment.quantity)).gte(0))
+                .to_string(PostgresQueryBuilder),
+            Some(variant_id) => Query::update()
+                .table(ProductVariants::Table)
+                .values([
+                    (
+                        ProductVariants::OnHand,
+                        Expr::col(ProductVariants::OnHand).add(adjustment.quantity),
+                    ),
+                    (ProductVariants::UpdatedAt, Utc::now().into()),
+                ])
+                .and_where(Expr::col(ProductVariants::Id).eq(variant_id))
+                .and_where(Expr::col(ProductVariants::ProductId).eq(adjustment.product_id))
+                .and_where(
+                    Expr::expr(Expr::col(ProductVariants::OnHand).add(adjustment.quantity)).gte(0),
+                )
+                .to_string(PostgresQueryBuilder),
+        };
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let res = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        if res.rows_affected() != 1 {
+            return Ok(None);
+        }
+
+        let query = Query::insert()
+            .into_table(StockAdjustments::Table)
+            .columns([
+                StockAdjustments::Id,
+                StockAdjustments::ProductId,
+                StockAdjustments::Kind,
+                StockAdjustments::Quantity,
+                StockAdjustments::Reason,
+                StockAdjustments::CreatedBy,
+                StockAdjustments::CreatedAt,
+                StockAdjustments::VariantId,
+            ])
+            .values_panic([
+                adjustment.id.into(),
+                adjustment.product_id.into(),
+                adjustment.kind.code().into(),
+                adjustment.quantity.into(),
+                adjustment.reason.into(),
+                created_by.to_string().into(),
+                Utc::now().into(),
+                adjustment.variant_id.into(),
+            ])
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok(Some(adjustment.id))
+    }
+
+    #[tracing::instrument(name = "List the stock adjustments of a product", skip(self))]
+    async fn list_stock_adjustments(
+        &self,
+        product_id: i64,
+    ) -> Result<Vec<StockAdjustmentJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::select()
+            .columns([
+                StockAdjustments::Id,
+                StockAdjustments::ProductId,
+                StockAdjustments::Kind,
+                StockAdjustments::Quantity,
+                StockAdjustments::Reason,
+                StockAdjustments::CreatedBy,
+                StockAdjustments::CreatedAt,
+                StockAdjustments::VariantId,
+            ])
+            .from(StockAdjustments::Table)
+            .and_where(Expr::col(StockAdjustments::ProductId).eq(product_id))
+            .order_by(StockAdjustments::CreatedAt, Order::Desc)
+            .order_by(StockAdjustments::Id, Order::Desc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, StockAdjustmentJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Save a new product image into database", skip(self))]
+    async fn add_image(
+        &self,
+        image: NewProductImage,
+        created_by: uuid::Uuid,
+    ) -> Result<i64, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::insert()
+            .into_table(ProductImages::Table)
+            .columns([
+                ProductImages::Id,
+                ProductImages::ProductId,
+                ProductImages::StorageKey,
+                ProductImages::ThumbnailKey,
+                ProductImages::Url,
+                ProductImages::ThumbnailUrl,
+                ProductImages::ContentType,
+                ProductImages::Width,
+                ProductImages::Height,
+                ProductImages::CreatedBy,
+                ProductImages::CreatedAt,
+            ])
+            .values_panic([
+                image.id.into(),
+                image.product_id.into(),
+                image.storage_key.into(),
+                image.thumbnail_key.into(),
+                image.url.into(),
+                image.thumbnail_url.into(),
+                image.content_type.into(),
+                image.width.into(),
+                image.height.into(),
+                created_by.to_string().into(),
+                Utc::now().into(),
+            ])
+            .returning(Query::returning().column(ProductImages::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).fetch_one(conn.as_mut()).await?;
+
+        Ok(res.get(0))
+    }
+
+    #[tracing::instrument(name = "Delete a product image from database", skip(self))]
+    async fn delete_image(&self, id: i64) -> Result<Option<(String, String)>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::update()
+            .table(ProductImages::Table)
+            .values([(ProductImages::DeletedAt, Utc::now().into())])
+            .and_where(Expr::col(ProductImages::Id).eq(id))
+            .and_where(Expr::col(ProductImages::DeletedAt).is_null())
+            .returning(
+                Query::returning()
+                    .columns([ProductImages::StorageKey, ProductImages::ThumbnailKey]),
+            )
+            .to_string(PostgresQueryBuilder);
+
+        let row = sqlx::query(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await?;
+
+        Ok(row.map(|row| (row.get(0), row.get(1))))
+    }
+
+    #[tracing::instrument(name = "List the priced products from database", skip(self))]
+    async fn list_priced_products(
+        &self,
+        pricing_rule_id: Option<i64>,
+    ) -> Result<Vec<ProductJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_products()
+            .and_where(Expr::col((Products::Table, Products::PricingRuleId)).is_not_null())
+            .and_where(Expr::col((Products::Table, Products::CostPrice)).is_not_null())
+            .and_where(Expr::col((Products::Table, Products::DeletedAt)).is_null())
+            .and_where_option(
+                pricing_rule_id
+                    .map(|e| Expr::col((Products::Table, Products::PricingRuleId)).eq(e)),
+            )
+            .order_by((Products::Table, Products::Id), Order::Asc)
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, ProductJson>(query.as_str())
             .fetch_all(conn.as_mut())
             .await
     }
+
+    #[tracing::instrument(
+        name = "Save the recomputed prices of products into database",
+        skip(self)
+    )]
+    async fn reprice(
+        &self,
+        quotes: Vec<(i64, i16, PriceQuote)>,
+        repriced_by: uuid::Uuid,
+    ) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        for (id, currency, quote) in quotes {
+            let query = Query::update()
+                .table(Products::Table)
+                .values([
+                    (Products::Currency, currency.into()),
+                    (Products::Cost, quote.cost.into()),
+                    (Products::Price, quote.price.into()),
+                    (Products::UpdatedAt, now.into()),
+                ])
+                .and_where(Expr::col(Products::Id).eq(id))
+                .returning(Query::returning().columns([Products::Currency, Products::Price]))
+                .to_string(PostgresQueryBuilder);
+
+            update_product_price(&mut tx, id, query.as_str(), repriced_by).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Index the search texts of products", skip(self))]
+    async fn reindex_search_texts(&self) -> Result<usize, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::select()
+            .columns([Products::Id, Products::Name])
+            .from(Products::Table)
+            .and_where(Expr::col(Products::SearchText).is_null())
+            .order_by(Products::Id, Order::Asc)
+            .lock(LockType::Update)
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let rows = sqlx::query(query.as_str()).fetch_all(&mut *tx).await?;
+
+        for row in &rows {
+            let id: i64 = row.get(0);
+            let name: String = row.get(1);
+
+            let query = Query::update()
+                .table(Products::Table)
+                .value(Products::SearchText, SearchText::new(&name).indexed())
+                .and_where(Expr::col(Products::Id).eq(id))
+                .to_string(PostgresQueryBuilder);
+
+            let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(rows.len())
+    }
 }
@@ -0,0 +1,249 @@
+use chrono::Utc;
+use sea_query::{Alias, Expr, Order, PostgresQueryBuilder, Query, SelectStatement, SimpleExpr};
+use sqlx::{Error, PgConnection, Row};
+
+use crate::repositories::Products;
+use crate::routes::{
+    NewProductVariant, ProductVariantJson, UpdateProductVariant, VariantOptionJson,
+};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum ProductVariants {
+    Table,
+    Id,
+    ProductId,
+    Sku,
+    Options,
+    Price,
+    OnHand,
+    Reserved,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
+
+/// Build a select statement of the product variants.
+/// The column order must be kept in sync with the `FromRow` implementation of
+/// `ProductVariantJson`.
+pub(crate) fn select_product_variants() -> SelectStatement {
+    Query::select()
+        .columns([
+            (ProductVariants::Table, ProductVariants::Id),
+            (ProductVariants::Table, ProductVariants::ProductId),
+            (ProductVariants::Table, ProductVariants::Sku),
+        ])
+        .expr(
+            Expr::col((ProductVariants::Table, ProductVariants::Options))
+                .cast_as(Alias::new("text")),
+        )
+        .columns([
+            (ProductVariants::Table, ProductVariants::Price),
+            (ProductVariants::Table, ProductVariants::OnHand),
+            (ProductVariants::Table, ProductVariants::Reserved),
+            (ProductVariants::Table, ProductVariants::CreatedAt),
+            (ProductVariants::Table, ProductVariants::UpdatedAt),
+            (ProductVariants::Table, ProductVariants::DeletedAt),
+        ])
+        .column((Products::Table, Products::Currency))
+        .from(ProductVariants::Table)
+        .inner_join(
+            Products::Table,
+            Expr::col((Products::Table, Products::Id))
+                .equals((ProductVariants::Table, ProductVariants::ProductId)),
+        )
+        .to_owned()
+}
+
+/// List the non-deleted variants of the given products with the given connection.
+pub(crate) async fn list_variants_of_products(
+    conn: &mut PgConnection,
+    product_ids: Vec<i64>,
+) -> Result<Vec<ProductVariantJson>, Error> {
+    if product_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let query = select_product_variants()
+        .and_where(
+            Expr::col((ProductVariants::Table, ProductVariants::ProductId)).is_in(product_ids),
+        )
+        .and_where(Expr::col((ProductVariants::Table, ProductVariants::DeletedAt)).is_null())
+        .order_by(
+            (ProductVariants::Table, ProductVariants::CreatedAt),
+            Order::Asc,
+        )
+        .order_by((ProductVariants::Table, ProductVariants::Id), Order::Asc)
+        .to_string(PostgresQueryBuilder);
+
+    sqlx::query_as::<_, ProductVariantJson>(query.as_str())
+        .fetch_all(&mut *conn)
+        .await
+}
+
+/// Move the on-hand and reserved quantities of a variant like `change_product_stock`.
+pub(crate) async fn change_variant_stock(
+    conn: &mut PgConnection,
+    variant_id: i64,
+    on_hand_delta: i32,
+    reserved_delta: i32,
+    reject_oversell: bool,
+) -> Result<bool, Error> {
+    if on_hand_delta == 0 && reserved_delta == 0 {
+        return Ok(true);
+    }
+
+    let on_hand = Expr::col(ProductVariants::OnHand).add(on_hand_delta);
+    let reserved = Expr::col(ProductVariants::Reserved).add(reserved_delta);
+
+    let mut query = Query::update()
+        .table(ProductVariants::Table)
+        .values([
+            (ProductVariants::OnHand, on_hand.clone()),
+            (ProductVariants::Reserved, reserved.clone()),
+        ])
+        .and_where(Expr::col(ProductVariants::Id).eq(variant_id))
+        .to_owned();
+
+    if reject_oversell {
+        query.and_where(Expr::expr(on_hand.clone().sub(reserved)).gte(0));
+    }
+
+    // The on-hand stock is never taken below zero, whether overselling is allowed or not.
+    if on_hand_delta < 0 {
+        query.and_where(Expr::expr(on_hand).gte(0));
+    }
+
+    let query = query.to_string(PostgresQueryBuilder);
+
+    let res = sqlx::query(query.as_str()).execute(&mut *conn).await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+/// Encode the options of a variant as a jsonb value.
+fn options_value(options: &[VariantOptionJson]) -> SimpleExpr {
+    let options = serde_json::to_string(options).expect("Failed to serialize variant options");
+    Expr::val(options).cast_as(Alias::new("jsonb"))
+}
+
+#[async_trait::async_trait]
+pub trait ProductVariantRepo {
+    async fn get(&self, id: i64) -> Result<Option<ProductVariantJson>, Error>;
+
+    async fn create(&self, new_variant: NewProductVariant) -> Result<i64, Error>;
+
+    async fn update(&self, update_variant: UpdateProductVariant) -> Result<(), Error>;
+
+    async fn delete(&self, id: i64) -> Result<(), Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresProductVariantRepo {
+    session: PostgresSession,
+}
+
+impl PostgresProductVariantRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProductVariantRepo for PostgresProductVariantRepo {
+    #[tracing::instrument(name = "Get a product variant from database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<ProductVariantJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_product_variants()
+            .and_where(Expr::col((ProductVariants::Table, ProductVariants::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        sqlx::query_as::<_, ProductVariantJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await
+    }
+
+    #[tracing::instrument(name = "Save a new product variant into database", skip(self))]
+    async fn create(&self, new_variant: NewProductVariant) -> Result<i64, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::insert()
+            .into_table(ProductVariants::Table)
+            .columns([
+                ProductVariants::Id,
+                ProductVariants::ProductId,
+                ProductVariants::Sku,
+                ProductVariants::Options,
+                ProductVariants::Price,
+                ProductVariants::CreatedAt,
+                ProductVariants::UpdatedAt,
+            ])
+            .values_panic([
+                new_variant.id.into(),
+                new_variant.product_id.into(),
+                new_variant.sku.0.into(),
+                options_value(&new_variant.options.0),
+                new_variant.price.map(|e| e.0.amount).into(),
+                Utc::now().into(),
+                Utc::now().into(),
+            ])
+            .returning(Query::returning().column(ProductVariants::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let res = sqlx::query(query.as_str()).fetch_one(conn.as_mut()).await?;
+
+        Ok(res.get(0))
+    }
+
+    #[tracing::instrument(name = "Update a product variant in database", skip(self))]
+    async fn update(&self, update_variant: UpdateProductVariant) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = {
+            let mut update_data = vec![];
+
+            if let Some(sku) = update_variant.sku {
+                update_data.push((ProductVariants::Sku, sku.0.into()));
+            }
+
+            if let Some(options) = update_variant.options {
+                update_data.push((ProductVariants::Options, options_value(&options.0)));
+            }
+
+            if let Some(price) = update_variant.price {
+                update_data.push((ProductVariants::Price, price.0.amount.into()));
+            }
+
+            update_data.push((ProductVariants::UpdatedAt, Utc::now().into()));
+
+            Query::update()
+                .table(ProductVariants::Table)
+                .values(update_data)
+                .and_where(
+                    Expr::col((ProductVariants::Table, ProductVariants::Id)).eq(update_variant.id),
+                )
+                .to_string(PostgresQueryBuilder)
+        };
+
+        let _ = sqlx::query(query.as_str()).execute(conn.as_mut()).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Delete a product variant from database", skip(self))]
+    async fn delete(&self, id: i64) -> Result<(), Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::update()
+            .table(ProductVariants::Table)
+            .values([(ProductVariants::DeletedAt, Utc::now().into())])
+            .and_where(Expr::col((ProductVariants::Table, ProductVariants::Id)).eq(id))
+            .and_where(Expr::col((ProductVariants::Table, ProductVariants::DeletedAt)).is_null())
+            .to_string(PostgresQueryBuilder);
+
+        let _ = sqlx::query(query.as_str()).execute(conn.as_mut()).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+use chrono::Utc;
+use sea_query::{Expr, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sqlx::{Connection, Error, PgConnection};
+
+use crate::errors::ShipmentError;
+use crate::repositories::{change_order_item_statuses, select_order_items, OrderItems};
+use crate::routes::{
+    ArriveShipment, NewShipment, OrderItemJson, OrderItemStatus, OrderItemStatusChangeJson,
+    ShipmentJson, ShipmentSearchParameters,
+};
+use crate::utils::PostgresSession;
+
+#[derive(sea_query::Iden)]
+pub(crate) enum Shipments {
+    Table,
+    Id,
+    Carrier,
+    TrackingNumber,
+    ShippedAt,
+    ExpectedArrivalAt,
+    ArrivedAt,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
+
+/// Build a select statement of the shipments.
+/// The column order must be kept in sync with the `FromRow` implementation of `ShipmentJson`.
+fn select_shipments() -> SelectStatement {
+    Query::select()
+        .columns([
+            (Shipments::Table, Shipments::Id),
+            (Shipments::Table, Shipments::Carrier),
+            (Shipments::Table, Shipments::TrackingNumber),
+            (Shipments::Table, Shipments::ShippedAt),
+            (Shipments::Table, Shipments::ExpectedArrivalAt),
+            (Shipments::Table, Shipments::ArrivedAt),
+            (Shipments::Table, Shipments::CreatedBy),
+            (Shipments::Table, Shipments::CreatedAt),
+            (Shipments::Table, Shipments::UpdatedAt),
+            (Shipments::Table, Shipments::DeletedAt),
+        ])
+        .from(Shipments::Table)
+        .to_owned()
+}
+
+/// Fill the non-deleted order items of the shipments.
+async fn fill_order_items(
+    conn: &mut PgConnection,
+    shipments: &mut [ShipmentJson],
+) -> Result<(), Error> {
+    if shipments.is_empty() {
+        return Ok(());
+    }
+
+    let shipment_ids = shipments
+        .iter()
+        .map(|e| e.id.parse::<i64>().map_err(|e| Error::Decode(e.into())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let query = select_order_items()
+        .and_where(Expr::col((OrderItems::Table, OrderItems::ShipmentId)).is_in(shipment_ids))
+        .and_where(Expr::col((OrderItems::Table, OrderItems::DeletedAt)).is_null())
+        .order_by((OrderItems::Table, OrderItems::CreatedAt), Order::Asc)
+        .order_by((OrderItems::Table, OrderItems::Id), Order::Asc)
+        .to_string(PostgresQueryBuilder);
+
+    let mut order_items = sqlx::query_as::<_, OrderItemJson>(query.as_str())
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for shipment in shipments.iter_mut() {
+        let (items, rest) = order_items
+            .into_iter()
+            .partition(|e| e.shipment_id.as_ref() == Some(&shipment.id));
+        order_items = rest;
+        shipment.order_items = items;
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+pub trait ShipmentRepo {
+    async fn get(&self, id: i64) -> Result<Option<ShipmentJson>, Error>;
+
+    /// Return `OrderItemNotShippable` when an order item is packed or deleted by others
+    /// meanwhile.
+    async fn create(
+        &self,
+        new_shipment: NewShipment,
+        created_by: uuid::Uuid,
+    ) -> Result<Result<i64, ShipmentError>, Error>;
+
+    /// Mark the shipment as arrived and move its order items to `arrived`. Return
+    /// `ShipmentIsArrived` when the shipment is marked by others meanwhile.
+    async fn arrive(
+        &self,
+        arrive_shipment: ArriveShipment,
+        changed_by: uuid::Uuid,
+    ) -> Result<Result<Vec<OrderItemStatusChangeJson>, ShipmentError>, Error>;
+
+    async fn list(
+        &self,
+        param: ShipmentSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<ShipmentJson>, Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresShipmentRepo {
+    session: PostgresSession,
+}
+
+impl PostgresShipmentRepo {
+    pub fn new(session: PostgresSession) -> Self {
+        Self { session }
+    }
+}
+
+#[async_trait::async_trait]
+impl ShipmentRepo for PostgresShipmentRepo {
+    #[tracing::instrument(name = "Get the shipment from the database", skip(self))]
+    async fn get(&self, id: i64) -> Result<Option<ShipmentJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = select_shipments()
+            .and_where(Expr::col((Shipments::Table, Shipments::Id)).eq(id))
+            .to_string(PostgresQueryBuilder);
+
+        let shipment = sqlx::query_as::<_, ShipmentJson>(query.as_str())
+            .fetch_optional(conn.as_mut())
+            .await?;
+
+        match shipment {
+            None => Ok(None),
+            Some(shipment) => {
+                let mut shipments = [shipment];
+                fill_order_items(conn.as_mut(), &mut shipments).await?;
+                let [shipment] = shipments;
+                Ok(Some(shipment))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Save a new shipment into database", skip(self))]
+    async fn create(
+        &self,
+        new_shipment: NewShipment,
+        created_by: uuid::Uuid,
+    ) -> Result<Result<i64, ShipmentError>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let now = Utc::now();
+
+        let query = Query::insert()
+            .into_table(Shipments::Table)
+            .columns([
+                Shipments::Id,
+                Shipments::Carrier,
+                Shipments::TrackingNumber,
+                Shipments::ShippedAt,
+                Shipments::ExpectedArrivalAt,
+                Shipments::CreatedBy,
+                Shipments::CreatedAt,
+            ])
+            .values_panic([
+                new_shipment.id.into(),
+                new_shipment.carrier.into(),
+                new_shipment.tracking_number.into(),
+                new_shipment.shipped_at.into(),
+                new_shipment.expected_arrival_at.into(),
+                created_by.to_string().into(),
+                now.into(),
+            ])
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let _ = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        // An order item can only be packed in one shipment, and the status is checked again here
+        // in case the order item is cancelled concurrently.
+        let query = Query::update()
+            .table(OrderItems::Table)
+            .values([
+                (OrderItems::ShipmentId, new_shipment.id.into()),
+                (OrderItems::UpdatedAt, now.into()),
+            ])
+            .and_where(Expr::col(OrderItems::Id).is_in(new_shipment.order_item_ids.clone()))
+            .and_where(Expr::col(OrderItems::ShipmentId).is_null())
+            .and_where(Expr::col(OrderItems::DeletedAt).is_null())
+            .and_where(Expr::col(OrderItems::Status).is_in([
+                OrderItemStatus::PurchasedInJapan.code(),
+                OrderItemStatus::InTransit.code(),
+            ]))
+            .returning(Query::returning().column(OrderItems::Id))
+            .to_string(PostgresQueryBuilder);
+
+        let packed_ids = sqlx::query_scalar::<_, i64>(query.as_str())
+            .fetch_all(&mut *tx)
+            .await?;
+
+        if let Some(id) = new_shipment
+            .order_item_ids
+            .iter()
+            .find(|e| !packed_ids.contains(e))
+        {
+            return Ok(Err(ShipmentError::OrderItemNotShippable(*id)));
+        }
+
+        let condition = Expr::col((OrderItems::Table, OrderItems::Id))
+            .is_in(new_shipment.order_item_ids)
+            .and(
+                Expr::col((OrderItems::Table, OrderItems::Status))
+                    .eq(OrderItemStatus::PurchasedInJapan.code()),
+            );
+        change_order_item_statuses(&mut tx, condition, OrderItemStatus::InTransit, created_by)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Ok(new_shipment.id))
+    }
+
+    #[tracing::instrument(name = "Mark a shipment as arrived in database", skip(self))]
+    async fn arrive(
+        &self,
+        arrive_shipment: ArriveShipment,
+        changed_by: uuid::Uuid,
+    ) -> Result<Result<Vec<OrderItemStatusChangeJson>, ShipmentError>, Error> {
+        let mut conn = self.session.get_session().await?;
+
+        let query = Query::update()
+            .table(Shipments::Table)
+            .values([
+                (Shipments::ArrivedAt, arrive_shipment.arrived_at.into()),
+                (Shipments::UpdatedAt, Utc::now().into()),
+            ])
+            .and_where(Expr::col(Shipments::Id).eq(arrive_shipment.id))
+            .and_where(Expr::col(Shipments::ArrivedAt).is_null())
+            .to_string(PostgresQueryBuilder);
+
+        let mut tx = conn.as_mut().begin().await?;
+
+        let res = sqlx::query(query.as_str()).execute(&mut *tx).await?;
+
+        if res.rows_affected() != 1 {
+            return Ok(Err(ShipmentError::ShipmentIsArrived));
+        }
+
+        let condition =
+            Expr::col((OrderItems::Table, OrderItems::ShipmentId)).eq(arrive_shipment.id);
+        let results =
+            change_order_item_statuses(&mut tx, condition, OrderItemStatus::Arrived, changed_by)
+                .await?;
+
+        tx.commit().await?;
+
+        Ok(Ok(results))
+    }
+
+    #[tracing::instrument(name = "List shipments from database", skip(self))]
+    async fn list(
+        &self,
+        param: ShipmentSearchParameters,
+        page: u64,
+        page_size: u64,
+    ) -> Result<Vec<ShipmentJson>, Error> {
+        let mut conn = self.session.get_session().await?;
+        let offset = page * page_size;
+
+        let query = select_shipments()
+            .and_where_option(
+                param
+                    .carrier
+                    .map(|e| Expr::col((Shipments::Table, Shipments::Carrier)).eq(e)),
+            )
+            .and_where_option(
+                param
+                    .tracking_number
+                    .map(|e| Expr::col((Shipments::Table, Shipments::TrackingNumber)).eq(e)),
+            )
+            .and_where_option(param.order_item_id.map(|e| {
+                Expr::col((Shipments::Table, Shipments::Id)).in_subquery(
+                    Query::select()
+                        .column(OrderItems::ShipmentId)
+                        .from(OrderItems::Table)
+                        .and_where(Expr::col(OrderItems::Id).eq(e))
+                        .to_owned(),
+                )
+            }))
+            .and_where_option(param.arrived.map(|arrived| {
+                let col = Expr::col((Shipments::Table, Shipments::ArrivedAt));
+                if arrived {
+                    col.is_not_null()
+                } else {
+                    col.is_null()
+                }
+            }))
+            .and_where_option(param.deleted.map(|deleted| {
+                let col = Expr::col((Shipments::Table, Shipments::DeletedAt));
+                if deleted {
+                    col.is_not_null()
+                } else {
+                    col.is_null()
+                }
+            }))
+            .order_by((Shipments::Table, Shipments::CreatedAt), Order::Desc)
+            .order_by((Shipments::Table, Shipments::Id), Order::Desc)
+            .offset(offset)
+            .limit(page_size)
+            .to_string(PostgresQueryBuilder);
+
+        let mut shipments = sqlx::query_as::<_, ShipmentJson>(query.as_str())
+            .fetch_all(conn.as_mut())
+            .await?;
+
+        fill_order_items(conn.as_mut(), &mut shipments).await?;
+
+        Ok(shipments)
+    }
+}
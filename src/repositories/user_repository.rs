@@ -48,7 +48,7 @@ impl UserRepo for PostgresUserRepoImpl {
         &self,
         username: &str,
     ) -> Result<Option<(uuid::Uuid, Secret<String>)>, anyhow::Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = Query::select()
             .columns([Users::Id, Users::PasswordHash])
@@ -71,7 +71,7 @@ impl UserRepo for PostgresUserRepoImpl {
     }
 
     async fn get_username(&self, user_id: &str) -> Result<String, anyhow::Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = Query::select()
             .column(Users::Username)
@@ -94,7 +94,7 @@ impl UserRepo for PostgresUserRepoImpl {
         id: uuid::Uuid,
         password: Secret<String>,
     ) -> Result<bool, anyhow::Error> {
-        let mut conn = self.session.get_session().await;
+        let mut conn = self.session.get_session().await?;
 
         let query = Query::update()
             .table(Users::Table)
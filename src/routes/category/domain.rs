@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+use crate::routes::category_id_generator;
+use crate::utils::deserialize_nullable;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CategoryJson {
+    pub id: String,
+    /// The root categories have no parent.
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateCategoryRequest {
+    pub parent_id: Option<String>,
+    pub name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateCategoryResponse {
+    pub id: i64,
+}
+
+/// Rename the category or move it under another parent.
+#[derive(serde::Deserialize, Debug)]
+pub struct UpdateCategoryRequest {
+    pub id: String,
+    /// A `null` parent moves the category to the root, a missing one keeps it unchanged.
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub parent_id: Option<Option<String>>,
+    pub name: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeleteCategoryRequest {
+    pub id: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListCategoriesResponse {
+    pub data: Vec<CategoryJson>,
+}
+
+#[derive(Debug)]
+pub struct ValidCategoryName(pub String);
+
+#[derive(Debug)]
+pub struct NewCategory {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub name: ValidCategoryName,
+}
+
+#[derive(Debug)]
+pub struct UpdateCategory {
+    pub id: i64,
+    pub parent_id: Option<Option<i64>>,
+    pub name: Option<ValidCategoryName>,
+}
+
+impl ValidCategoryName {
+    pub fn parse(name: String) -> Result<Self, String> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err("Category name is empty.".to_string());
+        }
+
+        if name.chars().count() > 128 {
+            return Err("Category name can't be longer than 128 characters.".to_string());
+        }
+
+        Ok(Self(name.to_owned()))
+    }
+}
+
+impl NewCategory {
+    pub fn parse(req: CreateCategoryRequest) -> Result<Self, String> {
+        let name = ValidCategoryName::parse(req.name)?;
+        let parent_id = req
+            .parent_id
+            .map(|e| e.parse::<i64>())
+            .transpose()
+            .map_err(|_| "Can't parse parent id to i64.".to_string())?;
+
+        let id = {
+            let generator = category_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            parent_id,
+            name,
+        })
+    }
+}
+
+impl UpdateCategory {
+    pub fn parse(req: UpdateCategoryRequest) -> Result<Self, String> {
+        let id = req
+            .id
+            .parse::<i64>()
+            .map_err(|_| "Can't parse id to i64.".to_string())?;
+        let parent_id = req
+            .parent_id
+            .map(|e| e.map(|e| e.parse::<i64>()).transpose())
+            .transpose()
+            .map_err(|_| "Can't parse parent id to i64.".to_string())?;
+
+        if parent_id == Some(Some(id)) {
+            return Err("A category can't be the parent of itself.".to_string());
+        }
+
+        Ok(Self {
+            id,
+            parent_id,
+            name: req.name.map(ValidCategoryName::parse).transpose()?,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for CategoryJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let parent_id: Option<i64> = row.try_get(1)?;
+        let name: String = row.try_get(2)?;
+        let created_at: DateTime<Utc> = row.try_get(3)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(4)?;
+        let deleted_at: Option<DateTime<Utc>> = row.try_get(5)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            parent_id: parent_id.map(|e| e.to_string()),
+            name,
+            created_at,
+            updated_at,
+            deleted_at,
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+
+use crate::errors::{AppError, CategoryError, ConstraintError, DatabaseResultExt};
+use crate::repositories::CategoryRepo;
+use crate::routes::{
+    Claims, CreateCategoryRequest, CreateCategoryResponse, DeleteCategoryRequest,
+    ListCategoriesResponse, NewCategory, UpdateCategory, UpdateCategoryRequest,
+};
+
+/// The foreign key can't see the soft-deleted categories, so the parent is checked here.
+async fn check_parent(
+    category_repo: &Arc<dyn CategoryRepo + Sync + Send>,
+    parent_id: i64,
+) -> Result<(), AppError> {
+    category_repo
+        .get(parent_id)
+        .await
+        .context("Failed to get a category from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ConstraintError::ReferenceNotExist("category"))?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Create a new category", skip(category_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_category_handler(
+    claims: Claims,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateCategoryRequest>, AppError>,
+) -> Result<Json<CreateCategoryResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let new_category = NewCategory::parse(payload).map_err(AppError::BadArguments)?;
+
+    if let Some(parent_id) = new_category.parent_id {
+        check_parent(&category_repo, parent_id).await?;
+    }
+
+    let id = category_repo
+        .create(new_category)
+        .await
+        .db_context("Failed to insert a new category in the database")?;
+
+    Ok(Json(CreateCategoryResponse { id }))
+}
+
+#[tracing::instrument(name = "Get a category", skip(category_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn get_category_handler(
+    claims: Claims,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let category_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no category id in the query string".to_string())
+        })?;
+
+    let category = category_repo
+        .get(category_id)
+        .await
+        .context("Failed to get a category from database")?
+        .ok_or(CategoryError::CategoryNotFound)?;
+
+    Ok(Json(category))
+}
+
+#[tracing::instrument(name = "Update a category", skip(category_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn update_category_handler(
+    claims: Claims,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateCategoryRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let update_category = UpdateCategory::parse(payload).map_err(AppError::BadArguments)?;
+
+    category_repo
+        .get(update_category.id)
+        .await
+        .context("Failed to get a category from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(CategoryError::CategoryNotFound)?;
+
+    if let Some(Some(parent_id)) = update_category.parent_id {
+        check_parent(&category_repo, parent_id).await?;
+    }
+
+    let need_update = update_category.parent_id.is_some() || update_category.name.is_some();
+
+    if need_update {
+        category_repo
+            .update(update_category)
+            .await
+            .db_context("Failed to update a category in the database")??;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Delete a category", skip(category_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn delete_category_handler(
+    claims: Claims,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteCategoryRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    category_repo
+        .delete(id)
+        .await
+        .context("Failed to delete a category in the database")??;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "List categories", skip(category_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_categories_handler(
+    claims: Claims,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+) -> Result<Json<ListCategoriesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let data = category_repo
+        .list()
+        .await
+        .context("Failed to get categories from database")?;
+
+    Ok(Json(ListCategoriesResponse { data }))
+}
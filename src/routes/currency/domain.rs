@@ -0,0 +1,183 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+/// A currency identified by its ISO 4217 numeric code, e.g. 344 for HKD.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CurrencyJson {
+    pub id: i16,
+    /// The ISO 4217 alphabetic code.
+    pub code: String,
+    pub symbol: String,
+    /// The number of decimal places of the amounts, e.g. 0 for JPY.
+    pub minor_units: i16,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateCurrencyRequest {
+    pub id: i16,
+    pub code: String,
+    pub symbol: String,
+    pub minor_units: i16,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateCurrencyResponse {
+    pub id: i16,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListCurrenciesResponse {
+    pub data: Vec<CurrencyJson>,
+}
+
+#[derive(Debug)]
+pub struct ValidCurrencyCode(pub String);
+
+/// An amount of a currency, which has at most as many decimal places as the minor units of the
+/// currency. The amounts are exchanged as decimal strings in JSON, e.g. `"19.99"`, so that they
+/// never drift like floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: i16,
+}
+
+#[derive(Debug)]
+pub struct NewCurrency {
+    pub id: i16,
+    pub code: ValidCurrencyCode,
+    pub symbol: String,
+    pub minor_units: i16,
+}
+
+impl CurrencyJson {
+    /// Find the currency among the known currencies.
+    pub fn find(id: i16, currencies: &[CurrencyJson]) -> Result<&CurrencyJson, String> {
+        currencies
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or(format!("{id} is not a known currency."))
+    }
+}
+
+impl Money {
+    /// The amounts are stored as `decimal(12, 2)`, so they must be less than 10^10.
+    pub fn upper_bound() -> Decimal {
+        Decimal::from(10_000_000_000_i64)
+    }
+
+    /// Parse an amount given by a user, it can't be negative, too large nor be more precise than
+    /// the minor units of the currency, e.g. `19.5` is refused in JPY.
+    pub fn parse(amount: Decimal, currency: &CurrencyJson) -> Result<Self, String> {
+        let amount = amount.normalize();
+
+        if amount < Decimal::ZERO {
+            return Err(format!(
+                "The amount in {} can't be negative.",
+                currency.code
+            ));
+        }
+
+        if amount >= Self::upper_bound() {
+            return Err(format!(
+                "The amount in {} must be less than {}.",
+                currency.code,
+                Self::upper_bound()
+            ));
+        }
+
+        if amount.scale() > currency.minor_units.max(0) as u32 {
+            return Err(format!(
+                "The amount in {} can't have more than {} decimal places.",
+                currency.code, currency.minor_units
+            ));
+        }
+
+        Ok(Self {
+            amount,
+            currency: currency.id,
+        })
+    }
+
+    /// Round a computed amount to the minor units of the currency, half away from zero.
+    pub fn round(amount: Decimal, currency: &CurrencyJson) -> Self {
+        Self {
+            amount: amount.round_dp_with_strategy(
+                currency.minor_units.max(0) as u32,
+                RoundingStrategy::MidpointAwayFromZero,
+            ),
+            currency: currency.id,
+        }
+    }
+}
+
+/// Only the amount is exchanged, the currency is given by the `currency` field next to it, so a
+/// deserialized amount has the currency `0` which isn't any known currency.
+impl serde::Serialize for Money {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.amount)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Money {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Decimal as serde::Deserialize>::deserialize(deserializer).map(|amount| Self {
+            amount,
+            currency: 0,
+        })
+    }
+}
+
+impl ValidCurrencyCode {
+    pub fn parse(code: String) -> Result<Self, String> {
+        let code = code.trim().to_uppercase();
+
+        if code.len() != 3 || !code.chars().all(|e| e.is_ascii_uppercase()) {
+            return Err("Currency code should be 3 letters.".to_string());
+        }
+
+        Ok(Self(code))
+    }
+}
+
+impl NewCurrency {
+    pub fn parse(req: CreateCurrencyRequest) -> Result<Self, String> {
+        if !(1..=999).contains(&req.id) {
+            return Err("Currency id should be between 1 and 999.".to_string());
+        }
+
+        let symbol = req.symbol.trim();
+
+        if symbol.is_empty() || symbol.chars().count() > 8 {
+            return Err("Currency symbol should have 1 to 8 characters.".to_string());
+        }
+
+        if !(0..=4).contains(&req.minor_units) {
+            return Err("Minor units should be between 0 and 4.".to_string());
+        }
+
+        Ok(Self {
+            id: req.id,
+            code: ValidCurrencyCode::parse(req.code)?,
+            symbol: symbol.to_owned(),
+            minor_units: req.minor_units,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for CurrencyJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i16 = row.try_get(0)?;
+        let code: String = row.try_get(1)?;
+        let symbol: String = row.try_get(2)?;
+        let minor_units: i16 = row.try_get(3)?;
+
+        Ok(Self {
+            id,
+            code,
+            symbol,
+            minor_units,
+        })
+    }
+}
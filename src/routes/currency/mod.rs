@@ -0,0 +1,5 @@
+pub use domain::*;
+pub use route::*;
+
+mod domain;
+mod route;
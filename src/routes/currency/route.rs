@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+
+use crate::errors::{AppError, DatabaseResultExt};
+use crate::repositories::CurrencyRepo;
+use crate::routes::{
+    Claims, CreateCurrencyRequest, CreateCurrencyResponse, ListCurrenciesResponse, NewCurrency,
+};
+
+#[tracing::instrument(name = "Create a new currency", skip(currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_currency_handler(
+    claims: Claims,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateCurrencyRequest>, AppError>,
+) -> Result<Json<CreateCurrencyResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let new_currency = NewCurrency::parse(payload).map_err(AppError::BadArguments)?;
+
+    let id = currency_repo
+        .create(new_currency)
+        .await
+        .db_context("Failed to insert a new currency in the database")?;
+
+    Ok(Json(CreateCurrencyResponse { id }))
+}
+
+#[tracing::instrument(name = "List currencies", skip(currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_currencies_handler(
+    claims: Claims,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+) -> Result<Json<ListCurrenciesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let data = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    Ok(Json(ListCurrenciesResponse { data }))
+}
@@ -2,8 +2,11 @@ use chrono::{DateTime, Utc};
 use sqlx::postgres::PgRow;
 use sqlx::Row;
 
-use crate::routes::customer_id_generator;
-use crate::utils::get_phone_number_regex;
+use crate::routes::{
+    customer_contact_method_id_generator, customer_id_generator, customer_merge_id_generator,
+    CurrencyTotalJson, OrderItemJson,
+};
+use crate::utils::SearchText;
 use validator::ValidateEmail;
 
 #[derive(Clone, Debug)]
@@ -72,16 +75,104 @@ pub struct CustomerJson {
     pub id: String,
     pub name: String,
     pub email: Option<String>,
+    /// The phone number in E.164, e.g. "+85361235678".
     pub phone: Option<String>,
+    /// The phone number formatted for display, e.g. "+853 6123 5678".
+    pub phone_display: Option<String>,
     pub remark: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub contact_methods: Vec<ContactMethodJson>,
+}
+
+/// The channel through which a customer can be reached. The discriminant is the code stored in
+/// `customer_contact_methods.kind`, so the existing values must never be changed.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactMethodKind {
+    Phone = 0,
+    Email = 1,
+    Whatsapp = 2,
+    Wechat = 3,
+    Instagram = 4,
+    Facebook = 5,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ContactMethodJson {
+    pub id: String,
+    pub customer_id: String,
+    pub kind: ContactMethodKind,
+    /// The normalized value, e.g. a phone number in E.164 or an Instagram handle without `@`.
+    pub value: String,
+    pub preferred: bool,
+    pub verified: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateContactMethodRequest {
+    pub customer_id: i64,
+    pub kind: ContactMethodKind,
+    pub value: String,
+    pub preferred: Option<bool>,
+    pub verified: Option<bool>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateContactMethodResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct UpdateContactMethodRequest {
+    pub id: String,
+    pub value: Option<String>,
+    pub preferred: Option<bool>,
+    pub verified: Option<bool>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeleteContactMethodRequest {
+    pub id: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListContactMethodsResponse {
+    pub data: Vec<ContactMethodJson>,
+}
+
+#[derive(Debug)]
+pub struct ValidContactValue(pub String);
+
+/// A contact method which becomes the only preferred one of the customer when `preferred` is
+/// set.
+#[derive(Debug)]
+pub struct NewContactMethod {
+    pub id: i64,
+    pub customer_id: i64,
+    pub kind: ContactMethodKind,
+    pub value: ValidContactValue,
+    pub preferred: bool,
+    pub verified: bool,
+}
+
+#[derive(Debug)]
+pub struct UpdateContactMethod {
+    pub id: i64,
+    pub value: Option<ValidContactValue>,
+    pub preferred: Option<bool>,
+    pub verified: Option<bool>,
 }
 
 #[derive(serde::Deserialize, Debug)]
 pub struct ListCustomersRequest {
     pub keyword: Option<String>,
+    /// The free-text search, which takes precedence over the `q` of the keyword.
+    pub q: Option<String>,
     pub page: Option<u64>,
     pub page_size: Option<u64>,
 }
@@ -91,6 +182,22 @@ pub struct ListCustomersResponse {
     pub data: Vec<CustomerJson>,
 }
 
+#[derive(serde::Deserialize, Debug)]
+pub struct ListCustomerOrderItemsRequest {
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CustomerOrderItemsResponse {
+    pub customer: CustomerJson,
+    pub order_items: Vec<OrderItemJson>,
+    pub totals: Vec<CurrencyTotalJson>,
+    /// The totals of all the currencies in the base currency, it's `None` when an exchange
+    /// rate is missing.
+    pub total: Option<CurrencyTotalJson>,
+}
+
 #[derive(serde::Deserialize, Default, Debug)]
 pub struct CustomerSearchParameters {
     pub id: Option<i64>,
@@ -102,6 +209,104 @@ pub struct CustomerSearchParameters {
     pub partial_phone: Option<String>,
     #[serde(rename(deserialize = "remark"))]
     pub partial_remark: Option<String>,
+    /// Match every word against any of the name, email, phone, remark and contact methods,
+    /// e.g. "Chan 5678".
+    pub q: Option<String>,
+    /// Match the customers which have a contact method containing the text.
+    pub contact: Option<String>,
+    /// Only match the contact methods of the kind with `contact`, or match the customers which
+    /// have any contact method of the kind.
+    pub contact_kind: Option<ContactMethodKind>,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct ListCustomerDuplicatesRequest {
+    /// Only suggest the duplicates of the customer.
+    pub customer_id: Option<i64>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+/// Why two customers are likely the same person.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomerDuplicateReason {
+    SamePhone,
+    SameEmail,
+    SimilarName,
+}
+
+/// Two non-deleted customers which are likely the same person, the older one comes first.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CustomerDuplicateJson {
+    pub customer: CustomerJson,
+    pub duplicate: CustomerJson,
+    pub reasons: Vec<CustomerDuplicateReason>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListCustomerDuplicatesResponse {
+    pub data: Vec<CustomerDuplicateJson>,
+}
+
+/// A phone number which can't be normalized, it's left unchanged for the staff to correct.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct UnnormalizedPhoneJson {
+    pub customer_id: String,
+    pub phone: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct NormalizePhonesResponse {
+    pub normalized: usize,
+    pub unchanged: Vec<UnnormalizedPhoneJson>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct MergeCustomersRequest {
+    /// The customer which is soft-deleted after the merge.
+    pub from_customer_id: i64,
+    /// The customer which takes over the references of the other one.
+    pub into_customer_id: i64,
+}
+
+/// The audit record of a merge, with the numbers of the moved references.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CustomerMergeJson {
+    pub id: String,
+    pub from_customer_id: String,
+    pub into_customer_id: String,
+    pub order_items_moved: i32,
+    pub orders_moved: i32,
+    pub payments_moved: i32,
+    pub contact_methods_moved: i32,
+    pub merged_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct NewCustomerMerge {
+    pub id: i64,
+    pub from_customer_id: i64,
+    pub into_customer_id: i64,
+}
+
+/// A word of the free-text search of customers.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CustomerQueryTerm {
+    /// The normalized word.
+    pub text: String,
+    /// The digits of the word when it looks like a part of a phone number.
+    pub phone_digits: Option<String>,
+}
+
+/// The free-text search of customers. A phone number such as "+853 6123-5678" is kept as one
+/// term, otherwise the text is split into words.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CustomerQuery {
+    /// The whole normalized text, which is compared for the exact matches.
+    pub text: String,
+    pub terms: Vec<CustomerQueryTerm>,
 }
 
 impl ValidEmail {
@@ -114,24 +319,371 @@ impl ValidEmail {
     }
 }
 
+/// A country whose phone numbers are accepted. The national numbers have the given lengths
+/// after the trunk prefix is removed, and they are displayed in the given groups of digits.
+struct PhoneCountry {
+    code: &'static str,
+    trunk_prefix: Option<char>,
+    min_len: usize,
+    max_len: usize,
+    groups: &'static [usize],
+}
+
+/// Macau, Hong Kong, mainland China, Taiwan and Japan.
+const PHONE_COUNTRIES: [PhoneCountry; 5] = [
+    PhoneCountry {
+        code: "853",
+        trunk_prefix: None,
+        min_len: 8,
+        max_len: 8,
+        groups: &[4, 4],
+    },
+    PhoneCountry {
+        code: "852",
+        trunk_prefix: None,
+        min_len: 8,
+        max_len: 8,
+        groups: &[4, 4],
+    },
+    PhoneCountry {
+        code: "86",
+        trunk_prefix: Some('0'),
+        min_len: 10,
+        max_len: 11,
+        groups: &[3, 4, 4],
+    },
+    PhoneCountry {
+        code: "886",
+        trunk_prefix: Some('0'),
+        min_len: 8,
+        max_len: 9,
+        groups: &[3, 3, 3],
+    },
+    PhoneCountry {
+        code: "81",
+        trunk_prefix: Some('0'),
+        min_len: 9,
+        max_len: 10,
+        groups: &[2, 4, 4],
+    },
+];
+
+impl PhoneCountry {
+    fn find(code: &str) -> Option<&'static Self> {
+        PHONE_COUNTRIES.iter().find(|e| e.code == code)
+    }
+
+    /// The country codes are prefix-free, so at most one of them matches.
+    fn find_prefix(digits: &str) -> Option<&'static Self> {
+        PHONE_COUNTRIES.iter().find(|e| digits.starts_with(e.code))
+    }
+}
+
 impl ValidPhone {
-    pub fn parse(s: String) -> Result<Self, String> {
-        dbg!(&s);
-        let re = get_phone_number_regex();
-        if re.is_match(&s) {
-            Ok(Self(s))
+    /// Check that the numbers of the country can be parsed, e.g. the default country.
+    pub fn check_country_code(code: u16) -> Result<(), String> {
+        PhoneCountry::find(&code.to_string())
+            .map(|_| ())
+            .ok_or_else(|| format!("The country code {code} is not supported."))
+    }
+
+    /// Parse a phone number into E.164. The country code is given after `+` or `00`, or in the
+    /// brackets at the beginning, otherwise the number is in the default country, e.g.
+    /// "(853) 6123 5678", "+852 9123-4567" and "090-1234-5678" in Japan become "+85361235678",
+    /// "+85291234567" and "+819012345678".
+    pub fn parse(s: String, default_country_code: u16) -> Result<Self, String> {
+        let invalid =
+            || format!("{s} is not a valid phone number. Please follow this format +853 6123 5678");
+
+        let value = s.trim();
+
+        if !value
+            .chars()
+            .all(|e| e.is_ascii_digit() || " +-.()".contains(e))
+        {
+            return Err(invalid());
+        }
+
+        let digits = value
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>();
+        let bracketed_code = value
+            .strip_prefix('(')
+            .and_then(|e| e.split_once(')'))
+            .and_then(|(code, _)| PhoneCountry::find(code.trim()));
+
+        let international = if value.starts_with('+') || bracketed_code.is_some() {
+            Some(digits.as_str())
+        } else {
+            digits.strip_prefix("00")
+        };
+
+        let (country, national) = match international {
+            Some(digits) => {
+                let country = PhoneCountry::find_prefix(digits)
+                    .ok_or_else(|| format!("The country code of {s} is not supported."))?;
+                (country, &digits[country.code.len()..])
+            }
+            None => {
+                let country =
+                    PhoneCountry::find(&default_country_code.to_string()).ok_or_else(|| {
+                        format!("The country code {default_country_code} is not supported.")
+                    })?;
+                let national = country
+                    .trunk_prefix
+                    .and_then(|e| digits.strip_prefix(e))
+                    .unwrap_or(&digits);
+                (country, national)
+            }
+        };
+
+        if national.len() < country.min_len
+            || national.len() > country.max_len
+            || national.starts_with('0')
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self(format!("+{}{national}", country.code)))
+    }
+
+    /// Format a phone number in E.164 for display, e.g. "+85361235678" becomes
+    /// "+853 6123 5678". The number is returned as it is when it isn't in E.164.
+    pub fn format(e164: &str) -> String {
+        let Some(country) = e164.strip_prefix('+').and_then(PhoneCountry::find_prefix) else {
+            return e164.to_string();
+        };
+
+        let national = &e164[country.code.len() + 1..];
+        let mut formatted = format!("+{}", country.code);
+
+        if country.groups.iter().sum::<usize>() == national.len() {
+            let mut rest = national;
+
+            for len in country.groups {
+                let (group, others) = rest.split_at(*len);
+                formatted.push(' ');
+                formatted.push_str(group);
+                rest = others;
+            }
         } else {
-            Err(format!(
-                "{s} is not a valid phone number. Please follow this format (853) 12345678"
-            ))
+            formatted.push(' ');
+            formatted.push_str(national);
+        }
+
+        formatted
+    }
+}
+
+impl CustomerQueryTerm {
+    fn new(text: &str) -> Self {
+        let is_phone = text
+            .chars()
+            .all(|e| e.is_ascii_digit() || " +-().".contains(e));
+        let digits = text
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>();
+
+        Self {
+            text: text.to_string(),
+            phone_digits: (is_phone && digits.len() >= 3).then_some(digits),
+        }
+    }
+}
+
+impl CustomerQuery {
+    /// Return `None` when there is nothing to search.
+    pub fn parse(q: &str) -> Option<Self> {
+        let text = SearchText::new(q).normalized;
+
+        if text.is_empty() {
+            return None;
+        }
+
+        let whole = CustomerQueryTerm::new(&text);
+        let terms = if whole.phone_digits.is_some() {
+            vec![whole]
+        } else {
+            text.split(|e: char| e.is_whitespace() || e == ',')
+                .filter(|e| !e.is_empty())
+                .map(CustomerQueryTerm::new)
+                .collect()
+        };
+
+        Some(Self { text, terms })
+    }
+}
+
+impl ContactMethodKind {
+    pub fn code(&self) -> i16 {
+        *self as i16
+    }
+}
+
+impl TryFrom<i16> for ContactMethodKind {
+    type Error = String;
+
+    fn try_from(code: i16) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(ContactMethodKind::Phone),
+            1 => Ok(ContactMethodKind::Email),
+            2 => Ok(ContactMethodKind::Whatsapp),
+            3 => Ok(ContactMethodKind::Wechat),
+            4 => Ok(ContactMethodKind::Instagram),
+            5 => Ok(ContactMethodKind::Facebook),
+            other => Err(format!("{other} is not a valid contact method kind")),
+        }
+    }
+}
+
+impl ValidContactValue {
+    /// Normalize the value by its kind, so that the same contact is always stored the same way.
+    /// The phone and WhatsApp numbers are in E.164, the others are lowercased.
+    pub fn parse(
+        kind: ContactMethodKind,
+        value: String,
+        default_country_code: u16,
+    ) -> Result<Self, String> {
+        let is_handle = |value: &str, extra: &str, max_len: usize| {
+            !value.is_empty()
+                && value.chars().count() <= max_len
+                && value
+                    .chars()
+                    .all(|e| e.is_ascii_alphanumeric() || extra.contains(e))
+        };
+
+        let normalized = value.trim().to_lowercase();
+
+        let valid = match kind {
+            ContactMethodKind::Phone | ContactMethodKind::Whatsapp => {
+                return ValidPhone::parse(value, default_country_code).map(|e| Self(e.0));
+            }
+            ContactMethodKind::Email => {
+                return ValidEmail::parse(normalized).map(|e| Self(e.0));
+            }
+            // A WeChat ID has 6 to 20 letters, digits, `_` or `-`, and starts with a letter.
+            ContactMethodKind::Wechat => {
+                normalized.starts_with(|e: char| e.is_ascii_alphabetic())
+                    && normalized.len() >= 6
+                    && is_handle(&normalized, "_-", 20)
+            }
+            ContactMethodKind::Instagram => {
+                let handle = normalized.trim_start_matches('@').to_string();
+                return if is_handle(&handle, "._", 30) {
+                    Ok(Self(handle))
+                } else {
+                    Err(format!("{value} is not a valid Instagram handle."))
+                };
+            }
+            // Either a username or the URL of a profile.
+            ContactMethodKind::Facebook => {
+                !normalized.is_empty()
+                    && normalized.len() <= 256
+                    && !normalized.contains(char::is_whitespace)
+            }
+        };
+
+        if valid {
+            Ok(Self(normalized))
+        } else {
+            Err(format!("{value} is not a valid {kind:?} contact."))
+        }
+    }
+}
+
+impl NewContactMethod {
+    pub fn parse(
+        req: CreateContactMethodRequest,
+        default_country_code: u16,
+    ) -> Result<Self, String> {
+        let value = ValidContactValue::parse(req.kind, req.value, default_country_code)?;
+
+        let id = {
+            let generator = customer_contact_method_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            customer_id: req.customer_id,
+            kind: req.kind,
+            value,
+            preferred: req.preferred.unwrap_or(false),
+            verified: req.verified.unwrap_or(false),
+        })
+    }
+}
+
+impl UpdateContactMethod {
+    /// The value is normalized by the kind of the existing contact method, which is fixed once
+    /// the contact method is created.
+    pub fn parse(
+        id: i64,
+        req: UpdateContactMethodRequest,
+        kind: ContactMethodKind,
+        default_country_code: u16,
+    ) -> Result<Self, String> {
+        let value = req
+            .value
+            .map(|e| ValidContactValue::parse(kind, e, default_country_code))
+            .transpose()?;
+
+        Ok(Self {
+            id,
+            value,
+            preferred: req.preferred,
+            verified: req.verified,
+        })
+    }
+}
+
+impl NewCustomerMerge {
+    pub fn parse(req: MergeCustomersRequest) -> Result<Self, String> {
+        if req.from_customer_id == req.into_customer_id {
+            return Err("A customer can't be merged into itself.".to_string());
+        }
+
+        let id = {
+            let generator = customer_merge_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            from_customer_id: req.from_customer_id,
+            into_customer_id: req.into_customer_id,
+        })
+    }
+
+    /// Append the remark of the merged customer to the remark of the other one, unless they're
+    /// the same.
+    pub fn combine_remarks(into: Option<String>, from: Option<String>) -> Option<String> {
+        let into = into.filter(|e| !e.trim().is_empty());
+        let from = from.filter(|e| !e.trim().is_empty());
+
+        match (into, from) {
+            (Some(into), Some(from)) if into.trim() != from.trim() => {
+                Some(format!("{into}\n{from}"))
+            }
+            (into, from) => into.or(from),
         }
     }
 }
 
 impl NewCustomer {
-    pub async fn parse(customer: CreateCustomerRequest) -> Result<Self, String> {
+    pub async fn parse(
+        customer: CreateCustomerRequest,
+        default_country_code: u16,
+    ) -> Result<Self, String> {
         let email = customer.email.map(ValidEmail::parse).transpose()?;
-        let phone = customer.phone.map(ValidPhone::parse).transpose()?;
+        let phone = customer
+            .phone
+            .map(|e| ValidPhone::parse(e, default_country_code))
+            .transpose()?;
 
         if email.is_none() && phone.is_none() {
             return Err("Email and phone are missing.".to_string());
@@ -155,9 +707,15 @@ impl NewCustomer {
 }
 
 impl UpdateCustomer {
-    pub fn parse(customer: UpdateCustomerRequest) -> Result<Self, String> {
+    pub fn parse(
+        customer: UpdateCustomerRequest,
+        default_country_code: u16,
+    ) -> Result<Self, String> {
         let email = customer.email.map(ValidEmail::parse).transpose()?;
-        let phone = customer.phone.map(ValidPhone::parse).transpose()?;
+        let phone = customer
+            .phone
+            .map(|e| ValidPhone::parse(e, default_country_code))
+            .transpose()?;
         let id = customer
             .id
             .parse::<i64>()
@@ -173,26 +731,112 @@ impl UpdateCustomer {
     }
 }
 
-impl<'r> ::sqlx::FromRow<'r, PgRow> for CustomerJson {
-    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
-        let id: i64 = row.try_get(0)?;
-        let name: String = row.try_get(1)?;
-        let email: Option<String> = row.try_get(2)?;
-        let phone: Option<String> = row.try_get(3)?;
-        let remark: Option<String> = row.try_get(4)?;
-        let created_at: DateTime<Utc> = row.try_get(5)?;
-        let updated_at: Option<DateTime<Utc>> = row.try_get(6)?;
-        let deleted_at: Option<DateTime<Utc>> = row.try_get(7)?;
+impl CustomerJson {
+    /// Read a customer from the 8 columns of the row starting at the offset.
+    fn from_row_at(row: &PgRow, offset: usize) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(offset)?;
+        let name: String = row.try_get(offset + 1)?;
+        let email: Option<String> = row.try_get(offset + 2)?;
+        let phone: Option<String> = row.try_get(offset + 3)?;
+        let remark: Option<String> = row.try_get(offset + 4)?;
+        let created_at: DateTime<Utc> = row.try_get(offset + 5)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(offset + 6)?;
+        let deleted_at: Option<DateTime<Utc>> = row.try_get(offset + 7)?;
 
         Ok(Self {
             id: id.to_string(),
             name,
             email,
+            phone_display: phone.as_deref().map(ValidPhone::format),
             phone,
             remark,
             created_at,
             updated_at,
             deleted_at,
+            contact_methods: vec![],
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for CustomerJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Self::from_row_at(row, 0)
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for CustomerDuplicateJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let customer = CustomerJson::from_row_at(row, 0)?;
+        let duplicate = CustomerJson::from_row_at(row, 8)?;
+        let same_phone: Option<bool> = row.try_get(16)?;
+        let same_email: Option<bool> = row.try_get(17)?;
+        let similar_name: Option<bool> = row.try_get(18)?;
+
+        let reasons = [
+            (same_phone, CustomerDuplicateReason::SamePhone),
+            (same_email, CustomerDuplicateReason::SameEmail),
+            (similar_name, CustomerDuplicateReason::SimilarName),
+        ]
+        .into_iter()
+        .filter(|(matched, _)| matched.unwrap_or(false))
+        .map(|(_, reason)| reason)
+        .collect();
+
+        Ok(Self {
+            customer,
+            duplicate,
+            reasons,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for CustomerMergeJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(0)?;
+        let from_customer_id: i64 = row.try_get(1)?;
+        let into_customer_id: i64 = row.try_get(2)?;
+        let order_items_moved: i32 = row.try_get(3)?;
+        let orders_moved: i32 = row.try_get(4)?;
+        let payments_moved: i32 = row.try_get(5)?;
+        let contact_methods_moved: i32 = row.try_get(6)?;
+        let merged_by: uuid::Uuid = row.try_get(7)?;
+        let created_at: DateTime<Utc> = row.try_get(8)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            from_customer_id: from_customer_id.to_string(),
+            into_customer_id: into_customer_id.to_string(),
+            order_items_moved,
+            orders_moved,
+            payments_moved,
+            contact_methods_moved,
+            merged_by: merged_by.to_string(),
+            created_at,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for ContactMethodJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(0)?;
+        let customer_id: i64 = row.try_get(1)?;
+        let kind: i16 = row.try_get(2)?;
+        let kind = ContactMethodKind::try_from(kind).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let value: String = row.try_get(3)?;
+        let preferred: bool = row.try_get(4)?;
+        let verified: bool = row.try_get(5)?;
+        let created_at: DateTime<Utc> = row.try_get(6)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(7)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            customer_id: customer_id.to_string(),
+            kind,
+            value,
+            preferred,
+            verified,
+            created_at,
+            updated_at,
         })
     }
 }
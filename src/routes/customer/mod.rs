@@ -5,7 +5,10 @@ use snowflake::SnowflakeIdGenerator;
 
 pub use domain::*;
 pub use route::{
-    create_customer_handler, delete_customer_handler, get_customer_handler, list_customers_handler,
+    create_contact_method_handler, create_customer_handler, delete_contact_method_handler,
+    delete_customer_handler, get_customer_handler, list_contact_methods_handler,
+    list_customer_duplicates_handler, list_customer_order_items_handler, list_customers_handler,
+    merge_customers_handler, normalize_customer_phones_handler, update_contact_method_handler,
     update_customer_handler,
 };
 
@@ -19,3 +22,19 @@ pub(crate) fn customer_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
         Mutex::new(generator)
     })
 }
+
+pub(crate) fn customer_merge_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 14);
+        Mutex::new(generator)
+    })
+}
+
+pub(crate) fn customer_contact_method_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 15);
+        Mutex::new(generator)
+    })
+}
@@ -9,22 +9,31 @@ use axum::{Extension, Json};
 use axum_extra::extract::WithRejection;
 use base64::Engine;
 
-use crate::errors::{AppError, CustomerError};
-use crate::repositories::CustomerRepo;
+use crate::configuration::{PhoneSettings, ReportingSettings};
+use crate::errors::{AppError, CustomerError, DatabaseResultExt};
+use crate::repositories::{
+    CurrencyRepo, CustomerContactMethodRepo, CustomerRepo, ExchangeRateRepo, OrderItemRepo,
+};
 use crate::routes::customer::{CreateCustomerRequest, CreateCustomerResponse, NewCustomer};
 use crate::routes::{
-    Claims, CustomerSearchParameters, DeleteCustomerRequest, ListCustomersRequest,
-    ListCustomersResponse, UpdateCustomer, UpdateCustomerRequest,
+    currency_converter, Claims, CreateContactMethodRequest, CreateContactMethodResponse,
+    CurrencyTotalJson, CustomerMergeJson, CustomerOrderItemsResponse, CustomerSearchParameters,
+    DeleteContactMethodRequest, DeleteCustomerRequest, ListContactMethodsResponse,
+    ListCustomerDuplicatesRequest, ListCustomerDuplicatesResponse, ListCustomerOrderItemsRequest,
+    ListCustomersRequest, ListCustomersResponse, MergeCustomersRequest, NewContactMethod,
+    NewCustomerMerge, NormalizePhonesResponse, OrderItemSearchParameters, UpdateContactMethod,
+    UpdateContactMethodRequest, UpdateCustomer, UpdateCustomerRequest,
 };
 
-#[tracing::instrument(name = "Create a new customer", skip(customer_repo, claims), fields(user_id=tracing::field::Empty))]
+#[tracing::instrument(name = "Create a new customer", skip(customer_repo, phone_settings, claims), fields(user_id=tracing::field::Empty))]
 pub async fn create_customer_handler(
     claims: Claims,
     Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(phone_settings): Extension<PhoneSettings>,
     WithRejection(Json(payload), _): WithRejection<Json<CreateCustomerRequest>, AppError>,
 ) -> Result<Json<CreateCustomerResponse>, AppError> {
-    tracing::Span::current().record("user_id", &tracing::field::display(&claims.sub));
-    let new_customer = NewCustomer::parse(payload)
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let new_customer = NewCustomer::parse(payload, phone_settings.default_country_code)
         .await
         .map_err(AppError::BadArguments)?;
 
@@ -47,15 +56,17 @@ pub async fn create_customer_handler(
     Ok(Json(CreateCustomerResponse { id }))
 }
 
-#[tracing::instrument(name = "Update a customer", skip(customer_repo, claims), fields(user_id=tracing::field::Empty))]
+#[tracing::instrument(name = "Update a customer", skip(customer_repo, phone_settings, claims), fields(user_id=tracing::field::Empty))]
 pub async fn update_customer_handler(
     claims: Claims,
     Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(phone_settings): Extension<PhoneSettings>,
     WithRejection(Json(payload), _): WithRejection<Json<UpdateCustomerRequest>, AppError>,
 ) -> Result<impl IntoResponse, AppError> {
     tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
 
-    let update_customer = UpdateCustomer::parse(payload).map_err(AppError::BadArguments)?;
+    let update_customer = UpdateCustomer::parse(payload, phone_settings.default_country_code)
+        .map_err(AppError::BadArguments)?;
 
     let email = &update_customer.email;
     let phone = &update_customer.phone;
@@ -126,6 +137,63 @@ pub async fn get_customer_handler(
     })
 }
 
+#[tracing::instrument(name = "List duplicate customers", skip(customer_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_customer_duplicates_handler(
+    claims: Claims,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Send + Sync>>,
+    Query(payload): Query<ListCustomerDuplicatesRequest>,
+) -> Result<Json<ListCustomerDuplicatesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let page = payload.page.unwrap_or(0);
+    let page_size = payload.page_size.unwrap_or(20);
+
+    let data = customer_repo
+        .list_duplicates(payload.customer_id, page, page_size)
+        .await
+        .context("Failed to get duplicate customers from database")?;
+
+    Ok(Json(ListCustomerDuplicatesResponse { data }))
+}
+
+#[tracing::instrument(name = "Merge customers", skip(customer_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn merge_customers_handler(
+    claims: Claims,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Send + Sync>>,
+    WithRejection(Json(payload), _): WithRejection<Json<MergeCustomersRequest>, AppError>,
+) -> Result<Json<CustomerMergeJson>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let merge = NewCustomerMerge::parse(payload).map_err(AppError::BadArguments)?;
+
+    let record = customer_repo
+        .merge(merge, claims.user_id()?)
+        .await
+        .db_context("Failed to merge customers in the database")?
+        .ok_or(CustomerError::CustomerNotFound)?;
+
+    Ok(Json(record))
+}
+
+/// Store the phone numbers saved before they were normalized in E.164, the numbers without a
+/// country code are in the default country of the phone settings. It only needs to run once
+/// after the upgrade, the numbers which can't be parsed are returned and left unchanged.
+#[tracing::instrument(name = "Normalize the phone numbers of customers", skip(customer_repo, phone_settings, claims), fields(user_id=tracing::field::Empty))]
+pub async fn normalize_customer_phones_handler(
+    claims: Claims,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Send + Sync>>,
+    Extension(phone_settings): Extension<PhoneSettings>,
+) -> Result<Json<NormalizePhonesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let response = customer_repo
+        .normalize_phones(phone_settings.default_country_code)
+        .await
+        .context("Failed to normalize the phone numbers of customers in the database")?;
+
+    Ok(Json(response))
+}
+
 #[tracing::instrument(name = "List customers", skip(customer_repo, claims), fields(user_id=tracing::field::Empty))]
 pub async fn list_customers_handler(
     claims: Claims,
@@ -134,7 +202,7 @@ pub async fn list_customers_handler(
 ) -> Result<impl IntoResponse, AppError> {
     tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
 
-    let search_parameter = if let Some(keyword) = &payload.keyword {
+    let mut search_parameter = if let Some(keyword) = &payload.keyword {
         // TODO: It should be written in pretty way.
         base64::engine::general_purpose::STANDARD
             .decode(keyword)
@@ -146,6 +214,10 @@ pub async fn list_customers_handler(
         CustomerSearchParameters::default()
     };
 
+    if let Some(q) = payload.q {
+        search_parameter.q = Some(q);
+    }
+
     let page = payload.page.unwrap_or(0);
     let page_size = payload.page_size.unwrap_or(20);
 
@@ -158,3 +230,201 @@ pub async fn list_customers_handler(
 
     Ok(Json(response))
 }
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "List the order items of a customer", skip(customer_repo, order_item_repo, exchange_rate_repo, currency_repo, reporting, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_customer_order_items_handler(
+    claims: Claims,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Send + Sync>>,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Send + Sync>>,
+    Extension(exchange_rate_repo): Extension<Arc<dyn ExchangeRateRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    Extension(reporting): Extension<ReportingSettings>,
+    Path(params): Path<HashMap<String, String>>,
+    Query(payload): Query<ListCustomerOrderItemsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let customer_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no customer id in the query string".to_string())
+        })?;
+
+    let customer = customer_repo
+        .get(customer_id)
+        .await
+        .context("Failed to get a customer from database")?
+        .ok_or(CustomerError::CustomerNotFound)?;
+
+    let page = payload.page.unwrap_or(0);
+    let page_size = payload.page_size.unwrap_or(20);
+
+    let search_parameter = OrderItemSearchParameters {
+        customer_id: Some(customer_id),
+        deleted: Some(false),
+        ..OrderItemSearchParameters::default()
+    };
+
+    let order_items = order_item_repo
+        .list(search_parameter, page, page_size)
+        .await
+        .context("Failed to get order items from database")?;
+
+    let totals = order_item_repo
+        .totals_by_currency(customer_id)
+        .await
+        .context("Failed to sum the order items of a customer")?;
+
+    let dated_totals = order_item_repo
+        .dated_totals(customer_id, &reporting.time_zone)
+        .await
+        .context("Failed to sum the order items of a customer by date")?;
+
+    let converter = currency_converter(
+        &exchange_rate_repo,
+        &currency_repo,
+        &reporting,
+        &dated_totals,
+    )
+    .await?;
+    let total = converter
+        .total(&dated_totals, 3)
+        .map(|totals| CurrencyTotalJson {
+            currency: converter.base_currency.id,
+            currency_name: Some(converter.base_currency.code.clone()),
+            ordered: totals[0],
+            arrived: totals[1],
+            picked_up: totals[2],
+        });
+
+    Ok(Json(CustomerOrderItemsResponse {
+        customer,
+        order_items,
+        totals,
+        total,
+    }))
+}
+
+#[tracing::instrument(name = "Create a new contact method", skip(customer_repo, contact_method_repo, phone_settings, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_contact_method_handler(
+    claims: Claims,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(contact_method_repo): Extension<Arc<dyn CustomerContactMethodRepo + Sync + Send>>,
+    Extension(phone_settings): Extension<PhoneSettings>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateContactMethodRequest>, AppError>,
+) -> Result<Json<CreateContactMethodResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let new_contact_method = NewContactMethod::parse(payload, phone_settings.default_country_code)
+        .map_err(AppError::BadArguments)?;
+
+    customer_repo
+        .get(new_contact_method.customer_id)
+        .await
+        .context("Failed to get a customer from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(CustomerError::CustomerNotFound)?;
+
+    let id = contact_method_repo
+        .create(new_contact_method)
+        .await
+        .db_context("Failed to insert a new contact method in the database")?;
+
+    Ok(Json(CreateContactMethodResponse { id }))
+}
+
+#[tracing::instrument(name = "Update a contact method", skip(contact_method_repo, phone_settings, claims), fields(user_id=tracing::field::Empty))]
+pub async fn update_contact_method_handler(
+    claims: Claims,
+    Extension(contact_method_repo): Extension<Arc<dyn CustomerContactMethodRepo + Sync + Send>>,
+    Extension(phone_settings): Extension<PhoneSettings>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateContactMethodRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let contact_method = contact_method_repo
+        .get(id)
+        .await
+        .context("Failed to get a contact method from database")?
+        .ok_or(CustomerError::ContactMethodNotFound)?;
+
+    let update_contact_method = UpdateContactMethod::parse(
+        id,
+        payload,
+        contact_method.kind,
+        phone_settings.default_country_code,
+    )
+    .map_err(AppError::BadArguments)?;
+
+    let need_update = update_contact_method.value.is_some()
+        || update_contact_method.preferred.is_some()
+        || update_contact_method.verified.is_some();
+
+    if need_update {
+        contact_method_repo
+            .update(update_contact_method)
+            .await
+            .db_context("Failed to update a contact method in the database")?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Delete a contact method", skip(contact_method_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn delete_contact_method_handler(
+    claims: Claims,
+    Extension(contact_method_repo): Extension<Arc<dyn CustomerContactMethodRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteContactMethodRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let deleted = contact_method_repo
+        .delete(id)
+        .await
+        .context("Failed to delete a contact method in the database")?;
+
+    if !deleted {
+        return Err(CustomerError::ContactMethodNotFound)?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "List the contact methods of a customer", skip(customer_repo, contact_method_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_contact_methods_handler(
+    claims: Claims,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(contact_method_repo): Extension<Arc<dyn CustomerContactMethodRepo + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<ListContactMethodsResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let customer_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| AppError::BadArguments("There is no customer id in the path".to_string()))?;
+
+    customer_repo
+        .get(customer_id)
+        .await
+        .context("Failed to get a customer from database")?
+        .ok_or(CustomerError::CustomerNotFound)?;
+
+    let data = contact_method_repo
+        .list(customer_id)
+        .await
+        .context("Failed to get contact methods from database")?;
+
+    Ok(Json(ListContactMethodsResponse { data }))
+}
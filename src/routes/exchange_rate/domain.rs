@@ -0,0 +1,286 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+use crate::routes::{exchange_rate_id_generator, CurrencyJson, Money};
+
+/// One unit of the base currency is worth `rate` units of the quote currency from `valid_from`
+/// until the next rate of the same currencies.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ExchangeRateJson {
+    pub id: String,
+    pub base_currency: i16,
+    pub quote_currency: i16,
+    pub rate: Decimal,
+    pub valid_from: NaiveDate,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateExchangeRateRequest {
+    pub base_currency: i16,
+    pub quote_currency: i16,
+    pub rate: Decimal,
+    pub valid_from: NaiveDate,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateExchangeRateResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ImportExchangeRatesResponse {
+    /// The number of the created or replaced rates.
+    pub imported: usize,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct ExchangeRateSearchParameters {
+    pub base_currency: Option<i16>,
+    pub quote_currency: Option<i16>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListExchangeRatesResponse {
+    pub data: Vec<ExchangeRateJson>,
+}
+
+#[derive(Debug)]
+pub struct ValidExchangeRate(pub Decimal);
+
+/// A rate which replaces the existing one of the same currencies and date.
+#[derive(Debug)]
+pub struct NewExchangeRate {
+    pub id: i64,
+    pub base_currency: i16,
+    pub quote_currency: i16,
+    pub rate: ValidExchangeRate,
+    pub valid_from: NaiveDate,
+}
+
+/// The amounts of a summary in one currency on one day, so that they are converted by the rates
+/// valid on that day. The meaning of the amounts depends on the summary.
+#[derive(Debug)]
+pub struct DatedAmountsJson {
+    pub currency: i16,
+    pub date: NaiveDate,
+    pub amounts: Vec<Decimal>,
+}
+
+/// Convert the amounts into the base currency with the rates valid on the given dates.
+/// A rate is found directly, by inverting the opposite rate, or through one intermediate
+/// currency such as JPY.
+#[derive(Debug)]
+pub struct CurrencyConverter {
+    pub base_currency: CurrencyJson,
+    rates: Vec<ExchangeRateJson>,
+}
+
+impl ValidExchangeRate {
+    pub fn parse(rate: Decimal) -> Result<Self, String> {
+        if rate <= Decimal::ZERO {
+            return Err("Exchange rate should be positive.".to_string());
+        }
+
+        if rate.scale() > 8 {
+            return Err("Exchange rate can't have more than 8 decimal places.".to_string());
+        }
+
+        Ok(Self(rate))
+    }
+}
+
+impl NewExchangeRate {
+    pub fn parse(req: CreateExchangeRateRequest) -> Result<Self, String> {
+        if req.base_currency == req.quote_currency {
+            return Err("The base and quote currencies should be different.".to_string());
+        }
+
+        let id = {
+            let generator = exchange_rate_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            base_currency: req.base_currency,
+            quote_currency: req.quote_currency,
+            rate: ValidExchangeRate::parse(req.rate)?,
+            valid_from: req.valid_from,
+        })
+    }
+
+    /// Parse a CSV file with the header `valid_from,base_currency,quote_currency,rate`, the
+    /// currencies are either ISO codes or numeric ids, e.g. `2024-05-01,JPY,HKD,0.0503`.
+    pub fn parse_csv(content: &str, currencies: &[CurrencyJson]) -> Result<Vec<Self>, String> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        match lines.next() {
+            Some((_, header))
+                if header.trim().to_lowercase().replace(' ', "")
+                    == "valid_from,base_currency,quote_currency,rate" => {}
+            _ => {
+                return Err(
+                    "The header should be valid_from,base_currency,quote_currency,rate."
+                        .to_string(),
+                )
+            }
+        }
+
+        let find_currency = |value: &str| {
+            currencies
+                .iter()
+                .find(|e| e.code.eq_ignore_ascii_case(value) || e.id.to_string() == value)
+                .map(|e| e.id)
+        };
+
+        let mut rates = vec![];
+
+        for (index, line) in lines {
+            let line_number = index + 1;
+            let columns = line.split(',').map(str::trim).collect::<Vec<_>>();
+
+            let [valid_from, base_currency, quote_currency, rate] = columns[..] else {
+                return Err(format!("Line {line_number} should have 4 columns."));
+            };
+
+            let req = CreateExchangeRateRequest {
+                base_currency: find_currency(base_currency).ok_or(format!(
+                    "Line {line_number}: {base_currency} is not a known currency."
+                ))?,
+                quote_currency: find_currency(quote_currency).ok_or(format!(
+                    "Line {line_number}: {quote_currency} is not a known currency."
+                ))?,
+                rate: rate
+                    .parse::<Decimal>()
+                    .map_err(|_| format!("Line {line_number}: {rate} is not a number."))?,
+                valid_from: valid_from
+                    .parse::<NaiveDate>()
+                    .map_err(|_| format!("Line {line_number}: {valid_from} is not a date."))?,
+            };
+
+            rates.push(Self::parse(req).map_err(|e| format!("Line {line_number}: {e}"))?);
+        }
+
+        if rates.is_empty() {
+            return Err("There is no exchange rate in the file.".to_string());
+        }
+
+        Ok(rates)
+    }
+}
+
+impl CurrencyConverter {
+    pub fn new(base_currency: CurrencyJson, rates: Vec<ExchangeRateJson>) -> Self {
+        Self {
+            base_currency,
+            rates,
+        }
+    }
+
+    /// The latest rate of the currencies which is valid on the date, the opposite rate is
+    /// inverted when there is no such rate.
+    fn direct_rate(&self, from: i16, to: i16, date: NaiveDate) -> Option<Decimal> {
+        let latest = |base: i16, quote: i16| {
+            self.rates
+                .iter()
+                .filter(|e| e.base_currency == base && e.quote_currency == quote)
+                .filter(|e| e.valid_from <= date)
+                .max_by_key(|e| e.valid_from)
+                .map(|e| e.rate)
+        };
+
+        latest(from, to).or_else(|| latest(to, from).map(|rate| Decimal::ONE / rate))
+    }
+
+    /// The amount of `to` which one unit of `from` is worth on the date.
+    pub fn rate(&self, from: i16, to: i16, date: NaiveDate) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        if let Some(rate) = self.direct_rate(from, to, date) {
+            return Some(rate);
+        }
+
+        self.rates
+            .iter()
+            .flat_map(|e| [e.base_currency, e.quote_currency])
+            .filter(|e| *e != from && *e != to)
+            .find_map(|via| {
+                Some(self.direct_rate(from, via, date)? * self.direct_rate(via, to, date)?)
+            })
+    }
+
+    /// Convert the amount into the base currency without rounding.
+    pub fn convert(&self, amount: Decimal, currency: i16, date: NaiveDate) -> Option<Decimal> {
+        Some(amount * self.rate(currency, self.base_currency.id, date)?)
+    }
+
+    /// Sum the amounts of each position in the base currency, rounded to the minor units of
+    /// the base currency. Return `None` when any rate is missing.
+    pub fn total(&self, rows: &[DatedAmountsJson], len: usize) -> Option<Vec<Money>> {
+        let mut totals = vec![Decimal::ZERO; len];
+
+        for row in rows {
+            for (total, amount) in totals.iter_mut().zip(row.amounts.iter()) {
+                *total += self.convert(*amount, row.currency, row.date)?;
+            }
+        }
+
+        Some(
+            totals
+                .into_iter()
+                .map(|e| Money::round(e, &self.base_currency))
+                .collect(),
+        )
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for ExchangeRateJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let base_currency: i16 = row.try_get(1)?;
+        let quote_currency: i16 = row.try_get(2)?;
+        let rate: Decimal = row.try_get(3)?;
+        let valid_from: NaiveDate = row.try_get(4)?;
+        let created_by: uuid::Uuid = row.try_get(5)?;
+        let created_at: DateTime<Utc> = row.try_get(6)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(7)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            base_currency,
+            quote_currency,
+            rate,
+            valid_from,
+            created_by: created_by.to_string(),
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for DatedAmountsJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let currency: i16 = row.try_get(0)?;
+        let date: NaiveDate = row.try_get(1)?;
+        let amounts = (2..row.len())
+            .map(|index| row.try_get::<Decimal, _>(index))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            currency,
+            date,
+            amounts,
+        })
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Multipart, Query};
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+
+use crate::configuration::ReportingSettings;
+use crate::errors::{AppError, DatabaseResultExt};
+use crate::repositories::{CurrencyRepo, ExchangeRateRepo};
+use crate::routes::{
+    Claims, CreateExchangeRateRequest, CreateExchangeRateResponse, CurrencyConverter,
+    DatedAmountsJson, ExchangeRateSearchParameters, ImportExchangeRatesResponse,
+    ListExchangeRatesResponse, NewExchangeRate,
+};
+
+/// Load the converter into the base currency of the reporting settings, with only the rates
+/// valid on the dates of the rows.
+pub(crate) async fn currency_converter(
+    exchange_rate_repo: &Arc<dyn ExchangeRateRepo + Sync + Send>,
+    currency_repo: &Arc<dyn CurrencyRepo + Sync + Send>,
+    reporting: &ReportingSettings,
+    rows: &[DatedAmountsJson],
+) -> Result<CurrencyConverter, AppError> {
+    let base_currency = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?
+        .into_iter()
+        .find(|e| e.id == reporting.base_currency)
+        .with_context(|| format!("{} is not a known currency", reporting.base_currency))?;
+
+    let dates = rows.iter().map(|e| e.date);
+    let rates = match (dates.clone().min(), dates.max()) {
+        (Some(from), Some(to)) => exchange_rate_repo
+            .list_valid_between(from, to)
+            .await
+            .context("Failed to get exchange rates from database")?,
+        _ => vec![],
+    };
+
+    Ok(CurrencyConverter::new(base_currency, rates))
+}
+
+#[tracing::instrument(name = "Create a new exchange rate", skip(exchange_rate_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_exchange_rate_handler(
+    claims: Claims,
+    Extension(exchange_rate_repo): Extension<Arc<dyn ExchangeRateRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateExchangeRateRequest>, AppError>,
+) -> Result<Json<CreateExchangeRateResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let new_rate = NewExchangeRate::parse(payload).map_err(AppError::BadArguments)?;
+
+    let ids = exchange_rate_repo
+        .save(vec![new_rate], claims.user_id()?)
+        .await
+        .db_context("Failed to insert a new exchange rate in the database")?;
+
+    Ok(Json(CreateExchangeRateResponse { id: ids[0] }))
+}
+
+/// Import the rates from the `file` field of the form, the whole file is rejected when any line
+/// is invalid.
+#[tracing::instrument(name = "Import exchange rates", skip(exchange_rate_repo, currency_repo, claims, multipart), fields(user_id=tracing::field::Empty))]
+pub async fn import_exchange_rates_handler(
+    claims: Claims,
+    Extension(exchange_rate_repo): Extension<Arc<dyn ExchangeRateRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportExchangeRatesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let mut content = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadArguments(e.body_text()))?
+    {
+        if field.name() == Some("file") {
+            let text = field
+                .text()
+                .await
+                .map_err(|e| AppError::BadArguments(e.body_text()))?;
+            content = Some(text);
+            break;
+        }
+    }
+
+    let content = content
+        .ok_or_else(|| AppError::BadArguments("There is no file in the form".to_string()))?;
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    let new_rates =
+        NewExchangeRate::parse_csv(&content, &currencies).map_err(AppError::BadArguments)?;
+
+    let ids = exchange_rate_repo
+        .save(new_rates, claims.user_id()?)
+        .await
+        .db_context("Failed to import exchange rates in the database")?;
+
+    Ok(Json(ImportExchangeRatesResponse {
+        imported: ids.len(),
+    }))
+}
+
+#[tracing::instrument(name = "List exchange rates", skip(exchange_rate_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_exchange_rates_handler(
+    claims: Claims,
+    Extension(exchange_rate_repo): Extension<Arc<dyn ExchangeRateRepo + Sync + Send>>,
+    Query(payload): Query<ExchangeRateSearchParameters>,
+) -> Result<Json<ListExchangeRatesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let data = exchange_rate_repo
+        .list(payload)
+        .await
+        .context("Failed to get exchange rates from database")?;
+
+    Ok(Json(ListExchangeRatesResponse { data }))
+}
@@ -21,6 +21,15 @@ pub struct Claims {
     pub exp: usize,
 }
 
+impl Claims {
+    /// The id of the user who sends the request.
+    pub fn user_id(&self) -> Result<uuid::Uuid, AppError> {
+        uuid::Uuid::parse_str(&self.sub)
+            .context("Failed to parse the user id from claims")
+            .map_err(AppError::UnexpectedError)
+    }
+}
+
 #[async_trait]
 impl<S> FromRequestParts<S> for Claims
 where
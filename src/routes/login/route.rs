@@ -35,7 +35,7 @@ pub async fn login(
                 .context("Failed to connect the redis")?;
 
             redis_connection
-                .set(&user_id, exp.timestamp() as usize)
+                .set::<_, _, ()>(&user_id, exp.timestamp() as usize)
                 .context("Failed to set a user to session storage.")?;
 
             let secret_key = JWT_SECRET_KEY_INSTANCE
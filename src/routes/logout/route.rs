@@ -19,7 +19,7 @@ pub async fn logout(
         .context("Failed to connect the redis")?;
 
     redis_connection
-        .del(user_id.as_str())
+        .del::<_, ()>(user_id.as_str())
         .context("Failed to clear a login session")
         .map_err(AppError::UnexpectedError)?;
 
@@ -1,16 +1,30 @@
+pub use category::*;
+pub use currency::*;
 pub use customer::*;
+pub use exchange_rate::*;
 pub use health_check::health_check;
 pub use login::domain::{Claims, Login, LoginResponse};
 pub use login::route::login;
 pub use logout::logout;
+pub use order::*;
 pub use order_item::*;
 pub use password::change_password;
+pub use payment::*;
+pub use pricing_rule::*;
 pub use product::*;
+pub use shipment::*;
 
+mod category;
+mod currency;
 mod customer;
+mod exchange_rate;
 mod health_check;
 mod login;
 mod logout;
+mod order;
 mod order_item;
 mod password;
+mod payment;
+mod pricing_rule;
 mod product;
+mod shipment;
@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+use crate::routes::{
+    order_id_generator, CurrencyTotalJson, CustomerJson, OrderItemJson, OrderItemStatus,
+    ValidCustomerId,
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct OrderJson {
+    pub id: String,
+    pub customer: CustomerJson,
+    pub created_by: String,
+    pub created_by_username: Option<String>,
+    pub note: Option<String>,
+    /// Derived from the order items, it's `None` when the order has no order item.
+    pub status: Option<OrderItemStatus>,
+    pub order_items: Vec<OrderItemJson>,
+    pub totals: Vec<CurrencyTotalJson>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Create an order for a customer, the existing order items in `order_item_ids` are attached to
+/// the new order.
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateOrderRequest {
+    pub customer_id: i64,
+    pub note: Option<String>,
+    pub order_item_ids: Option<Vec<String>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateOrderResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ListOrdersRequest {
+    pub keyword: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListOrdersResponse {
+    pub data: Vec<OrderJson>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
+pub struct OrderSearchParameters {
+    pub customer_id: Option<i64>,
+    pub deleted: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct NewOrder {
+    pub id: i64,
+    pub customer_id: ValidCustomerId,
+    pub note: Option<String>,
+    pub order_item_ids: Vec<i64>,
+}
+
+impl NewOrder {
+    pub async fn parse(req: CreateOrderRequest) -> Result<Self, String> {
+        let mut order_item_ids = req
+            .order_item_ids
+            .unwrap_or_default()
+            .iter()
+            .map(|e| e.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Can't parse order item id to i64.".to_string())?;
+        order_item_ids.sort();
+        order_item_ids.dedup();
+
+        let note = req.note.filter(|e| !e.trim().is_empty());
+
+        let id = async {
+            let generator = order_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        }
+        .await;
+
+        Ok(Self {
+            id,
+            customer_id: ValidCustomerId(req.customer_id),
+            note,
+            order_item_ids,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderJson {
+    /// The order items and the totals are left empty, they are filled by the repository.
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let customer_id: i64 = row.try_get(1)?;
+        let created_by: uuid::Uuid = row.try_get(2)?;
+        let note: Option<String> = row.try_get(3)?;
+        let created_at: DateTime<Utc> = row.try_get(4)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(5)?;
+        let deleted_at: Option<DateTime<Utc>> = row.try_get(6)?;
+
+        let customer_name: String = row.try_get(7)?;
+        let customer_created_at: DateTime<Utc> = row.try_get(8)?;
+
+        let created_by_username: Option<String> = row.try_get(9)?;
+
+        let customer = CustomerJson {
+            id: customer_id.to_string(),
+            name: customer_name,
+            email: None,
+            phone: None,
+            phone_display: None,
+            contact_methods: vec![],
+            remark: None,
+            created_at: customer_created_at,
+            updated_at: None,
+            deleted_at: None,
+        };
+
+        Ok(Self {
+            id: id.to_string(),
+            customer,
+            created_by: created_by.to_string(),
+            created_by_username,
+            note,
+            status: None,
+            order_items: vec![],
+            totals: vec![],
+            created_at,
+            updated_at,
+            deleted_at,
+        })
+    }
+}
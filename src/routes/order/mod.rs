@@ -0,0 +1,16 @@
+pub use domain::*;
+use once_cell::sync::OnceCell;
+pub use route::*;
+use snowflake::SnowflakeIdGenerator;
+use std::sync::Mutex;
+
+mod domain;
+mod route;
+
+pub(crate) fn order_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 4);
+        Mutex::new(generator)
+    })
+}
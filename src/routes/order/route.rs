@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Path, Query};
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+use base64::Engine;
+
+use crate::errors::{AppError, ConstraintError, DatabaseResultExt, OrderError};
+use crate::repositories::{CustomerRepo, OrderItemRepo, OrderRepo};
+use crate::routes::{
+    Claims, CreateOrderRequest, CreateOrderResponse, ListOrdersRequest, ListOrdersResponse,
+    NewOrder, OrderJson, OrderSearchParameters,
+};
+
+#[tracing::instrument(name = "Create a new order", skip(order_repo, order_item_repo, customer_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_order_handler(
+    claims: Claims,
+    Extension(order_repo): Extension<Arc<dyn OrderRepo + Sync + Send>>,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateOrderRequest>, AppError>,
+) -> Result<Json<CreateOrderResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let new_order = NewOrder::parse(payload)
+        .await
+        .map_err(AppError::BadArguments)?;
+
+    let customer = customer_repo
+        .get(new_order.customer_id.0)
+        .await
+        .context("Failed to get a customer from database")?;
+
+    if customer.is_none_or(|e| e.deleted_at.is_some()) {
+        return Err(ConstraintError::ReferenceNotExist("customer"))?;
+    }
+
+    let customer_id = new_order.customer_id.0.to_string();
+
+    for order_item_id in &new_order.order_item_ids {
+        let order_item = order_item_repo
+            .get(*order_item_id)
+            .await
+            .context("Failed to get an order item from database")?;
+
+        let is_attachable = order_item.is_some_and(|e| {
+            e.deleted_at.is_none() && e.order_id.is_none() && e.customer.id == customer_id
+        });
+
+        if !is_attachable {
+            return Err(OrderError::OrderItemNotAttachable(*order_item_id))?;
+        }
+    }
+
+    let id = order_repo
+        .create(new_order, claims.user_id()?)
+        .await
+        .db_context("Failed to insert a new order in the database")??;
+
+    Ok(Json(CreateOrderResponse { id }))
+}
+
+#[tracing::instrument(name = "Get an order", skip(order_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn get_order_handler(
+    claims: Claims,
+    Extension(order_repo): Extension<Arc<dyn OrderRepo + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<OrderJson>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let order_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no order id in the query string".to_string())
+        })?;
+
+    let order = order_repo
+        .get(order_id)
+        .await
+        .context("Failed to get an order from database")?
+        .ok_or(OrderError::OrderNotFound)?;
+
+    Ok(Json(order))
+}
+
+#[tracing::instrument(name = "List orders", skip(order_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_orders_handler(
+    claims: Claims,
+    Extension(order_repo): Extension<Arc<dyn OrderRepo + Sync + Send>>,
+    Query(payload): Query<ListOrdersRequest>,
+) -> Result<Json<ListOrdersResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let search_parameter = if let Some(keyword) = &payload.keyword {
+        base64::engine::general_purpose::STANDARD
+            .decode(keyword)
+            .as_ref()
+            .map(|e| serde_json::from_slice::<OrderSearchParameters>(e))
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+    } else {
+        OrderSearchParameters::default()
+    };
+
+    let page = payload.page.unwrap_or(0);
+    let page_size = payload.page_size.unwrap_or(20);
+
+    let data = order_repo
+        .list(search_parameter, page, page_size)
+        .await
+        .context("Failed to get orders from database")?;
+
+    Ok(Json(ListOrdersResponse { data }))
+}
@@ -3,15 +3,25 @@ use rust_decimal::Decimal;
 use sqlx::postgres::PgRow;
 use sqlx::{Error, Row};
 
-use crate::routes::{order_item_id_generator, CustomerJson, ProductJson};
+use crate::routes::{
+    order_item_id_generator, order_item_status_history_id_generator, CurrencyJson, CustomerJson,
+    Money, ProductJson,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct OrderItemJson {
     pub id: String,
+    pub order_id: Option<String>,
+    pub shipment_id: Option<String>,
     pub customer: CustomerJson,
     pub product: ProductJson,
+    pub variant_id: Option<String>,
     pub quantity: u32,
-    pub status: u32,
+    /// The price and currency of the product when the order item was created, the price of the
+    /// variant takes precedence, unless the price was overridden.
+    pub unit_price: Money,
+    pub currency: i16,
+    pub status: OrderItemStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
@@ -19,10 +29,113 @@ pub struct OrderItemJson {
 
 #[derive(serde::Deserialize, Debug)]
 pub struct CreateOrderItemsRequest {
-    customer_id: i64,
-    product_id: i64,
-    quantity: u32,
-    status: u32,
+    pub order_id: Option<String>,
+    pub customer_id: i64,
+    pub product_id: i64,
+    /// The variant of the product, which takes the stock instead of the product.
+    pub variant_id: Option<String>,
+    pub quantity: u32,
+    /// Override the price of the product, e.g. for a negotiated deal.
+    pub unit_price: Option<Decimal>,
+    pub status: Option<OrderItemStatus>,
+    /// Refuse to create the order item when the product doesn't have enough available stock.
+    pub reject_oversell: Option<bool>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateOrderItemResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct UpdateOrderItemRequest {
+    pub id: String,
+    pub quantity: Option<u32>,
+    pub unit_price: Option<Decimal>,
+    pub status: Option<OrderItemStatus>,
+}
+
+/// Change the status of many order items at once, they are selected either by `ids` or by
+/// `product_id` and/or `current_status`.
+#[derive(serde::Deserialize, Debug)]
+pub struct UpdateOrderItemStatusesRequest {
+    pub ids: Option<Vec<String>>,
+    pub product_id: Option<i64>,
+    pub current_status: Option<OrderItemStatus>,
+    pub status: OrderItemStatus,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct OrderItemStatusChangeJson {
+    pub id: String,
+    pub success: bool,
+    pub previous_status: Option<OrderItemStatus>,
+    pub error_message: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct UpdateOrderItemStatusesResponse {
+    pub data: Vec<OrderItemStatusChangeJson>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeleteOrderItemRequest {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ListOrderItemsRequest {
+    pub keyword: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListOrderItemsResponse {
+    pub data: Vec<OrderItemJson>,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct OrderItemSearchParameters {
+    pub id: Option<i64>,
+    pub order_id: Option<i64>,
+    pub shipment_id: Option<i64>,
+    pub customer_id: Option<i64>,
+    pub product_id: Option<i64>,
+    pub statuses: Option<Vec<OrderItemStatus>>,
+    /// Inclusive lower bound of `created_at`.
+    pub created_from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of `created_at`.
+    pub created_to: Option<DateTime<Utc>>,
+    /// `Some(true)` only lists the deleted order items, otherwise only the others are listed.
+    pub deleted: Option<bool>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct OrderItemStatusHistoryJson {
+    pub id: String,
+    pub order_item_id: String,
+    pub previous_status: Option<OrderItemStatus>,
+    pub next_status: OrderItemStatus,
+    pub changed_by: String,
+    pub changed_by_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListOrderItemStatusHistoriesResponse {
+    pub data: Vec<OrderItemStatusHistoryJson>,
+}
+
+/// The value of a customer's order items in one currency.
+/// `arrived` only counts the items which are waiting to be picked up.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CurrencyTotalJson {
+    pub currency: i16,
+    pub currency_name: Option<String>,
+    pub ordered: Money,
+    pub arrived: Money,
+    pub picked_up: Money,
 }
 
 #[derive(Debug)]
@@ -35,22 +148,173 @@ pub struct ValidCustomerId(pub i64);
 pub struct ValidQuantity(pub u32);
 
 #[derive(Debug)]
-pub struct ValidStatus(pub u32);
+pub struct ValidUnitPrice(pub Money);
+
+/// The lifecycle of an order item. The discriminant is the code stored in `order_items.status`,
+/// so the existing values must never be changed.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderItemStatus {
+    Requested = 0,
+    PurchasedInJapan = 1,
+    InTransit = 2,
+    Arrived = 3,
+    PickedUp = 4,
+    Cancelled = 5,
+    Refunded = 6,
+}
 
 #[derive(Debug)]
 pub struct NewOrderItem {
     pub id: i64,
+    pub order_id: Option<i64>,
     pub customer_id: ValidCustomerId,
     pub product_id: ValidProductId,
+    pub variant_id: Option<i64>,
     pub quantity: ValidQuantity,
-    pub status: ValidStatus,
+    /// Take the price of the variant or the product when it's `None`.
+    pub unit_price: Option<ValidUnitPrice>,
+    pub status: OrderItemStatus,
+    pub reject_oversell: bool,
+}
+
+#[derive(Debug)]
+pub struct UpdateOrderItem {
+    pub id: i64,
+    pub quantity: Option<ValidQuantity>,
+    pub unit_price: Option<ValidUnitPrice>,
+    pub status: Option<OrderItemStatus>,
+}
+
+#[derive(Debug)]
+pub struct NewOrderItemStatusHistory {
+    pub id: i64,
+    pub order_item_id: i64,
+    pub previous_status: Option<OrderItemStatus>,
+    pub next_status: OrderItemStatus,
+    pub changed_by: uuid::Uuid,
+}
+
+#[derive(Debug)]
+pub enum OrderItemSelector {
+    Ids(Vec<i64>),
+    Filter {
+        product_id: Option<i64>,
+        current_status: Option<OrderItemStatus>,
+    },
+}
+
+#[derive(Debug)]
+pub struct UpdateOrderItemStatuses {
+    pub selector: OrderItemSelector,
+    pub status: OrderItemStatus,
+}
+
+impl OrderItemStatus {
+    pub fn code(&self) -> i16 {
+        *self as i16
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderItemStatus::Requested => "requested",
+            OrderItemStatus::PurchasedInJapan => "purchased_in_japan",
+            OrderItemStatus::InTransit => "in_transit",
+            OrderItemStatus::Arrived => "arrived",
+            OrderItemStatus::PickedUp => "picked_up",
+            OrderItemStatus::Cancelled => "cancelled",
+            OrderItemStatus::Refunded => "refunded",
+        }
+    }
+
+    /// The statuses which can be reached from the current status.
+    pub fn next_statuses(&self) -> &'static [OrderItemStatus] {
+        use OrderItemStatus::*;
+
+        match self {
+            Requested => &[PurchasedInJapan, Cancelled],
+            PurchasedInJapan => &[InTransit, Cancelled, Refunded],
+            InTransit => &[Arrived, Refunded],
+            Arrived => &[PickedUp, Refunded],
+            PickedUp | Cancelled | Refunded => &[],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: OrderItemStatus) -> bool {
+        self.next_statuses().contains(&next)
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.next_statuses().is_empty()
+    }
+
+    /// Derive the status of an order from its items: the least progressed status of the active
+    /// items, or `Refunded` / `Cancelled` when every item is refunded or cancelled.
+    pub fn aggregate(statuses: impl IntoIterator<Item = OrderItemStatus>) -> Option<Self> {
+        use OrderItemStatus::*;
+
+        let statuses = statuses.into_iter().collect::<Vec<_>>();
+
+        statuses
+            .iter()
+            .filter(|e| !matches!(e, Cancelled | Refunded))
+            .min_by_key(|e| e.code())
+            .or_else(|| statuses.iter().find(|e| **e == Refunded))
+            .or_else(|| statuses.first())
+            .copied()
+    }
+}
+
+impl std::fmt::Display for OrderItemStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<i16> for OrderItemStatus {
+    type Error = String;
+
+    fn try_from(code: i16) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(OrderItemStatus::Requested),
+            1 => Ok(OrderItemStatus::PurchasedInJapan),
+            2 => Ok(OrderItemStatus::InTransit),
+            3 => Ok(OrderItemStatus::Arrived),
+            4 => Ok(OrderItemStatus::PickedUp),
+            5 => Ok(OrderItemStatus::Cancelled),
+            6 => Ok(OrderItemStatus::Refunded),
+            other => Err(format!("{other} is not a valid order item status")),
+        }
+    }
+}
+
+impl ValidQuantity {
+    pub fn parse(quantity: u32) -> Result<Self, String> {
+        if quantity == 0 {
+            return Err("Quantity must be greater than 0.".to_string());
+        }
+
+        if quantity > i16::MAX as u32 {
+            return Err(format!("Quantity must be less than {}.", i16::MAX));
+        }
+
+        Ok(Self(quantity))
+    }
+}
+
+impl ValidUnitPrice {
+    /// The order item is charged in the currency of its product.
+    pub fn parse(unit_price: Decimal, currency: &CurrencyJson) -> Result<Self, String> {
+        Money::parse(unit_price, currency).map(Self)
+    }
 }
 
 impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderItemJson {
     fn from_row(row: &'r PgRow) -> Result<Self, Error> {
         let id: i64 = row.try_get(0)?;
-        let quantity: i32 = row.try_get(1)?;
-        let status: i32 = row.try_get(2)?;
+        let quantity: i16 = row.try_get(1)?;
+        let status: i16 = row.try_get(2)?;
+        let status = OrderItemStatus::try_from(status).map_err(|e| Error::Decode(e.into()))?;
         let created_at: DateTime<Utc> = row.try_get(3)?;
         let updated_at: Option<DateTime<Utc>> = row.try_get(4)?;
         let deleted_at: Option<DateTime<Utc>> = row.try_get(5)?;
@@ -66,11 +330,21 @@ impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderItemJson {
         let product_price: Decimal = row.try_get(12)?;
         let product_created_at: DateTime<Utc> = row.try_get(13)?;
 
+        let order_id: Option<i64> = row.try_get(14)?;
+        let shipment_id: Option<i64> = row.try_get(15)?;
+        let unit_price: Decimal = row.try_get(16)?;
+        let currency: i16 = row.try_get(17)?;
+        let product_on_hand: i32 = row.try_get(18)?;
+        let product_reserved: i32 = row.try_get(19)?;
+        let variant_id: Option<i64> = row.try_get(20)?;
+
         let customer = CustomerJson {
             id: customer_id.to_string(),
             name: customer_name,
             email: None,
             phone: None,
+            phone_display: None,
+            contact_methods: vec![],
             remark: None,
             created_at: customer_created_at,
             updated_at: None,
@@ -81,7 +355,24 @@ impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderItemJson {
             id: product_id.to_string(),
             name: product_name,
             currency: product_currency,
-            price: product_price,
+            currency_code: None,
+            currency_symbol: None,
+            price: Money {
+                amount: product_price,
+                currency: product_currency,
+            },
+            on_hand: product_on_hand,
+            reserved: product_reserved,
+            available: product_on_hand - product_reserved,
+            variants: vec![],
+            category_id: None,
+            tags: vec![],
+            images: vec![],
+            cost_price: None,
+            cost_tax_rate: None,
+            pricing_rule_id: None,
+            cost: None,
+            margin: None,
             created_at: product_created_at,
             updated_at: None,
             deleted_at: None,
@@ -89,10 +380,18 @@ impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderItemJson {
 
         Ok(Self {
             id: id.to_string(),
+            order_id: order_id.map(|e| e.to_string()),
+            shipment_id: shipment_id.map(|e| e.to_string()),
             customer,
             product,
+            variant_id: variant_id.map(|e| e.to_string()),
             quantity: quantity as u32,
-            status: status as u32,
+            unit_price: Money {
+                amount: unit_price,
+                currency,
+            },
+            currency,
+            status,
             created_at,
             updated_at,
             deleted_at,
@@ -101,9 +400,26 @@ impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderItemJson {
 }
 
 impl NewOrderItem {
-    pub async fn parse(req: CreateOrderItemsRequest) -> Result<Self, String> {
-        // TODO: check product is exist
-        // TODO: check customer is exist
+    /// The currency must be the one of the product.
+    pub async fn parse(
+        req: CreateOrderItemsRequest,
+        currency: &CurrencyJson,
+    ) -> Result<Self, String> {
+        let quantity = ValidQuantity::parse(req.quantity)?;
+        let unit_price = req
+            .unit_price
+            .map(|e| ValidUnitPrice::parse(e, currency))
+            .transpose()?;
+        let order_id = req
+            .order_id
+            .map(|e| e.parse::<i64>())
+            .transpose()
+            .map_err(|_| "Can't parse order id to i64.".to_string())?;
+        let variant_id = req
+            .variant_id
+            .map(|e| e.parse::<i64>())
+            .transpose()
+            .map_err(|_| "Can't parse variant id to i64.".to_string())?;
 
         let id = async {
             let generator = order_item_id_generator();
@@ -114,10 +430,151 @@ impl NewOrderItem {
 
         Ok(Self {
             id,
+            order_id,
             customer_id: ValidCustomerId(req.customer_id),
             product_id: ValidProductId(req.product_id),
-            quantity: ValidQuantity(req.quantity),
-            status: ValidStatus(req.status),
+            variant_id,
+            quantity,
+            unit_price,
+            status: req.status.unwrap_or(OrderItemStatus::Requested),
+            reject_oversell: req.reject_oversell.unwrap_or(false),
+        })
+    }
+}
+
+impl UpdateOrderItem {
+    /// The currency must be the one of the order item.
+    pub fn parse(req: UpdateOrderItemRequest, currency: &CurrencyJson) -> Result<Self, String> {
+        let id = req
+            .id
+            .parse::<i64>()
+            .map_err(|_| "Can't parse id to i64.".to_string())?;
+
+        let quantity = req.quantity.map(ValidQuantity::parse).transpose()?;
+        let unit_price = req
+            .unit_price
+            .map(|e| ValidUnitPrice::parse(e, currency))
+            .transpose()?;
+
+        Ok(Self {
+            id,
+            quantity,
+            unit_price,
+            status: req.status,
+        })
+    }
+}
+
+impl UpdateOrderItemStatuses {
+    pub fn parse(req: UpdateOrderItemStatusesRequest) -> Result<Self, String> {
+        let selector = match (req.ids, req.product_id, req.current_status) {
+            (Some(ids), None, None) => {
+                if ids.is_empty() {
+                    return Err("Order item ids are empty.".to_string());
+                }
+
+                let ids = ids
+                    .iter()
+                    .map(|e| e.parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| "Can't parse id to i64.".to_string())?;
+
+                OrderItemSelector::Ids(ids)
+            }
+            (None, None, None) => {
+                return Err("Either ids or a product id / current status is required.".to_string())
+            }
+            (None, product_id, current_status) => OrderItemSelector::Filter {
+                product_id,
+                current_status,
+            },
+            (Some(_), _, _) => {
+                return Err("Ids can't be used with a product id / current status.".to_string())
+            }
+        };
+
+        Ok(Self {
+            selector,
+            status: req.status,
+        })
+    }
+}
+
+impl NewOrderItemStatusHistory {
+    pub fn new(
+        order_item_id: i64,
+        previous_status: Option<OrderItemStatus>,
+        next_status: OrderItemStatus,
+        changed_by: uuid::Uuid,
+    ) -> Self {
+        let id = {
+            let generator = order_item_status_history_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Self {
+            id,
+            order_item_id,
+            previous_status,
+            next_status,
+            changed_by,
+        }
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for OrderItemStatusHistoryJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let order_item_id: i64 = row.try_get(1)?;
+        let previous_status: Option<i16> = row.try_get(2)?;
+        let next_status: i16 = row.try_get(3)?;
+        let changed_by: uuid::Uuid = row.try_get(4)?;
+        let created_at: DateTime<Utc> = row.try_get(5)?;
+        let changed_by_username: Option<String> = row.try_get(6)?;
+
+        let previous_status = previous_status
+            .map(OrderItemStatus::try_from)
+            .transpose()
+            .map_err(|e| Error::Decode(e.into()))?;
+        let next_status =
+            OrderItemStatus::try_from(next_status).map_err(|e| Error::Decode(e.into()))?;
+
+        Ok(Self {
+            id: id.to_string(),
+            order_item_id: order_item_id.to_string(),
+            previous_status,
+            next_status,
+            changed_by: changed_by.to_string(),
+            changed_by_username,
+            created_at,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for CurrencyTotalJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let currency: i16 = row.try_get(0)?;
+        let currency_name: Option<String> = row.try_get(1)?;
+        let ordered: Decimal = row.try_get(2)?;
+        let arrived: Decimal = row.try_get(3)?;
+        let picked_up: Decimal = row.try_get(4)?;
+
+        Ok(Self {
+            currency,
+            currency_name,
+            ordered: Money {
+                amount: ordered,
+                currency,
+            },
+            arrived: Money {
+                amount: arrived,
+                currency,
+            },
+            picked_up: Money {
+                amount: picked_up,
+                currency,
+            },
         })
     }
 }
@@ -1,9 +1,11 @@
 pub use domain::*;
 use once_cell::sync::OnceCell;
+pub use route::*;
 use snowflake::SnowflakeIdGenerator;
 use std::sync::Mutex;
 
 mod domain;
+mod route;
 
 pub(crate) fn order_item_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
     static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
@@ -12,3 +14,11 @@ pub(crate) fn order_item_id_generator() -> &'static Mutex<SnowflakeIdGenerator>
         Mutex::new(generator)
     })
 }
+
+pub(crate) fn order_item_status_history_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 3);
+        Mutex::new(generator)
+    })
+}
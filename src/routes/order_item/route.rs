@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+use base64::Engine;
+
+use crate::errors::{AppError, ConstraintError, DatabaseResultExt, OrderError, OrderItemError};
+use crate::repositories::{
+    CurrencyRepo, CustomerRepo, OrderItemRepo, OrderItemStatusHistoryRepo, OrderRepo,
+    ProductRepository, ProductVariantRepo,
+};
+use crate::routes::{
+    Claims, CreateOrderItemResponse, CreateOrderItemsRequest, CurrencyJson, DeleteOrderItemRequest,
+    ListOrderItemStatusHistoriesResponse, ListOrderItemsRequest, ListOrderItemsResponse,
+    NewOrderItem, OrderItemSearchParameters, UpdateOrderItem, UpdateOrderItemRequest,
+    UpdateOrderItemStatuses, UpdateOrderItemStatusesRequest, UpdateOrderItemStatusesResponse,
+};
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "Create a new order item", skip(order_item_repo, order_repo, customer_repo, product_repo, product_variant_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_order_item_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Extension(order_repo): Extension<Arc<dyn OrderRepo + Sync + Send>>,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(product_variant_repo): Extension<Arc<dyn ProductVariantRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateOrderItemsRequest>, AppError>,
+) -> Result<Json<CreateOrderItemResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    // The foreign keys can't see the soft-deleted rows, so we have to check them here.
+    let customer = customer_repo
+        .get(payload.customer_id)
+        .await
+        .context("Failed to get a customer from database")?;
+
+    if customer.is_none_or(|e| e.deleted_at.is_some()) {
+        return Err(ConstraintError::ReferenceNotExist("customer"))?;
+    }
+
+    let product = product_repo
+        .get(payload.product_id)
+        .await
+        .context("Failed to get a product from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ConstraintError::ReferenceNotExist("product"))?;
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    // The order item is charged in the currency of the product.
+    let currency = CurrencyJson::find(product.currency, &currencies)
+        .map_err(anyhow::Error::msg)
+        .context("Failed to find the currency of a product")?;
+
+    let new_order_item = NewOrderItem::parse(payload, currency)
+        .await
+        .map_err(AppError::BadArguments)?;
+
+    // The variant takes the stock instead of the product.
+    let available = match new_order_item.variant_id {
+        Some(variant_id) => {
+            product_variant_repo
+                .get(variant_id)
+                .await
+                .context("Failed to get a product variant from database")?
+                .filter(|e| e.deleted_at.is_none() && e.product_id == product.id)
+                .ok_or(ConstraintError::ReferenceNotExist("product variant"))?
+                .available
+        }
+        None => product.available,
+    };
+
+    if new_order_item.reject_oversell && available < new_order_item.quantity.0 as i32 {
+        return Err(OrderItemError::OutOfStock)?;
+    }
+
+    if let Some(order_id) = new_order_item.order_id {
+        let order = order_repo
+            .get(order_id)
+            .await
+            .context("Failed to get an order from database")?
+            .filter(|e| e.deleted_at.is_none())
+            .ok_or(ConstraintError::ReferenceNotExist("order"))?;
+
+        if order.customer.id != new_order_item.customer_id.0.to_string() {
+            return Err(OrderError::CustomerMismatch)?;
+        }
+    }
+
+    // The stock may be taken by others after the checking above.
+    let id = order_item_repo
+        .create(new_order_item, claims.user_id()?)
+        .await
+        .db_context("Failed to insert a new order item in the database")??;
+
+    Ok(Json(CreateOrderItemResponse { id }))
+}
+
+#[tracing::instrument(name = "Get an order item", skip(order_item_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn get_order_item_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let order_item_id = params.get("id").and_then(|e| e.parse::<i64>().ok());
+
+    if order_item_id.is_none() {
+        return Err(AppError::BadArguments(
+            "There is no order item id in the query string".to_string(),
+        ));
+    }
+
+    let order_item_json = order_item_repo
+        .get(order_item_id.unwrap())
+        .await
+        .map(|e| e.map(Json))
+        .context("Failed to get an order item from database")?;
+
+    Ok(match order_item_json {
+        None => StatusCode::OK.into_response(),
+        Some(json) => json.into_response(),
+    })
+}
+
+#[tracing::instrument(name = "Update an order item", skip(order_item_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn update_order_item_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateOrderItemRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let order_item = order_item_repo
+        .get(id)
+        .await
+        .context("Failed to get an order item from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(OrderItemError::OrderItemNotFound)?;
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    // The unit price is kept in the currency of the order item.
+    let currency = CurrencyJson::find(order_item.currency, &currencies)
+        .map_err(anyhow::Error::msg)
+        .context("Failed to find the currency of an order item")?;
+
+    let update_order_item =
+        UpdateOrderItem::parse(payload, currency).map_err(AppError::BadArguments)?;
+
+    let need_update = update_order_item.quantity.is_some()
+        || update_order_item.unit_price.is_some()
+        || update_order_item.status.is_some();
+
+    if need_update {
+        // The status transition is checked again after the order item is locked.
+        order_item_repo
+            .update(update_order_item, claims.user_id()?)
+            .await
+            .db_context("Failed to update an order item in the database")??;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Update the statuses of order items", skip(order_item_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn update_order_item_statuses_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateOrderItemStatusesRequest>, AppError>,
+) -> Result<Json<UpdateOrderItemStatusesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let update_order_item_statuses =
+        UpdateOrderItemStatuses::parse(payload).map_err(AppError::BadArguments)?;
+
+    let data = order_item_repo
+        .update_statuses(update_order_item_statuses, claims.user_id()?)
+        .await
+        .context("Failed to update the statuses of order items in the database")?;
+
+    Ok(Json(UpdateOrderItemStatusesResponse { data }))
+}
+
+#[tracing::instrument(name = "Delete an order item", skip(order_item_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn delete_order_item_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteOrderItemRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    order_item_repo
+        .delete(payload.id)
+        .await
+        .context("Failed to delete an order item in the database")?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "List order items", skip(order_item_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_order_items_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Query(payload): Query<ListOrderItemsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let search_parameter = if let Some(keyword) = &payload.keyword {
+        base64::engine::general_purpose::STANDARD
+            .decode(keyword)
+            .as_ref()
+            .map(|e| serde_json::from_slice::<OrderItemSearchParameters>(e))
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+    } else {
+        OrderItemSearchParameters::default()
+    };
+
+    let page = payload.page.unwrap_or(0);
+    let page_size = payload.page_size.unwrap_or(20);
+
+    let data = order_item_repo
+        .list(search_parameter, page, page_size)
+        .await
+        .context("Failed to get order items from database")?;
+
+    let response = ListOrderItemsResponse { data };
+
+    Ok(Json(response))
+}
+
+#[tracing::instrument(name = "List the status histories of an order item", skip(order_item_repo, order_item_status_history_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_order_item_status_histories_handler(
+    claims: Claims,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Extension(order_item_status_history_repo): Extension<
+        Arc<dyn OrderItemStatusHistoryRepo + Sync + Send>,
+    >,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let order_item_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no order item id in the query string".to_string())
+        })?;
+
+    order_item_repo
+        .get(order_item_id)
+        .await
+        .context("Failed to get an order item from database")?
+        .ok_or(OrderItemError::OrderItemNotFound)?;
+
+    let data = order_item_status_history_repo
+        .list(order_item_id)
+        .await
+        .context("Failed to get the status histories of an order item from database")?;
+
+    Ok(Json(ListOrderItemStatusHistoriesResponse { data }))
+}
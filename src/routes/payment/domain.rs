@@ -0,0 +1,247 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+use crate::routes::{payment_id_generator, CurrencyJson, Money, ValidCustomerId};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct PaymentJson {
+    pub id: String,
+    pub customer_id: String,
+    pub amount: Money,
+    pub currency: i16,
+    pub method: PaymentMethod,
+    pub note: Option<String>,
+    pub order_item_ids: Vec<String>,
+    pub recorded_by: String,
+    pub recorded_by_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub voided_by: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreatePaymentRequest {
+    pub customer_id: i64,
+    pub amount: Decimal,
+    pub currency: i16,
+    pub method: PaymentMethod,
+    pub note: Option<String>,
+    pub order_item_ids: Option<Vec<String>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreatePaymentResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct VoidPaymentRequest {
+    pub id: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ListPaymentsRequest {
+    pub keyword: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListPaymentsResponse {
+    pub data: Vec<PaymentJson>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
+pub struct PaymentSearchParameters {
+    pub customer_id: Option<i64>,
+    pub currency: Option<i16>,
+    pub method: Option<PaymentMethod>,
+    /// `Some(true)` only lists the voided payments, `Some(false)` only lists the others.
+    pub voided: Option<bool>,
+}
+
+/// The balance of a customer in one currency, a positive balance means the customer has paid
+/// more than the order items are worth.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct BalanceJson {
+    pub currency: i16,
+    pub currency_name: Option<String>,
+    pub charged: Money,
+    pub paid: Money,
+    pub balance: Money,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CustomerBalancesResponse {
+    pub data: Vec<BalanceJson>,
+    /// The balance of all the currencies in the base currency, it's `None` when an exchange
+    /// rate is missing.
+    pub total: Option<BalanceJson>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMethod {
+    Cash,
+    #[serde(rename = "mpay")]
+    MPay,
+    BankTransfer,
+}
+
+impl PaymentMethod {
+    pub fn code(&self) -> i16 {
+        match self {
+            PaymentMethod::Cash => 0,
+            PaymentMethod::MPay => 1,
+            PaymentMethod::BankTransfer => 2,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "cash",
+            PaymentMethod::MPay => "mpay",
+            PaymentMethod::BankTransfer => "bank_transfer",
+        }
+    }
+}
+
+impl Display for PaymentMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<i16> for PaymentMethod {
+    type Error = String;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PaymentMethod::Cash),
+            1 => Ok(PaymentMethod::MPay),
+            2 => Ok(PaymentMethod::BankTransfer),
+            _ => Err(format!("{value} is not a valid payment method.")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidAmount(pub Money);
+
+impl ValidAmount {
+    pub fn parse(amount: Decimal, currency: &CurrencyJson) -> Result<Self, String> {
+        if amount <= Decimal::ZERO {
+            return Err("The amount should be greater than 0.".to_string());
+        }
+
+        Money::parse(amount, currency).map(Self)
+    }
+}
+
+#[derive(Debug)]
+pub struct NewPayment {
+    pub id: i64,
+    pub customer_id: ValidCustomerId,
+    pub amount: ValidAmount,
+    pub method: PaymentMethod,
+    pub note: Option<String>,
+    pub order_item_ids: Vec<i64>,
+}
+
+impl NewPayment {
+    /// The currency must be the one of the request.
+    pub async fn parse(req: CreatePaymentRequest, currency: &CurrencyJson) -> Result<Self, String> {
+        let amount = ValidAmount::parse(req.amount, currency)?;
+
+        let mut order_item_ids = req
+            .order_item_ids
+            .unwrap_or_default()
+            .iter()
+            .map(|e| e.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Can't parse order item id to i64.".to_string())?;
+        order_item_ids.sort();
+        order_item_ids.dedup();
+
+        let note = req.note.filter(|e| !e.trim().is_empty());
+
+        let id = async {
+            let generator = payment_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        }
+        .await;
+
+        Ok(Self {
+            id,
+            customer_id: ValidCustomerId(req.customer_id),
+            amount,
+            method: req.method,
+            note,
+            order_item_ids,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for PaymentJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let customer_id: i64 = row.try_get(1)?;
+        let amount: Decimal = row.try_get(2)?;
+        let currency: i16 = row.try_get(3)?;
+        let method: i16 = row.try_get(4)?;
+        let method = PaymentMethod::try_from(method).map_err(|e| Error::Decode(e.into()))?;
+        let note: Option<String> = row.try_get(5)?;
+        let recorded_by: uuid::Uuid = row.try_get(6)?;
+        let created_at: DateTime<Utc> = row.try_get(7)?;
+        let voided_at: Option<DateTime<Utc>> = row.try_get(8)?;
+        let voided_by: Option<uuid::Uuid> = row.try_get(9)?;
+        let recorded_by_username: Option<String> = row.try_get(10)?;
+        let order_item_ids: Vec<i64> = row.try_get(11)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            customer_id: customer_id.to_string(),
+            amount: Money { amount, currency },
+            currency,
+            method,
+            note,
+            order_item_ids: order_item_ids.iter().map(|e| e.to_string()).collect(),
+            recorded_by: recorded_by.to_string(),
+            recorded_by_username,
+            created_at,
+            voided_at,
+            voided_by: voided_by.map(|e| e.to_string()),
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for BalanceJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let currency: i16 = row.try_get(0)?;
+        let currency_name: Option<String> = row.try_get(1)?;
+        let charged: Decimal = row.try_get(2)?;
+        let paid: Decimal = row.try_get(3)?;
+
+        Ok(Self {
+            currency,
+            currency_name,
+            charged: Money {
+                amount: charged,
+                currency,
+            },
+            paid: Money {
+                amount: paid,
+                currency,
+            },
+            balance: Money {
+                amount: paid - charged,
+                currency,
+            },
+        })
+    }
+}
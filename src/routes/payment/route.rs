@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+use base64::Engine;
+
+use crate::configuration::ReportingSettings;
+use crate::errors::{AppError, ConstraintError, CustomerError, DatabaseResultExt, PaymentError};
+use crate::repositories::{
+    CurrencyRepo, CustomerRepo, ExchangeRateRepo, OrderItemRepo, PaymentRepo,
+};
+use crate::routes::{
+    currency_converter, BalanceJson, Claims, CreatePaymentRequest, CreatePaymentResponse,
+    CurrencyJson, CustomerBalancesResponse, ListPaymentsRequest, ListPaymentsResponse, Money,
+    NewPayment, PaymentSearchParameters, VoidPaymentRequest,
+};
+
+#[tracing::instrument(name = "Record a new payment", skip(payment_repo, customer_repo, order_item_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_payment_handler(
+    claims: Claims,
+    Extension(payment_repo): Extension<Arc<dyn PaymentRepo + Sync + Send>>,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreatePaymentRequest>, AppError>,
+) -> Result<Json<CreatePaymentResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+    let currency = CurrencyJson::find(payload.currency, &currencies)
+        .map_err(|_| ConstraintError::ReferenceNotExist("currency"))?;
+
+    let new_payment = NewPayment::parse(payload, currency)
+        .await
+        .map_err(AppError::BadArguments)?;
+
+    let customer = customer_repo
+        .get(new_payment.customer_id.0)
+        .await
+        .context("Failed to get a customer from database")?;
+
+    if customer.is_none_or(|e| e.deleted_at.is_some()) {
+        return Err(ConstraintError::ReferenceNotExist("customer"))?;
+    }
+
+    let customer_id = new_payment.customer_id.0.to_string();
+
+    for order_item_id in &new_payment.order_item_ids {
+        let order_item = order_item_repo
+            .get(*order_item_id)
+            .await
+            .context("Failed to get an order item from database")?;
+
+        if !order_item.is_some_and(|e| e.deleted_at.is_none() && e.customer.id == customer_id) {
+            return Err(PaymentError::OrderItemNotPayable(*order_item_id))?;
+        }
+    }
+
+    let id = payment_repo
+        .create(new_payment, claims.user_id()?)
+        .await
+        .db_context("Failed to insert a new payment in the database")?;
+
+    Ok(Json(CreatePaymentResponse { id }))
+}
+
+#[tracing::instrument(name = "Void a payment", skip(payment_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn void_payment_handler(
+    claims: Claims,
+    Extension(payment_repo): Extension<Arc<dyn PaymentRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<VoidPaymentRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let payment = payment_repo
+        .get(id)
+        .await
+        .context("Failed to get a payment from database")?
+        .ok_or(PaymentError::PaymentNotFound)?;
+
+    if payment.voided_at.is_some() {
+        return Err(PaymentError::PaymentIsVoided)?;
+    }
+
+    payment_repo
+        .void(id, claims.user_id()?)
+        .await
+        .context("Failed to void a payment in the database")?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "List payments", skip(payment_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_payments_handler(
+    claims: Claims,
+    Extension(payment_repo): Extension<Arc<dyn PaymentRepo + Sync + Send>>,
+    Query(payload): Query<ListPaymentsRequest>,
+) -> Result<Json<ListPaymentsResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let search_parameter = if let Some(keyword) = &payload.keyword {
+        base64::engine::general_purpose::STANDARD
+            .decode(keyword)
+            .as_ref()
+            .map(|e| serde_json::from_slice::<PaymentSearchParameters>(e))
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+    } else {
+        PaymentSearchParameters::default()
+    };
+
+    let page = payload.page.unwrap_or(0);
+    let page_size = payload.page_size.unwrap_or(20);
+
+    let data = payment_repo
+        .list(search_parameter, page, page_size)
+        .await
+        .context("Failed to get payments from database")?;
+
+    Ok(Json(ListPaymentsResponse { data }))
+}
+
+#[tracing::instrument(name = "Get the balances of a customer", skip(payment_repo, customer_repo, exchange_rate_repo, currency_repo, reporting, claims), fields(user_id=tracing::field::Empty))]
+pub async fn get_customer_balances_handler(
+    claims: Claims,
+    Extension(payment_repo): Extension<Arc<dyn PaymentRepo + Sync + Send>>,
+    Extension(customer_repo): Extension<Arc<dyn CustomerRepo + Sync + Send>>,
+    Extension(exchange_rate_repo): Extension<Arc<dyn ExchangeRateRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    Extension(reporting): Extension<ReportingSettings>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<CustomerBalancesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let customer_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no customer id in the query string".to_string())
+        })?;
+
+    customer_repo
+        .get(customer_id)
+        .await
+        .context("Failed to get a customer from database")?
+        .ok_or(CustomerError::CustomerNotFound)?;
+
+    let data = payment_repo
+        .balances(customer_id)
+        .await
+        .context("Failed to compute the balances of a customer from database")?;
+
+    let dated_balances = payment_repo
+        .dated_balances(customer_id, &reporting.time_zone)
+        .await
+        .context("Failed to compute the daily balances of a customer from database")?;
+
+    let converter = currency_converter(
+        &exchange_rate_repo,
+        &currency_repo,
+        &reporting,
+        &dated_balances,
+    )
+    .await?;
+    let total = converter
+        .total(&dated_balances, 2)
+        .map(|totals| BalanceJson {
+            currency: converter.base_currency.id,
+            currency_name: Some(converter.base_currency.code.clone()),
+            charged: totals[0],
+            paid: totals[1],
+            balance: Money {
+                amount: totals[1].amount - totals[0].amount,
+                currency: converter.base_currency.id,
+            },
+        });
+
+    Ok(Json(CustomerBalancesResponse { data, total }))
+}
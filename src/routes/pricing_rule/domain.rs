@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+use crate::routes::{pricing_rule_id_generator, CurrencyJson, Money};
+
+/// Turn the Japanese cost price of a product into its sell price:
+/// `cost = cost_price * (1 + tax_rate) * exchange_rate` and
+/// `price = cost * (1 + markup_rate) + handling_fee`, both rounded to the minor units of the
+/// currency.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PricingRuleJson {
+    pub id: String,
+    pub name: String,
+    /// The currency of the sell price.
+    pub currency: i16,
+    /// The amount of the currency for 1 JPY.
+    pub exchange_rate: Decimal,
+    pub markup_rate: Decimal,
+    /// The fee per item in the currency.
+    pub handling_fee: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceQuote {
+    pub cost: Decimal,
+    pub price: Decimal,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreatePricingRuleRequest {
+    pub name: String,
+    pub currency: i16,
+    pub exchange_rate: Decimal,
+    pub markup_rate: Decimal,
+    pub handling_fee: Option<Decimal>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreatePricingRuleResponse {
+    pub id: i64,
+}
+
+/// The prices of the products aren't changed until they are repriced.
+#[derive(serde::Deserialize, Debug)]
+pub struct UpdatePricingRuleRequest {
+    pub id: String,
+    pub name: Option<String>,
+    pub exchange_rate: Option<Decimal>,
+    pub markup_rate: Option<Decimal>,
+    pub handling_fee: Option<Decimal>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListPricingRulesResponse {
+    pub data: Vec<PricingRuleJson>,
+}
+
+#[derive(Debug)]
+pub struct ValidRate(pub Decimal);
+
+#[derive(Debug)]
+pub struct NewPricingRule {
+    pub id: i64,
+    pub name: String,
+    pub currency: i16,
+    pub exchange_rate: ValidRate,
+    pub markup_rate: ValidRate,
+    pub handling_fee: Decimal,
+}
+
+#[derive(Debug)]
+pub struct UpdatePricingRule {
+    pub id: i64,
+    pub name: Option<String>,
+    pub exchange_rate: Option<ValidRate>,
+    pub markup_rate: Option<ValidRate>,
+    pub handling_fee: Option<Decimal>,
+}
+
+impl PricingRuleJson {
+    /// The currency must be the currency of the pricing rule. Fail when the cost or the price
+    /// is too large to be stored.
+    pub fn quote(
+        &self,
+        cost_price: Decimal,
+        cost_tax_rate: Option<Decimal>,
+        currency: &CurrencyJson,
+    ) -> Result<PriceQuote, String> {
+        let too_large = || "The price computed by the pricing rule is too large.".to_string();
+        let tax_rate = cost_tax_rate.unwrap_or_default();
+
+        let cost = cost_price
+            .checked_mul(Decimal::ONE + tax_rate)
+            .and_then(|e| e.checked_mul(self.exchange_rate))
+            .map(|e| Money::round(e, currency).amount)
+            .ok_or_else(too_large)?;
+        let price = cost
+            .checked_mul(Decimal::ONE + self.markup_rate)
+            .and_then(|e| e.checked_add(self.handling_fee))
+            .map(|e| Money::round(e, currency).amount)
+            .ok_or_else(too_large)?;
+
+        if price >= Money::upper_bound() {
+            return Err(too_large());
+        }
+
+        Ok(PriceQuote { cost, price })
+    }
+}
+
+fn parse_name(name: String) -> Result<String, String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Pricing rule name is empty.".to_string());
+    }
+
+    Ok(name.to_owned())
+}
+
+fn parse_handling_fee(handling_fee: Decimal) -> Result<Decimal, String> {
+    if handling_fee.is_sign_negative() {
+        return Err("Handling fee can't be negative.".to_string());
+    }
+
+    if handling_fee >= Money::upper_bound() {
+        return Err(format!(
+            "Handling fee must be less than {}.",
+            Money::upper_bound()
+        ));
+    }
+
+    if handling_fee.scale() > 2 {
+        return Err("Handling fee can't have more than 2 decimal places.".to_string());
+    }
+
+    Ok(handling_fee)
+}
+
+impl ValidRate {
+    pub fn parse_exchange_rate(rate: Decimal) -> Result<Self, String> {
+        if rate <= Decimal::ZERO {
+            return Err("Exchange rate should be positive.".to_string());
+        }
+
+        // The rate is stored as `decimal(12, 6)`.
+        if rate >= Decimal::from(1_000_000) {
+            return Err("Exchange rate must be less than 1000000.".to_string());
+        }
+
+        if rate.scale() > 6 {
+            return Err("Exchange rate can't have more than 6 decimal places.".to_string());
+        }
+
+        Ok(Self(rate))
+    }
+
+    pub fn parse_markup_rate(rate: Decimal) -> Result<Self, String> {
+        if rate.is_sign_negative() {
+            return Err("Markup rate can't be negative.".to_string());
+        }
+
+        // The rate is stored as `decimal(6, 4)`.
+        if rate >= Decimal::from(100) {
+            return Err("Markup rate must be less than 100.".to_string());
+        }
+
+        if rate.scale() > 4 {
+            return Err("Markup rate can't have more than 4 decimal places.".to_string());
+        }
+
+        Ok(Self(rate))
+    }
+}
+
+impl NewPricingRule {
+    pub fn parse(req: CreatePricingRuleRequest) -> Result<Self, String> {
+        let id = {
+            let generator = pricing_rule_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            name: parse_name(req.name)?,
+            currency: req.currency,
+            exchange_rate: ValidRate::parse_exchange_rate(req.exchange_rate)?,
+            markup_rate: ValidRate::parse_markup_rate(req.markup_rate)?,
+            handling_fee: parse_handling_fee(req.handling_fee.unwrap_or_default())?,
+        })
+    }
+}
+
+impl UpdatePricingRule {
+    pub fn parse(req: UpdatePricingRuleRequest) -> Result<Self, String> {
+        let id = req
+            .id
+            .parse::<i64>()
+            .map_err(|_| "Can't parse id to i64.".to_string())?;
+
+        Ok(Self {
+            id,
+            name: req.name.map(parse_name).transpose()?,
+            exchange_rate: req
+                .exchange_rate
+                .map(ValidRate::parse_exchange_rate)
+                .transpose()?,
+            markup_rate: req
+                .markup_rate
+                .map(ValidRate::parse_markup_rate)
+                .transpose()?,
+            handling_fee: req.handling_fee.map(parse_handling_fee).transpose()?,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for PricingRuleJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let name: String = row.try_get(1)?;
+        let currency: i16 = row.try_get(2)?;
+        let exchange_rate: Decimal = row.try_get(3)?;
+        let markup_rate: Decimal = row.try_get(4)?;
+        let handling_fee: Decimal = row.try_get(5)?;
+        let created_at: DateTime<Utc> = row.try_get(6)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(7)?;
+        let deleted_at: Option<DateTime<Utc>> = row.try_get(8)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            name,
+            currency,
+            exchange_rate,
+            markup_rate,
+            handling_fee,
+            created_at,
+            updated_at,
+            deleted_at,
+        })
+    }
+}
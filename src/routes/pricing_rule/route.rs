@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+
+use crate::errors::{AppError, DatabaseResultExt, PricingRuleError};
+use crate::repositories::PricingRuleRepo;
+use crate::routes::{
+    Claims, CreatePricingRuleRequest, CreatePricingRuleResponse, ListPricingRulesResponse,
+    NewPricingRule, UpdatePricingRule, UpdatePricingRuleRequest,
+};
+
+#[tracing::instrument(name = "Create a new pricing rule", skip(pricing_rule_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_pricing_rule_handler(
+    claims: Claims,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreatePricingRuleRequest>, AppError>,
+) -> Result<Json<CreatePricingRuleResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let new_pricing_rule = NewPricingRule::parse(payload).map_err(AppError::BadArguments)?;
+
+    let id = pricing_rule_repo
+        .create(new_pricing_rule)
+        .await
+        .db_context("Failed to insert a new pricing rule in the database")?;
+
+    Ok(Json(CreatePricingRuleResponse { id }))
+}
+
+#[tracing::instrument(name = "Get a pricing rule", skip(pricing_rule_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn get_pricing_rule_handler(
+    claims: Claims,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let pricing_rule_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no pricing rule id in the query string".to_string())
+        })?;
+
+    let pricing_rule = pricing_rule_repo
+        .get(pricing_rule_id)
+        .await
+        .context("Failed to get a pricing rule from database")?
+        .ok_or(PricingRuleError::PricingRuleNotFound)?;
+
+    Ok(Json(pricing_rule))
+}
+
+#[tracing::instrument(name = "Update a pricing rule", skip(pricing_rule_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn update_pricing_rule_handler(
+    claims: Claims,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdatePricingRuleRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let update_pricing_rule = UpdatePricingRule::parse(payload).map_err(AppError::BadArguments)?;
+
+    pricing_rule_repo
+        .get(update_pricing_rule.id)
+        .await
+        .context("Failed to get a pricing rule from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(PricingRuleError::PricingRuleNotFound)?;
+
+    let need_update = update_pricing_rule.name.is_some()
+        || update_pricing_rule.exchange_rate.is_some()
+        || update_pricing_rule.markup_rate.is_some()
+        || update_pricing_rule.handling_fee.is_some();
+
+    if need_update {
+        pricing_rule_repo
+            .update(update_pricing_rule)
+            .await
+            .db_context("Failed to update a pricing rule in the database")?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "List pricing rules", skip(pricing_rule_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_pricing_rules_handler(
+    claims: Claims,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+) -> Result<Json<ListPricingRulesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let data = pricing_rule_repo
+        .list()
+        .await
+        .context("Failed to get pricing rules from database")?;
+
+    Ok(Json(ListPricingRulesResponse { data }))
+}
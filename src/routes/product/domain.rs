@@ -1,25 +1,55 @@
-use crate::routes::product_id_generator;
+use std::fmt::{Display, Formatter};
+
+use crate::routes::{
+    product_id_generator, product_image_id_generator, product_price_history_id_generator,
+    product_variant_id_generator, stock_adjustment_id_generator, CurrencyJson, Money,
+    PricingRuleJson,
+};
+use crate::utils::deserialize_nullable;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use sqlx::postgres::PgRow;
 use sqlx::Row;
 
+/// The currency and price are required unless the product is priced by a pricing rule, which
+/// computes them from the cost price instead.
 #[derive(serde::Deserialize, Debug)]
 pub struct CreateProductRequest {
     pub name: String,
-    pub currency: i16,
-    pub price: f64,
+    pub currency: Option<i16>,
+    pub price: Option<Decimal>,
+    pub category_id: Option<String>,
+    pub tags: Option<Vec<String>>,
+    /// The shelf price in JPY.
+    pub cost_price: Option<Decimal>,
+    /// The Japanese consumption tax rate paid on the cost price, e.g. `0.10`.
+    pub cost_tax_rate: Option<Decimal>,
+    pub pricing_rule_id: Option<String>,
 }
 
 pub struct ValidCurrency(pub i16);
 
 pub struct ValidProductName(pub String);
 
+/// The tags are trimmed, lowercased and deduplicated, so that they match case-insensitively.
+#[derive(Debug)]
+pub struct ValidTags(pub Vec<String>);
+
+pub struct ValidCostPrice(pub Decimal);
+
+pub struct ValidTaxRate(pub Decimal);
+
 pub struct NewProduct {
     pub id: i64,
     pub name: ValidProductName,
-    pub currency: ValidCurrency,
-    pub price: f64,
+    pub price: Money,
+    pub category_id: Option<i64>,
+    pub tags: ValidTags,
+    pub cost_price: Option<Decimal>,
+    pub cost_tax_rate: Option<Decimal>,
+    pub pricing_rule_id: Option<i64>,
+    /// The cost price converted into the currency, it's known only with a pricing rule.
+    pub cost: Option<Decimal>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -27,12 +57,65 @@ pub struct ProductJson {
     pub id: String,
     pub name: String,
     pub currency: i16,
-    pub price: Decimal,
+    pub currency_code: Option<String>,
+    pub currency_symbol: Option<String>,
+    pub price: Money,
+    pub on_hand: i32,
+    pub reserved: i32,
+    /// The quantity which isn't reserved by the order items, it's negative when oversold.
+    pub available: i32,
+    pub variants: Vec<ProductVariantJson>,
+    pub category_id: Option<String>,
+    pub tags: Vec<String>,
+    pub images: Vec<ProductImageJson>,
+    pub cost_price: Option<Decimal>,
+    pub cost_tax_rate: Option<Decimal>,
+    pub pricing_rule_id: Option<String>,
+    pub cost: Option<Decimal>,
+    /// The price minus the cost.
+    pub margin: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// A variant of a product such as a size or a colour, it has its own stock.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ProductVariantJson {
+    pub id: String,
+    pub product_id: String,
+    pub sku: String,
+    pub options: Vec<VariantOptionJson>,
+    /// The currency of the product.
+    pub currency: i16,
+    /// Override the price of the product when it's set.
+    pub price: Option<Money>,
+    pub on_hand: i32,
+    pub reserved: i32,
+    pub available: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ProductImageJson {
+    pub id: String,
+    pub product_id: String,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VariantOptionJson {
+    pub name: String,
+    pub value: String,
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct CreateProductResponse {
     pub id: i64,
@@ -43,19 +126,90 @@ pub struct DeleteProductRequest {
     pub id: i64,
 }
 
+/// The currency and price can't be set on a product which is priced by a pricing rule, they are
+/// recomputed when any of the cost fields or the pricing rule is changed.
 #[derive(serde::Deserialize, Debug)]
 pub struct UpdateProductRequest {
     pub id: String,
     pub name: Option<String>,
     pub currency: Option<i16>,
-    pub price: Option<f64>,
+    pub price: Option<Decimal>,
+    pub category_id: Option<String>,
+    /// Replace all the tags of the product.
+    pub tags: Option<Vec<String>>,
+    pub cost_price: Option<Decimal>,
+    pub cost_tax_rate: Option<Decimal>,
+    /// A `null` pricing rule stops computing the price, a missing one keeps it unchanged.
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub pricing_rule_id: Option<Option<String>>,
 }
 
 pub struct UpdateProduct {
     pub id: i64,
     pub name: Option<ValidProductName>,
     pub currency: Option<ValidCurrency>,
-    pub price: Option<f64>,
+    /// The price in the new currency, or in the current currency when it's unchanged.
+    pub price: Option<Money>,
+    pub category_id: Option<i64>,
+    pub tags: Option<ValidTags>,
+    pub cost_price: Option<Decimal>,
+    pub cost_tax_rate: Option<Decimal>,
+    pub pricing_rule_id: Option<Option<i64>>,
+    pub cost: Option<Decimal>,
+}
+
+/// A change of the price and/or currency of a product.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ProductPriceHistoryJson {
+    pub id: String,
+    pub product_id: String,
+    pub previous_currency: i16,
+    pub previous_price: Money,
+    pub currency: i16,
+    pub price: Money,
+    pub changed_by: String,
+    pub changed_by_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListProductPriceHistoriesResponse {
+    pub data: Vec<ProductPriceHistoryJson>,
+}
+
+#[derive(Debug)]
+pub struct NewProductPriceHistory {
+    pub id: i64,
+    pub product_id: i64,
+    pub previous: Money,
+    pub current: Money,
+    pub changed_by: uuid::Uuid,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RepriceProductsRequest {
+    /// Only reprice the products of the pricing rule, or all the priced products when it's
+    /// absent.
+    pub pricing_rule_id: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RepricedProductJson {
+    pub id: String,
+    pub currency: i16,
+    pub previous_price: Money,
+    pub price: Money,
+    pub cost: Decimal,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RepriceProductsResponse {
+    pub data: Vec<RepricedProductJson>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReindexSearchTextsResponse {
+    pub indexed: usize,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -75,14 +229,161 @@ pub struct ProductSearchParameters {
     pub id: Option<i64>,
     #[serde(rename(deserialize = "name"))]
     pub partial_name: Option<String>,
+    /// Match the products in the category or any of its descendants.
+    pub category_id: Option<i64>,
+    /// Match the products which have any of the tags.
+    pub any_tags: Option<Vec<String>>,
+    /// Match the products which have all the tags.
+    pub all_tags: Option<Vec<String>>,
+    /// Match the names which contain the text or are similar to it, in any of Chinese, Japanese
+    /// and English, e.g. "pokmon" matches "ポケモン カード".
+    pub search: Option<String>,
+    /// Sort by the relevance to the `search` by default when it's given, otherwise by the id.
+    pub sort: Option<ProductSort>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSort {
+    Id,
+    Relevance,
+}
+
+impl ValidTags {
+    pub fn parse(tags: Vec<String>) -> Result<Self, String> {
+        let mut valid_tags: Vec<String> = vec![];
+
+        for tag in tags {
+            let tag = tag.trim().to_lowercase();
+
+            if tag.is_empty() {
+                return Err("Tag is empty.".to_string());
+            }
+
+            if tag.chars().count() > 64 {
+                return Err("Tag can't be longer than 64 characters.".to_string());
+            }
+
+            if !valid_tags.contains(&tag) {
+                valid_tags.push(tag);
+            }
+        }
+
+        Ok(Self(valid_tags))
+    }
+}
+
+impl ValidCurrency {
+    /// The currency must be one of the known currencies.
+    pub fn parse(currency: i16, currencies: &[CurrencyJson]) -> Result<Self, String> {
+        CurrencyJson::find(currency, currencies).map(|e| Self(e.id))
+    }
+}
+
+impl ValidCostPrice {
+    pub fn parse(cost_price: Decimal) -> Result<Self, String> {
+        if cost_price.is_sign_negative() {
+            return Err("Cost price can't be negative.".to_string());
+        }
+
+        if cost_price >= Money::upper_bound() {
+            return Err(format!(
+                "Cost price must be less than {}.",
+                Money::upper_bound()
+            ));
+        }
+
+        if cost_price.scale() > 2 {
+            return Err("Cost price can't have more than 2 decimal places.".to_string());
+        }
+
+        Ok(Self(cost_price))
+    }
+}
+
+impl ValidTaxRate {
+    pub fn parse(rate: Decimal) -> Result<Self, String> {
+        if rate.is_sign_negative() || rate > Decimal::ONE {
+            return Err("Tax rate should be between 0 and 1.".to_string());
+        }
+
+        if rate.scale() > 4 {
+            return Err("Tax rate can't have more than 4 decimal places.".to_string());
+        }
+
+        Ok(Self(rate))
+    }
+}
+
+fn parse_category_id(category_id: Option<String>) -> Result<Option<i64>, String> {
+    category_id
+        .map(|e| e.parse::<i64>())
+        .transpose()
+        .map_err(|_| "Can't parse category id to i64.".to_string())
+}
+
+pub fn parse_pricing_rule_id(pricing_rule_id: Option<&String>) -> Result<Option<i64>, String> {
+    pricing_rule_id
+        .map(|e| e.parse::<i64>())
+        .transpose()
+        .map_err(|_| "Can't parse pricing rule id to i64.".to_string())
 }
 
 impl NewProduct {
-    pub async fn parse(req: CreateProductRequest) -> Result<Self, String> {
+    /// The pricing rule must be the one referenced by the request.
+    pub async fn parse(
+        req: CreateProductRequest,
+        pricing_rule: Option<&PricingRuleJson>,
+        currencies: &[CurrencyJson],
+    ) -> Result<Self, String> {
         if req.name.trim().is_empty() {
             return Err("Product name is empty.".to_string());
         }
 
+        let cost_price = req
+            .cost_price
+            .map(ValidCostPrice::parse)
+            .transpose()?
+            .map(|e| e.0);
+        let cost_tax_rate = req
+            .cost_tax_rate
+            .map(ValidTaxRate::parse)
+            .transpose()?
+            .map(|e| e.0);
+
+        let (price, cost) = match pricing_rule {
+            Some(rule) => {
+                if req.price.is_some() {
+                    return Err("Price is computed by the pricing rule.".to_string());
+                }
+
+                if req.currency.is_some_and(|e| e != rule.currency) {
+                    return Err("Currency doesn't match the pricing rule.".to_string());
+                }
+
+                let cost_price =
+                    cost_price.ok_or("Cost price is required by the pricing rule.".to_string())?;
+                let currency = CurrencyJson::find(rule.currency, currencies)?;
+                let quote = rule.quote(cost_price, cost_tax_rate, currency)?;
+
+                let price = Money {
+                    amount: quote.price,
+                    currency: currency.id,
+                };
+
+                (price, Some(quote.cost))
+            }
+            None => {
+                let currency = req.currency.ok_or("Currency is missing.".to_string())?;
+                let price = req.price.ok_or("Price is missing.".to_string())?;
+
+                (
+                    Money::parse(price, CurrencyJson::find(currency, currencies)?)?,
+                    None,
+                )
+            }
+        };
+
         let id = async {
             let generator = product_id_generator();
             let mut generator = generator.lock().unwrap();
@@ -93,14 +394,26 @@ impl NewProduct {
         Ok(Self {
             id,
             name: ValidProductName(req.name.trim().to_owned()),
-            currency: ValidCurrency(req.currency),
-            price: req.price,
+            price,
+            category_id: parse_category_id(req.category_id)?,
+            tags: ValidTags::parse(req.tags.unwrap_or_default())?,
+            cost_price,
+            cost_tax_rate,
+            pricing_rule_id: parse_pricing_rule_id(req.pricing_rule_id.as_ref())?,
+            cost,
         })
     }
 }
 
 impl UpdateProduct {
-    pub async fn parse(req: UpdateProductRequest) -> Result<Self, String> {
+    /// The new price and/or currency are checked against the current product, the price must
+    /// fit the minor units of its currency, e.g. a product can't be moved from HKD to JPY at
+    /// 19.99.
+    pub async fn parse(
+        req: UpdateProductRequest,
+        current: &ProductJson,
+        currencies: &[CurrencyJson],
+    ) -> Result<Self, String> {
         if req.name.is_some() && req.name.as_ref().unwrap().trim().is_empty() {
             return Err("Product name is empty.".to_string());
         }
@@ -110,11 +423,136 @@ impl UpdateProduct {
             .parse::<i64>()
             .map_err(|_| "Can't parse id to i64.".to_string())?;
 
+        let currency = req
+            .currency
+            .map(|e| ValidCurrency::parse(e, currencies))
+            .transpose()?;
+
+        let price_currency = CurrencyJson::find(
+            currency.as_ref().map_or(current.currency, |e| e.0),
+            currencies,
+        )?;
+        let price = req
+            .price
+            .map(|e| Money::parse(e, price_currency))
+            .transpose()?;
+
+        // The current price is kept, so it must fit the new currency.
+        if price.is_none() && currency.is_some() {
+            Money::parse(current.price.amount, price_currency)?;
+        }
+
         Ok(Self {
             id,
             name: req.name.map(|e| ValidProductName(e.trim().to_owned())),
-            currency: req.currency.map(ValidCurrency),
-            price: req.price,
+            currency,
+            price,
+            category_id: parse_category_id(req.category_id)?,
+            tags: req.tags.map(ValidTags::parse).transpose()?,
+            cost_price: req
+                .cost_price
+                .map(ValidCostPrice::parse)
+                .transpose()?
+                .map(|e| e.0),
+            cost_tax_rate: req
+                .cost_tax_rate
+                .map(ValidTaxRate::parse)
+                .transpose()?
+                .map(|e| e.0),
+            pricing_rule_id: req
+                .pricing_rule_id
+                .map(|e| parse_pricing_rule_id(e.as_ref()))
+                .transpose()?,
+            cost: None,
+        })
+    }
+
+    /// Whether the cost price, the tax rate or the pricing rule is changed, so that the price
+    /// has to be recomputed.
+    pub fn changes_cost(&self) -> bool {
+        self.cost_price.is_some() || self.cost_tax_rate.is_some() || self.pricing_rule_id.is_some()
+    }
+
+    /// Recompute the price of the product with its pricing rule, the unchanged cost fields are
+    /// taken from the current product.
+    pub fn apply_pricing_rule(
+        &mut self,
+        rule: &PricingRuleJson,
+        current: &ProductJson,
+        currencies: &[CurrencyJson],
+    ) -> Result<(), String> {
+        if self.price.is_some() || self.currency.is_some() {
+            return Err("Price is computed by the pricing rule.".to_string());
+        }
+
+        let cost_price = self
+            .cost_price
+            .or(current.cost_price)
+            .ok_or("Cost price is required by the pricing rule.".to_string())?;
+        let currency = CurrencyJson::find(rule.currency, currencies)?;
+        let quote = rule.quote(
+            cost_price,
+            self.cost_tax_rate.or(current.cost_tax_rate),
+            currency,
+        )?;
+
+        self.currency = Some(ValidCurrency(rule.currency));
+        self.price = Some(Money {
+            amount: quote.price,
+            currency: rule.currency,
+        });
+        self.cost = Some(quote.cost);
+
+        Ok(())
+    }
+}
+
+impl NewProductPriceHistory {
+    pub fn new(product_id: i64, previous: Money, current: Money, changed_by: uuid::Uuid) -> Self {
+        let id = {
+            let generator = product_price_history_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Self {
+            id,
+            product_id,
+            previous,
+            current,
+            changed_by,
+        }
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for ProductPriceHistoryJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(0)?;
+        let product_id: i64 = row.try_get(1)?;
+        let previous_currency: i16 = row.try_get(2)?;
+        let previous_price: Decimal = row.try_get(3)?;
+        let currency: i16 = row.try_get(4)?;
+        let price: Decimal = row.try_get(5)?;
+        let changed_by: uuid::Uuid = row.try_get(6)?;
+        let created_at: DateTime<Utc> = row.try_get(7)?;
+        let changed_by_username: Option<String> = row.try_get(8)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            product_id: product_id.to_string(),
+            previous_currency,
+            previous_price: Money {
+                amount: previous_price,
+                currency: previous_currency,
+            },
+            currency,
+            price: Money {
+                amount: price,
+                currency,
+            },
+            changed_by: changed_by.to_string(),
+            changed_by_username,
+            created_at,
         })
     }
 }
@@ -128,15 +566,530 @@ impl<'r> ::sqlx::FromRow<'r, PgRow> for ProductJson {
         let created_at: DateTime<Utc> = row.try_get(4)?;
         let updated_at: Option<DateTime<Utc>> = row.try_get(5)?;
         let deleted_at: Option<DateTime<Utc>> = row.try_get(6)?;
+        let on_hand: i32 = row.try_get(7)?;
+        let reserved: i32 = row.try_get(8)?;
+        let category_id: Option<i64> = row.try_get(9)?;
+        let cost_price: Option<Decimal> = row.try_get(10)?;
+        let cost_tax_rate: Option<Decimal> = row.try_get(11)?;
+        let pricing_rule_id: Option<i64> = row.try_get(12)?;
+        let cost: Option<Decimal> = row.try_get(13)?;
+        let currency_code: Option<String> = row.try_get(14)?;
+        let currency_symbol: Option<String> = row.try_get(15)?;
 
         Ok(Self {
             id: id.to_string(),
             name,
             currency,
+            currency_code,
+            currency_symbol,
+            price: Money {
+                amount: price,
+                currency,
+            },
+            on_hand,
+            reserved,
+            available: on_hand - reserved,
+            variants: vec![],
+            category_id: category_id.map(|e| e.to_string()),
+            tags: vec![],
+            images: vec![],
+            cost_price,
+            cost_tax_rate,
+            pricing_rule_id: pricing_rule_id.map(|e| e.to_string()),
+            cost,
+            margin: cost.map(|e| price - e),
+            created_at,
+            updated_at,
+            deleted_at,
+        })
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct StockAdjustmentJson {
+    pub id: String,
+    pub product_id: String,
+    pub variant_id: Option<String>,
+    pub kind: StockAdjustmentKind,
+    /// The change of the on-hand quantity.
+    pub quantity: i32,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// `quantity` is the received or damaged amount, or the signed change for a correction.
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateStockAdjustmentRequest {
+    pub product_id: i64,
+    /// Adjust the stock of the variant instead of the product.
+    pub variant_id: Option<String>,
+    pub kind: StockAdjustmentKind,
+    pub quantity: i32,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateStockAdjustmentResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListStockAdjustmentsResponse {
+    pub data: Vec<StockAdjustmentJson>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StockAdjustmentKind {
+    Received,
+    Damaged,
+    Correction,
+}
+
+impl StockAdjustmentKind {
+    pub fn code(&self) -> i16 {
+        match self {
+            StockAdjustmentKind::Received => 0,
+            StockAdjustmentKind::Damaged => 1,
+            StockAdjustmentKind::Correction => 2,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockAdjustmentKind::Received => "received",
+            StockAdjustmentKind::Damaged => "damaged",
+            StockAdjustmentKind::Correction => "correction",
+        }
+    }
+}
+
+impl Display for StockAdjustmentKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<i16> for StockAdjustmentKind {
+    type Error = String;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(StockAdjustmentKind::Received),
+            1 => Ok(StockAdjustmentKind::Damaged),
+            2 => Ok(StockAdjustmentKind::Correction),
+            _ => Err(format!("{value} is not a valid stock adjustment kind.")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NewStockAdjustment {
+    pub id: i64,
+    pub product_id: i64,
+    pub variant_id: Option<i64>,
+    pub kind: StockAdjustmentKind,
+    /// The signed change of the on-hand quantity.
+    pub quantity: i32,
+    pub reason: String,
+}
+
+impl NewStockAdjustment {
+    pub fn parse(req: CreateStockAdjustmentRequest) -> Result<Self, String> {
+        let reason = req.reason.trim().to_string();
+        if reason.is_empty() {
+            return Err("The reason of a stock adjustment is empty.".to_string());
+        }
+
+        let quantity = match req.kind {
+            StockAdjustmentKind::Received | StockAdjustmentKind::Damaged if req.quantity <= 0 => {
+                return Err(format!("The {} quantity must be greater than 0.", req.kind));
+            }
+            StockAdjustmentKind::Correction if req.quantity == 0 => {
+                return Err("The correction quantity can't be 0.".to_string());
+            }
+            StockAdjustmentKind::Damaged => -req.quantity,
+            _ => req.quantity,
+        };
+
+        let variant_id = req
+            .variant_id
+            .map(|e| e.parse::<i64>())
+            .transpose()
+            .map_err(|_| "Can't parse variant id to i64.".to_string())?;
+
+        let id = {
+            let generator = stock_adjustment_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            product_id: req.product_id,
+            variant_id,
+            kind: req.kind,
+            quantity,
+            reason,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for StockAdjustmentJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(0)?;
+        let product_id: i64 = row.try_get(1)?;
+        let kind: i16 = row.try_get(2)?;
+        let kind =
+            StockAdjustmentKind::try_from(kind).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let quantity: i32 = row.try_get(3)?;
+        let reason: String = row.try_get(4)?;
+        let created_by: uuid::Uuid = row.try_get(5)?;
+        let created_at: DateTime<Utc> = row.try_get(6)?;
+        let variant_id: Option<i64> = row.try_get(7)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            product_id: product_id.to_string(),
+            variant_id: variant_id.map(|e| e.to_string()),
+            kind,
+            quantity,
+            reason,
+            created_by: created_by.to_string(),
+            created_at,
+        })
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateProductVariantRequest {
+    pub product_id: i64,
+    pub sku: String,
+    pub options: Vec<VariantOptionJson>,
+    pub price: Option<Decimal>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateProductVariantResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct UpdateProductVariantRequest {
+    pub id: String,
+    pub sku: Option<String>,
+    pub options: Option<Vec<VariantOptionJson>>,
+    pub price: Option<Decimal>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeleteProductVariantRequest {
+    pub id: String,
+}
+
+#[derive(Debug)]
+pub struct ValidSku(pub String);
+
+/// The option name/value pairs of a variant, the names are unique.
+#[derive(Debug)]
+pub struct ValidVariantOptions(pub Vec<VariantOptionJson>);
+
+#[derive(Debug)]
+pub struct ValidVariantPrice(pub Money);
+
+#[derive(Debug)]
+pub struct NewProductVariant {
+    pub id: i64,
+    pub product_id: i64,
+    pub sku: ValidSku,
+    pub options: ValidVariantOptions,
+    pub price: Option<ValidVariantPrice>,
+}
+
+#[derive(Debug)]
+pub struct UpdateProductVariant {
+    pub id: i64,
+    pub sku: Option<ValidSku>,
+    pub options: Option<ValidVariantOptions>,
+    pub price: Option<ValidVariantPrice>,
+}
+
+impl ValidSku {
+    pub fn parse(sku: String) -> Result<Self, String> {
+        let sku = sku.trim();
+
+        if sku.is_empty() {
+            return Err("SKU is empty.".to_string());
+        }
+
+        if sku.chars().count() > 64 {
+            return Err("SKU can't be longer than 64 characters.".to_string());
+        }
+
+        Ok(Self(sku.to_owned()))
+    }
+}
+
+impl ValidVariantOptions {
+    pub fn parse(options: Vec<VariantOptionJson>) -> Result<Self, String> {
+        if options.is_empty() {
+            return Err("A variant should have at least one option.".to_string());
+        }
+
+        let mut valid_options: Vec<VariantOptionJson> = vec![];
+
+        for option in options {
+            let name = option.name.trim().to_owned();
+            let value = option.value.trim().to_owned();
+
+            if name.is_empty() || value.is_empty() {
+                return Err("The name and value of an option can't be empty.".to_string());
+            }
+
+            if valid_options.iter().any(|e| e.name == name) {
+                return Err(format!("The option {name} is duplicate."));
+            }
+
+            valid_options.push(VariantOptionJson { name, value });
+        }
+
+        Ok(Self(valid_options))
+    }
+}
+
+impl ValidVariantPrice {
+    /// A variant is sold in the currency of its product.
+    pub fn parse(price: Decimal, currency: &CurrencyJson) -> Result<Self, String> {
+        Money::parse(price, currency).map(Self)
+    }
+}
+
+impl NewProductVariant {
+    /// The currency must be the one of the product.
+    pub fn parse(
+        req: CreateProductVariantRequest,
+        currency: &CurrencyJson,
+    ) -> Result<Self, String> {
+        let sku = ValidSku::parse(req.sku)?;
+        let options = ValidVariantOptions::parse(req.options)?;
+        let price = req
+            .price
+            .map(|e| ValidVariantPrice::parse(e, currency))
+            .transpose()?;
+
+        let id = {
+            let generator = product_variant_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        Ok(Self {
+            id,
+            product_id: req.product_id,
+            sku,
+            options,
             price,
+        })
+    }
+}
+
+impl UpdateProductVariant {
+    /// The currency must be the one of the product of the variant.
+    pub fn parse(
+        req: UpdateProductVariantRequest,
+        currency: &CurrencyJson,
+    ) -> Result<Self, String> {
+        let id = req
+            .id
+            .parse::<i64>()
+            .map_err(|_| "Can't parse id to i64.".to_string())?;
+
+        Ok(Self {
+            id,
+            sku: req.sku.map(ValidSku::parse).transpose()?,
+            options: req.options.map(ValidVariantOptions::parse).transpose()?,
+            price: req
+                .price
+                .map(|e| ValidVariantPrice::parse(e, currency))
+                .transpose()?,
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for ProductVariantJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(0)?;
+        let product_id: i64 = row.try_get(1)?;
+        let sku: String = row.try_get(2)?;
+        let options: String = row.try_get(3)?;
+        let options = serde_json::from_str::<Vec<VariantOptionJson>>(&options)
+            .map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let price: Option<Decimal> = row.try_get(4)?;
+        let on_hand: i32 = row.try_get(5)?;
+        let reserved: i32 = row.try_get(6)?;
+        let created_at: DateTime<Utc> = row.try_get(7)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(8)?;
+        let deleted_at: Option<DateTime<Utc>> = row.try_get(9)?;
+        let currency: i16 = row.try_get(10)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            product_id: product_id.to_string(),
+            sku,
+            options,
+            currency,
+            price: price.map(|amount| Money { amount, currency }),
+            on_hand,
+            reserved,
+            available: on_hand - reserved,
             created_at,
             updated_at,
             deleted_at,
         })
     }
 }
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateProductImageResponse {
+    pub id: i64,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeleteProductImageRequest {
+    pub id: String,
+}
+
+/// An uploaded image which is decoded successfully, with a thumbnail in JPEG.
+pub struct ValidProductImage {
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub content: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct NewProductImage {
+    pub id: i64,
+    pub product_id: i64,
+    pub storage_key: String,
+    pub thumbnail_key: String,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl ValidProductImage {
+    /// The longer side of the thumbnails in pixels.
+    pub const THUMBNAIL_SIZE: u32 = 320;
+
+    /// The longest side of the images in pixels which can be decoded.
+    pub const MAX_SIZE: u32 = 8000;
+
+    /// The most memory the decoder can allocate for an image, in bytes.
+    pub const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+
+    /// Decode the image to make sure it's a JPEG, PNG or WebP image, and generate its thumbnail.
+    /// The decoder is limited in size, so that a small file can't claim a huge image and exhaust
+    /// the memory. It's CPU-bound, so it should be called in a blocking task.
+    pub fn parse(content: Vec<u8>) -> Result<Self, String> {
+        let format = image::guess_format(&content)
+            .map_err(|_| "The file is not a supported image.".to_string())?;
+
+        let (content_type, extension) = match format {
+            image::ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+            image::ImageFormat::Png => ("image/png", "png"),
+            image::ImageFormat::WebP => ("image/webp", "webp"),
+            _ => return Err("Only JPEG, PNG and WebP images are supported.".to_string()),
+        };
+
+        let mut limits = image::Limits::default();
+        limits.max_image_width = Some(Self::MAX_SIZE);
+        limits.max_image_height = Some(Self::MAX_SIZE);
+        limits.max_alloc = Some(Self::MAX_ALLOC);
+
+        let mut reader = image::ImageReader::with_format(std::io::Cursor::new(&content), format);
+        reader.limits(limits);
+
+        let decoded = reader.decode().map_err(|e| match e {
+            image::ImageError::Limits(_) => format!(
+                "The image is too large, its sides can't be longer than {} pixels.",
+                Self::MAX_SIZE
+            ),
+            _ => "The image is broken.".to_string(),
+        })?;
+
+        let mut thumbnail = vec![];
+        decoded
+            .thumbnail(Self::THUMBNAIL_SIZE, Self::THUMBNAIL_SIZE)
+            .into_rgb8()
+            .write_to(
+                &mut std::io::Cursor::new(&mut thumbnail),
+                image::ImageFormat::Jpeg,
+            )
+            .map_err(|_| "Failed to generate the thumbnail.".to_string())?;
+
+        Ok(Self {
+            content_type,
+            extension,
+            width: decoded.width(),
+            height: decoded.height(),
+            content,
+            thumbnail,
+        })
+    }
+}
+
+impl NewProductImage {
+    /// The keys of the image and its thumbnail are derived from the product id and image id.
+    pub fn new(product_id: i64, image: &ValidProductImage, url: impl Fn(&str) -> String) -> Self {
+        let id = {
+            let generator = product_image_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        };
+
+        let storage_key = format!("products/{product_id}/{id}.{}", image.extension);
+        let thumbnail_key = format!("products/{product_id}/{id}_thumbnail.jpg");
+
+        Self {
+            id,
+            product_id,
+            url: url(&storage_key),
+            thumbnail_url: url(&thumbnail_key),
+            storage_key,
+            thumbnail_key,
+            content_type: image.content_type.to_owned(),
+            width: image.width as i32,
+            height: image.height as i32,
+        }
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for ProductImageJson {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let id: i64 = row.try_get(0)?;
+        let product_id: i64 = row.try_get(1)?;
+        let url: String = row.try_get(2)?;
+        let thumbnail_url: String = row.try_get(3)?;
+        let content_type: String = row.try_get(4)?;
+        let width: i32 = row.try_get(5)?;
+        let height: i32 = row.try_get(6)?;
+        let created_at: DateTime<Utc> = row.try_get(7)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            product_id: product_id.to_string(),
+            url,
+            thumbnail_url,
+            content_type,
+            width,
+            height,
+            created_at,
+        })
+    }
+}
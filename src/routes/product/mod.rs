@@ -16,3 +16,35 @@ pub(crate) fn product_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
         Mutex::new(generator)
     })
 }
+
+pub(crate) fn stock_adjustment_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 7);
+        Mutex::new(generator)
+    })
+}
+
+pub(crate) fn product_variant_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 8);
+        Mutex::new(generator)
+    })
+}
+
+pub(crate) fn product_image_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 10);
+        Mutex::new(generator)
+    })
+}
+
+pub(crate) fn product_price_history_id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    static INSTANCE: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let generator = SnowflakeIdGenerator::new(0, 13);
+        Mutex::new(generator)
+    })
+}
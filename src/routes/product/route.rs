@@ -1,11 +1,22 @@
-use crate::errors::AppError;
-use crate::repositories::ProductRepository;
+use crate::errors::{AppError, ConstraintError, DatabaseResultExt, ProductError};
+use crate::repositories::{
+    CategoryRepo, CurrencyRepo, PricingRuleRepo, ProductPriceHistoryRepo, ProductRepository,
+    ProductVariantRepo,
+};
 use crate::routes::{
-    Claims, CreateProductRequest, CreateProductResponse, DeleteProductRequest, ListProductsRequest,
-    ListProductsResponse, NewProduct, ProductSearchParameters, UpdateProduct, UpdateProductRequest,
+    parse_pricing_rule_id, Claims, CreateProductImageResponse, CreateProductRequest,
+    CreateProductResponse, CreateProductVariantRequest, CreateProductVariantResponse,
+    CreateStockAdjustmentRequest, CreateStockAdjustmentResponse, CurrencyJson,
+    DeleteProductImageRequest, DeleteProductRequest, DeleteProductVariantRequest,
+    ListProductPriceHistoriesResponse, ListProductsRequest, ListProductsResponse,
+    ListStockAdjustmentsResponse, Money, NewProduct, NewProductImage, NewProductVariant,
+    NewStockAdjustment, PricingRuleJson, ProductSearchParameters, ReindexSearchTextsResponse,
+    RepriceProductsRequest, RepriceProductsResponse, RepricedProductJson, UpdateProduct,
+    UpdateProductRequest, UpdateProductVariant, UpdateProductVariantRequest, ValidProductImage,
 };
+use crate::storage::BlobStorage;
 use anyhow::Context;
-use axum::extract::{Path, Query};
+use axum::extract::{Multipart, Path, Query};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
@@ -14,22 +25,71 @@ use base64::Engine;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-#[tracing::instrument(name="create a new product", skip(product_repo, claims), fields(user_id=tracing::field::Empty))]
+/// The foreign key can't see the soft-deleted categories, so the category is checked here.
+async fn check_category(
+    category_repo: &Arc<dyn CategoryRepo + Sync + Send>,
+    category_id: i64,
+) -> Result<(), AppError> {
+    category_repo
+        .get(category_id)
+        .await
+        .context("Failed to get a category from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ConstraintError::ReferenceNotExist("category"))?;
+
+    Ok(())
+}
+
+/// The pricing rules are referenced by the products, so a missing one is a constraint error.
+async fn get_pricing_rule(
+    pricing_rule_repo: &Arc<dyn PricingRuleRepo + Sync + Send>,
+    pricing_rule_id: i64,
+) -> Result<PricingRuleJson, AppError> {
+    let pricing_rule = pricing_rule_repo
+        .get(pricing_rule_id)
+        .await
+        .context("Failed to get a pricing rule from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ConstraintError::ReferenceNotExist("pricing rule"))?;
+
+    Ok(pricing_rule)
+}
+
+#[tracing::instrument(name="create a new product", skip(product_repo, category_repo, pricing_rule_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
 pub async fn create_product_handler(
     claims: Claims,
     Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
     WithRejection(Json(payload), _): WithRejection<Json<CreateProductRequest>, AppError>,
 ) -> Result<Json<CreateProductResponse>, AppError> {
-    tracing::Span::current().record("user_id", &tracing::field::display(&claims.sub));
-    let new_product = NewProduct::parse(payload)
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let pricing_rule = match parse_pricing_rule_id(payload.pricing_rule_id.as_ref())
+        .map_err(AppError::BadArguments)?
+    {
+        Some(pricing_rule_id) => Some(get_pricing_rule(&pricing_rule_repo, pricing_rule_id).await?),
+        None => None,
+    };
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    let new_product = NewProduct::parse(payload, pricing_rule.as_ref(), &currencies)
         .await
         .map_err(AppError::BadArguments)?;
 
-    // TODO: need some checking?
+    if let Some(category_id) = new_product.category_id {
+        check_category(&category_repo, category_id).await?;
+    }
+
     let id = product_repo
         .create(new_product)
         .await
-        .context("Failed to insert a new product in the database")?;
+        .db_context("Failed to insert a new product in the database")?;
 
     Ok(Json(CreateProductResponse { id }))
 }
@@ -40,7 +100,7 @@ pub async fn get_product_handler(
     Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
     Path(params): Path<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, AppError> {
-    tracing::Span::current().record("user_id", &tracing::field::display(&claims.sub));
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
     let product_id = params.get("id").and_then(|e| e.parse::<i64>().ok());
 
     if product_id.is_none() {
@@ -67,7 +127,7 @@ pub async fn delete_product_handler(
     Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
     WithRejection(Json(payload), _): WithRejection<Json<DeleteProductRequest>, AppError>,
 ) -> Result<impl IntoResponse, AppError> {
-    tracing::Span::current().record("user_id", &tracing::field::display(&claims.sub));
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
 
     product_repo
         .delete(payload.id)
@@ -80,28 +140,170 @@ pub async fn delete_product_handler(
 pub async fn update_product_handler(
     claims: Claims,
     Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(category_repo): Extension<Arc<dyn CategoryRepo + Sync + Send>>,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
     WithRejection(Json(payload), _): WithRejection<Json<UpdateProductRequest>, AppError>,
 ) -> Result<impl IntoResponse, AppError> {
-    tracing::Span::current().record("user_id", &tracing::field::display(&claims.sub));
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let product = product_repo
+        .get(id)
+        .await
+        .context("Failed to get a product from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ProductError::ProductNotFound)?;
 
-    let update_product = UpdateProduct::parse(payload)
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    let mut update_product = UpdateProduct::parse(payload, &product, &currencies)
         .await
         .map_err(AppError::BadArguments)?;
 
+    if let Some(category_id) = update_product.category_id {
+        check_category(&category_repo, category_id).await?;
+    }
+
+    let changes_price = update_product.price.is_some() || update_product.currency.is_some();
+
+    if update_product.changes_cost() || changes_price {
+        let pricing_rule_id = match update_product.pricing_rule_id {
+            Some(pricing_rule_id) => pricing_rule_id,
+            None => parse_pricing_rule_id(product.pricing_rule_id.as_ref())
+                .map_err(anyhow::Error::msg)
+                .context("Failed to parse the pricing rule id of a product")?,
+        };
+
+        if let Some(pricing_rule_id) = pricing_rule_id {
+            let pricing_rule = get_pricing_rule(&pricing_rule_repo, pricing_rule_id).await?;
+
+            update_product
+                .apply_pricing_rule(&pricing_rule, &product, &currencies)
+                .map_err(AppError::BadArguments)?;
+        }
+    }
+
     let need_update = update_product.name.is_some()
         || update_product.currency.is_some()
-        || update_product.price.is_some();
+        || update_product.price.is_some()
+        || update_product.category_id.is_some()
+        || update_product.tags.is_some()
+        || update_product.changes_cost();
 
     if need_update {
         product_repo
-            .update(update_product)
+            .update(update_product, claims.user_id()?)
             .await
-            .context("Failed to update a product in the database")?;
+            .db_context("Failed to update a product in the database")?;
     }
 
     Ok(StatusCode::OK)
 }
 
+/// Index the names of the products saved before the search texts were added, so they can be
+/// found by searching. It only needs to run once after the upgrade.
+#[tracing::instrument(name = "Reindex the search texts of products", skip(product_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn reindex_product_search_texts_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+) -> Result<Json<ReindexSearchTextsResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let indexed = product_repo
+        .reindex_search_texts()
+        .await
+        .context("Failed to index the search texts of products in the database")?;
+
+    Ok(Json(ReindexSearchTextsResponse { indexed }))
+}
+
+/// Recompute the prices of the products with the current pricing rules, e.g. after the exchange
+/// rate is updated.
+#[tracing::instrument(name = "Reprice products", skip(product_repo, pricing_rule_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn reprice_products_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(pricing_rule_repo): Extension<Arc<dyn PricingRuleRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RepriceProductsRequest>, AppError>,
+) -> Result<Json<RepriceProductsResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let pricing_rule_id =
+        parse_pricing_rule_id(payload.pricing_rule_id.as_ref()).map_err(AppError::BadArguments)?;
+
+    let pricing_rules = match pricing_rule_id {
+        Some(pricing_rule_id) => vec![get_pricing_rule(&pricing_rule_repo, pricing_rule_id).await?],
+        None => pricing_rule_repo
+            .list()
+            .await
+            .context("Failed to get pricing rules from database")?,
+    };
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+
+    let products = product_repo
+        .list_priced_products(pricing_rule_id)
+        .await
+        .context("Failed to get the priced products from database")?;
+
+    let mut quotes = vec![];
+    let mut data = vec![];
+
+    for product in products {
+        let Some(pricing_rule) = pricing_rules
+            .iter()
+            .find(|e| Some(&e.id) == product.pricing_rule_id.as_ref())
+        else {
+            continue;
+        };
+        let Some(cost_price) = product.cost_price else {
+            continue;
+        };
+
+        let currency = CurrencyJson::find(pricing_rule.currency, &currencies)
+            .map_err(anyhow::Error::msg)
+            .context("Failed to find the currency of a pricing rule")?;
+        let quote = pricing_rule
+            .quote(cost_price, product.cost_tax_rate, currency)
+            .map_err(|e| AppError::BadArguments(format!("Product {}: {e}", product.id)))?;
+        let id = product
+            .id
+            .parse::<i64>()
+            .context("Failed to parse the id of a product")?;
+
+        quotes.push((id, pricing_rule.currency, quote));
+        data.push(RepricedProductJson {
+            id: product.id,
+            currency: pricing_rule.currency,
+            previous_price: product.price,
+            price: Money {
+                amount: quote.price,
+                currency: pricing_rule.currency,
+            },
+            cost: quote.cost,
+        });
+    }
+
+    product_repo
+        .reprice(quotes, claims.user_id()?)
+        .await
+        .context("Failed to save the recomputed prices of products")?;
+
+    Ok(Json(RepriceProductsResponse { data }))
+}
+
 #[tracing::instrument(name = "List products", skip(product_repo, claims), fields(user_id=tracing::field::Empty))]
 pub async fn list_products_handler(
     claims: Claims,
@@ -134,3 +336,317 @@ pub async fn list_products_handler(
 
     Ok(Json(response))
 }
+
+#[tracing::instrument(name = "Adjust the stock of a product", skip(product_repo, product_variant_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_stock_adjustment_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(product_variant_repo): Extension<Arc<dyn ProductVariantRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateStockAdjustmentRequest>, AppError>,
+) -> Result<Json<CreateStockAdjustmentResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let adjustment = NewStockAdjustment::parse(payload).map_err(AppError::BadArguments)?;
+
+    product_repo
+        .get(adjustment.product_id)
+        .await
+        .context("Failed to get a product from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ProductError::ProductNotFound)?;
+
+    if let Some(variant_id) = adjustment.variant_id {
+        product_variant_repo
+            .get(variant_id)
+            .await
+            .context("Failed to get a product variant from database")?
+            .filter(|e| e.deleted_at.is_none())
+            .filter(|e| e.product_id == adjustment.product_id.to_string())
+            .ok_or(ProductError::VariantNotFound)?;
+    }
+
+    let id = product_repo
+        .adjust_stock(adjustment, claims.user_id()?)
+        .await
+        .db_context("Failed to adjust the stock of a product in the database")?
+        .ok_or(ProductError::NegativeOnHand)?;
+
+    Ok(Json(CreateStockAdjustmentResponse { id }))
+}
+
+#[tracing::instrument(name = "List the stock adjustments of a product", skip(product_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_stock_adjustments_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<ListStockAdjustmentsResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let product_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no product id in the query string".to_string())
+        })?;
+
+    product_repo
+        .get(product_id)
+        .await
+        .context("Failed to get a product from database")?
+        .ok_or(ProductError::ProductNotFound)?;
+
+    let data = product_repo
+        .list_stock_adjustments(product_id)
+        .await
+        .context("Failed to get the stock adjustments of a product from database")?;
+
+    Ok(Json(ListStockAdjustmentsResponse { data }))
+}
+
+#[tracing::instrument(name = "List the price histories of a product", skip(product_repo, product_price_history_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_product_price_histories_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(product_price_history_repo): Extension<
+        Arc<dyn ProductPriceHistoryRepo + Sync + Send>,
+    >,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<ListProductPriceHistoriesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let product_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no product id in the query string".to_string())
+        })?;
+
+    product_repo
+        .get(product_id)
+        .await
+        .context("Failed to get a product from database")?
+        .ok_or(ProductError::ProductNotFound)?;
+
+    let data = product_price_history_repo
+        .list(product_id)
+        .await
+        .context("Failed to get the price histories of a product from database")?;
+
+    Ok(Json(ListProductPriceHistoriesResponse { data }))
+}
+
+#[tracing::instrument(name = "Create a new product variant", skip(product_repo, product_variant_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_product_variant_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(product_variant_repo): Extension<Arc<dyn ProductVariantRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateProductVariantRequest>, AppError>,
+) -> Result<Json<CreateProductVariantResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let product = product_repo
+        .get(payload.product_id)
+        .await
+        .context("Failed to get a product from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ProductError::ProductNotFound)?;
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+    let currency = CurrencyJson::find(product.currency, &currencies)
+        .map_err(anyhow::Error::msg)
+        .context("Failed to find the currency of a product")?;
+
+    let new_variant =
+        NewProductVariant::parse(payload, currency).map_err(AppError::BadArguments)?;
+
+    let id = product_variant_repo
+        .create(new_variant)
+        .await
+        .db_context("Failed to insert a new product variant in the database")?;
+
+    Ok(Json(CreateProductVariantResponse { id }))
+}
+
+#[tracing::instrument(name = "Update a product variant", skip(product_variant_repo, currency_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn update_product_variant_handler(
+    claims: Claims,
+    Extension(product_variant_repo): Extension<Arc<dyn ProductVariantRepo + Sync + Send>>,
+    Extension(currency_repo): Extension<Arc<dyn CurrencyRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateProductVariantRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let variant = product_variant_repo
+        .get(id)
+        .await
+        .context("Failed to get a product variant from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ProductError::VariantNotFound)?;
+
+    let currencies = currency_repo
+        .list()
+        .await
+        .context("Failed to get currencies from database")?;
+    let currency = CurrencyJson::find(variant.currency, &currencies)
+        .map_err(anyhow::Error::msg)
+        .context("Failed to find the currency of a product variant")?;
+
+    let update_variant =
+        UpdateProductVariant::parse(payload, currency).map_err(AppError::BadArguments)?;
+
+    let need_update = update_variant.sku.is_some()
+        || update_variant.options.is_some()
+        || update_variant.price.is_some();
+
+    if need_update {
+        product_variant_repo
+            .update(update_variant)
+            .await
+            .db_context("Failed to update a product variant in the database")?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Delete a product variant", skip(product_variant_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn delete_product_variant_handler(
+    claims: Claims,
+    Extension(product_variant_repo): Extension<Arc<dyn ProductVariantRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteProductVariantRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    product_variant_repo
+        .delete(id)
+        .await
+        .context("Failed to delete a product variant in the database")?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Upload an image of a product", skip(product_repo, blob_storage, claims, multipart), fields(user_id=tracing::field::Empty))]
+pub async fn upload_product_image_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(blob_storage): Extension<Arc<dyn BlobStorage + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+    mut multipart: Multipart,
+) -> Result<Json<CreateProductImageResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let product_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no product id in the query string".to_string())
+        })?;
+
+    product_repo
+        .get(product_id)
+        .await
+        .context("Failed to get a product from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ProductError::ProductNotFound)?;
+
+    let mut content = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadArguments(e.body_text()))?
+    {
+        if field.name() == Some("image") {
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|e| AppError::BadArguments(e.body_text()))?;
+            content = Some(bytes.to_vec());
+            break;
+        }
+    }
+
+    let content = content
+        .ok_or_else(|| AppError::BadArguments("There is no image in the form".to_string()))?;
+
+    let image = tokio::task::spawn_blocking(move || ValidProductImage::parse(content))
+        .await
+        .context("Failed to process an image")?
+        .map_err(AppError::BadArguments)?;
+
+    let new_image = NewProductImage::new(product_id, &image, |key| blob_storage.url(key));
+    let keys = [
+        new_image.storage_key.clone(),
+        new_image.thumbnail_key.clone(),
+    ];
+    let user_id = claims.user_id()?;
+
+    let result = async {
+        blob_storage
+            .put(&new_image.storage_key, image.content_type, image.content)
+            .await?;
+        blob_storage
+            .put(&new_image.thumbnail_key, "image/jpeg", image.thumbnail)
+            .await?;
+
+        product_repo
+            .add_image(new_image, user_id)
+            .await
+            .db_context("Failed to insert a new product image in the database")
+    }
+    .await;
+
+    // The files are written before the row, so they are removed again when anything fails.
+    if result.is_err() {
+        for key in &keys {
+            if let Err(e) = blob_storage.delete(key).await {
+                tracing::warn!("Failed to delete an orphaned image file {key}: {e:?}");
+            }
+        }
+    }
+
+    Ok(Json(CreateProductImageResponse { id: result? }))
+}
+
+#[tracing::instrument(name = "Delete an image of a product", skip(product_repo, blob_storage, claims), fields(user_id=tracing::field::Empty))]
+pub async fn delete_product_image_handler(
+    claims: Claims,
+    Extension(product_repo): Extension<Arc<dyn ProductRepository + Sync + Send>>,
+    Extension(blob_storage): Extension<Arc<dyn BlobStorage + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteProductImageRequest>, AppError>,
+) -> Result<impl IntoResponse, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let id = payload
+        .id
+        .parse::<i64>()
+        .map_err(|_| AppError::BadArguments("Can't parse id to i64.".to_string()))?;
+
+    let keys = product_repo
+        .delete_image(id)
+        .await
+        .context("Failed to delete a product image in the database")?;
+
+    // The image is already deleted in the database, so the files left behind are only logged.
+    if let Some((storage_key, thumbnail_key)) = keys {
+        let image = blob_storage.delete(&storage_key).await;
+        let thumbnail = blob_storage.delete(&thumbnail_key).await;
+        if let Err(e) = image.and(thumbnail) {
+            tracing::warn!(
+                "Failed to delete the files {storage_key} and {thumbnail_key} of a deleted image: {e:?}"
+            );
+        }
+    }
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{Error, Row};
+
+use crate::routes::{shipment_id_generator, OrderItemJson};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ShipmentJson {
+    pub id: String,
+    pub carrier: String,
+    pub tracking_number: Option<String>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub expected_arrival_at: Option<DateTime<Utc>>,
+    pub arrived_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+    pub order_items: Vec<OrderItemJson>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Ship the order items in one box, they are moved to `in_transit` when they are purchased.
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateShipmentRequest {
+    pub carrier: String,
+    pub tracking_number: Option<String>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub expected_arrival_at: Option<DateTime<Utc>>,
+    pub order_item_ids: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateShipmentResponse {
+    pub id: i64,
+}
+
+/// Mark the shipment as arrived, `arrived_at` defaults to now.
+#[derive(serde::Deserialize, Debug)]
+pub struct ArriveShipmentRequest {
+    pub id: String,
+    pub arrived_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ListShipmentsRequest {
+    pub keyword: Option<String>,
+    pub page: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ListShipmentsResponse {
+    pub data: Vec<ShipmentJson>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default)]
+pub struct ShipmentSearchParameters {
+    pub carrier: Option<String>,
+    pub tracking_number: Option<String>,
+    /// Find the shipment which contains the order item.
+    pub order_item_id: Option<i64>,
+    pub arrived: Option<bool>,
+    pub deleted: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct NewShipment {
+    pub id: i64,
+    pub carrier: String,
+    pub tracking_number: Option<String>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub expected_arrival_at: Option<DateTime<Utc>>,
+    pub order_item_ids: Vec<i64>,
+}
+
+#[derive(Debug)]
+pub struct ArriveShipment {
+    pub id: i64,
+    pub arrived_at: DateTime<Utc>,
+}
+
+impl NewShipment {
+    pub async fn parse(req: CreateShipmentRequest) -> Result<Self, String> {
+        let carrier = req.carrier.trim().to_string();
+        if carrier.is_empty() {
+            return Err("The carrier can't be empty.".to_string());
+        }
+
+        let tracking_number = req
+            .tracking_number
+            .map(|e| e.trim().to_string())
+            .filter(|e| !e.is_empty());
+
+        if let (Some(shipped_at), Some(expected_arrival_at)) =
+            (req.shipped_at, req.expected_arrival_at)
+        {
+            if expected_arrival_at < shipped_at {
+                return Err(
+                    "The expected arrival can't be earlier than the shipped date.".to_string(),
+                );
+            }
+        }
+
+        let mut order_item_ids = req
+            .order_item_ids
+            .iter()
+            .map(|e| e.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Can't parse order item id to i64.".to_string())?;
+        order_item_ids.sort();
+        order_item_ids.dedup();
+
+        if order_item_ids.is_empty() {
+            return Err("The shipment should contain at least one order item.".to_string());
+        }
+
+        let id = async {
+            let generator = shipment_id_generator();
+            let mut generator = generator.lock().unwrap();
+            generator.real_time_generate()
+        }
+        .await;
+
+        Ok(Self {
+            id,
+            carrier,
+            tracking_number,
+            shipped_at: req.shipped_at,
+            expected_arrival_at: req.expected_arrival_at,
+            order_item_ids,
+        })
+    }
+}
+
+impl ArriveShipment {
+    pub fn parse(req: ArriveShipmentRequest) -> Result<Self, String> {
+        let id = req
+            .id
+            .parse::<i64>()
+            .map_err(|_| "Can't parse id to i64.".to_string())?;
+
+        Ok(Self {
+            id,
+            arrived_at: req.arrived_at.unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+impl<'r> ::sqlx::FromRow<'r, PgRow> for ShipmentJson {
+    /// The order items are left empty, they are filled by the repository.
+    fn from_row(row: &'r PgRow) -> Result<Self, Error> {
+        let id: i64 = row.try_get(0)?;
+        let carrier: String = row.try_get(1)?;
+        let tracking_number: Option<String> = row.try_get(2)?;
+        let shipped_at: Option<DateTime<Utc>> = row.try_get(3)?;
+        let expected_arrival_at: Option<DateTime<Utc>> = row.try_get(4)?;
+        let arrived_at: Option<DateTime<Utc>> = row.try_get(5)?;
+        let created_by: uuid::Uuid = row.try_get(6)?;
+        let created_at: DateTime<Utc> = row.try_get(7)?;
+        let updated_at: Option<DateTime<Utc>> = row.try_get(8)?;
+        let deleted_at: Option<DateTime<Utc>> = row.try_get(9)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            carrier,
+            tracking_number,
+            shipped_at,
+            expected_arrival_at,
+            arrived_at,
+            created_by: created_by.to_string(),
+            order_items: vec![],
+            created_at,
+            updated_at,
+            deleted_at,
+        })
+    }
+}
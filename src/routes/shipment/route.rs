@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Path, Query};
+use axum::{Extension, Json};
+use axum_extra::extract::WithRejection;
+use base64::Engine;
+
+use crate::errors::{AppError, DatabaseResultExt, ShipmentError};
+use crate::repositories::{OrderItemRepo, ShipmentRepo};
+use crate::routes::{
+    ArriveShipment, ArriveShipmentRequest, Claims, CreateShipmentRequest, CreateShipmentResponse,
+    ListShipmentsRequest, ListShipmentsResponse, NewShipment, OrderItemStatus, ShipmentJson,
+    ShipmentSearchParameters, UpdateOrderItemStatusesResponse,
+};
+
+#[tracing::instrument(name = "Create a new shipment", skip(shipment_repo, order_item_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn create_shipment_handler(
+    claims: Claims,
+    Extension(shipment_repo): Extension<Arc<dyn ShipmentRepo + Sync + Send>>,
+    Extension(order_item_repo): Extension<Arc<dyn OrderItemRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateShipmentRequest>, AppError>,
+) -> Result<Json<CreateShipmentResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let new_shipment = NewShipment::parse(payload)
+        .await
+        .map_err(AppError::BadArguments)?;
+
+    for order_item_id in &new_shipment.order_item_ids {
+        let order_item = order_item_repo
+            .get(*order_item_id)
+            .await
+            .context("Failed to get an order item from database")?;
+
+        let is_shippable = order_item.is_some_and(|e| {
+            e.deleted_at.is_none()
+                && e.shipment_id.is_none()
+                && matches!(
+                    e.status,
+                    OrderItemStatus::PurchasedInJapan | OrderItemStatus::InTransit
+                )
+        });
+
+        if !is_shippable {
+            return Err(ShipmentError::OrderItemNotShippable(*order_item_id))?;
+        }
+    }
+
+    let id = shipment_repo
+        .create(new_shipment, claims.user_id()?)
+        .await
+        .db_context("Failed to insert a new shipment in the database")??;
+
+    Ok(Json(CreateShipmentResponse { id }))
+}
+
+#[tracing::instrument(name = "Get a shipment", skip(shipment_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn get_shipment_handler(
+    claims: Claims,
+    Extension(shipment_repo): Extension<Arc<dyn ShipmentRepo + Sync + Send>>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<ShipmentJson>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let shipment_id = params
+        .get("id")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or_else(|| {
+            AppError::BadArguments("There is no shipment id in the query string".to_string())
+        })?;
+
+    let shipment = shipment_repo
+        .get(shipment_id)
+        .await
+        .context("Failed to get a shipment from database")?
+        .ok_or(ShipmentError::ShipmentNotFound)?;
+
+    Ok(Json(shipment))
+}
+
+#[tracing::instrument(name = "Mark a shipment as arrived", skip(shipment_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn arrive_shipment_handler(
+    claims: Claims,
+    Extension(shipment_repo): Extension<Arc<dyn ShipmentRepo + Sync + Send>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ArriveShipmentRequest>, AppError>,
+) -> Result<Json<UpdateOrderItemStatusesResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+    let arrive_shipment = ArriveShipment::parse(payload).map_err(AppError::BadArguments)?;
+
+    let shipment = shipment_repo
+        .get(arrive_shipment.id)
+        .await
+        .context("Failed to get a shipment from database")?
+        .filter(|e| e.deleted_at.is_none())
+        .ok_or(ShipmentError::ShipmentNotFound)?;
+
+    if shipment.arrived_at.is_some() {
+        return Err(ShipmentError::ShipmentIsArrived)?;
+    }
+
+    let data = shipment_repo
+        .arrive(arrive_shipment, claims.user_id()?)
+        .await
+        .context("Failed to mark a shipment as arrived in the database")??;
+
+    Ok(Json(UpdateOrderItemStatusesResponse { data }))
+}
+
+#[tracing::instrument(name = "List shipments", skip(shipment_repo, claims), fields(user_id=tracing::field::Empty))]
+pub async fn list_shipments_handler(
+    claims: Claims,
+    Extension(shipment_repo): Extension<Arc<dyn ShipmentRepo + Sync + Send>>,
+    Query(payload): Query<ListShipmentsRequest>,
+) -> Result<Json<ListShipmentsResponse>, AppError> {
+    tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+
+    let search_parameter = if let Some(keyword) = &payload.keyword {
+        base64::engine::general_purpose::STANDARD
+            .decode(keyword)
+            .as_ref()
+            .map(|e| serde_json::from_slice::<ShipmentSearchParameters>(e))
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+            .map_err(|_| AppError::DecodeSearchParameterFailed)?
+    } else {
+        ShipmentSearchParameters::default()
+    };
+
+    let page = payload.page.unwrap_or(0);
+    let page_size = payload.page_size.unwrap_or(20);
+
+    let data = shipment_repo
+        .list(search_parameter, page, page_size)
+        .await
+        .context("Failed to get shipments from database")?;
+
+    Ok(Json(ListShipmentsResponse { data }))
+}
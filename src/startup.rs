@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use tokio::net::TcpListener;
 
+use axum::extract::DefaultBodyLimit;
 use axum::http::Request;
 use axum::routing::{delete, get, post, put};
 use axum::{Extension, Router};
@@ -10,22 +11,47 @@ use sqlx::PgPool;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tower_http::request_id::{MakeRequestId, RequestId};
+use tower_http::services::ServeDir;
 use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
 use tower_http::ServiceBuilderExt;
 use tracing::Level;
 use uuid::Uuid;
 
-use crate::configuration::{DatabaseSettings, Settings};
+use crate::configuration::{DatabaseSettings, Settings, StorageSettings};
 use crate::repositories::{
-    CustomerRepo, OrderItemRepo, PostgresCustomerRepoImpl, PostgresOrderItemRepo,
-    PostgresProductRepoImpl, PostgresUserRepoImpl, ProductRepository, UserRepo,
+    CategoryRepo, CurrencyRepo, CustomerContactMethodRepo, CustomerRepo, ExchangeRateRepo,
+    OrderItemRepo, OrderItemStatusHistoryRepo, OrderRepo, PaymentRepo, PostgresCategoryRepo,
+    PostgresCurrencyRepo, PostgresCustomerContactMethodRepo, PostgresCustomerRepoImpl,
+    PostgresExchangeRateRepo, PostgresOrderItemRepo, PostgresOrderItemStatusHistoryRepo,
+    PostgresOrderRepo, PostgresPaymentRepo, PostgresPricingRuleRepo,
+    PostgresProductPriceHistoryRepo, PostgresProductRepoImpl, PostgresProductVariantRepo,
+    PostgresShipmentRepo, PostgresUserRepoImpl, PricingRuleRepo, ProductPriceHistoryRepo,
+    ProductRepository, ProductVariantRepo, ShipmentRepo, UserRepo,
 };
 use crate::routes::{
-    change_password, create_customer_handler, create_product_handler, delete_customer_handler,
-    delete_product_handler, get_customer_handler, get_product_handler, health_check,
-    list_customers_handler, list_products_handler, login, logout, update_customer_handler,
-    update_product_handler,
+    arrive_shipment_handler, change_password, create_category_handler,
+    create_contact_method_handler, create_currency_handler, create_customer_handler,
+    create_exchange_rate_handler, create_order_handler, create_order_item_handler,
+    create_payment_handler, create_pricing_rule_handler, create_product_handler,
+    create_product_variant_handler, create_shipment_handler, create_stock_adjustment_handler,
+    delete_category_handler, delete_contact_method_handler, delete_customer_handler,
+    delete_order_item_handler, delete_product_handler, delete_product_image_handler,
+    delete_product_variant_handler, get_category_handler, get_customer_balances_handler,
+    get_customer_handler, get_order_handler, get_order_item_handler, get_pricing_rule_handler,
+    get_product_handler, get_shipment_handler, health_check, import_exchange_rates_handler,
+    list_categories_handler, list_contact_methods_handler, list_currencies_handler,
+    list_customer_duplicates_handler, list_customer_order_items_handler, list_customers_handler,
+    list_exchange_rates_handler, list_order_item_status_histories_handler,
+    list_order_items_handler, list_orders_handler, list_payments_handler,
+    list_pricing_rules_handler, list_product_price_histories_handler, list_products_handler,
+    list_shipments_handler, list_stock_adjustments_handler, login, logout, merge_customers_handler,
+    normalize_customer_phones_handler, reindex_product_search_texts_handler,
+    reprice_products_handler, update_category_handler, update_contact_method_handler,
+    update_customer_handler, update_order_item_handler, update_order_item_statuses_handler,
+    update_pricing_rule_handler, update_product_handler, update_product_variant_handler,
+    upload_product_image_handler, void_payment_handler, ValidPhone,
 };
+use crate::storage::get_blob_storage;
 use crate::utils::PostgresSession;
 
 #[derive(Clone)]
@@ -46,16 +72,32 @@ impl MakeRequestId for MakeRequestUuid {
     }
 }
 
+/// The maximum size of an uploaded image in bytes.
+const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024;
+
 pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io::Error> {
     let client = redis::Client::open(config.redis_uri.expose_secret().as_str())
         .expect("Failed to connect the redis");
 
+    let blob_storage = get_blob_storage(&config.storage);
+    let reporting = config.reporting.clone();
+    let phone = config.phone.clone();
+    ValidPhone::check_country_code(phone.default_country_code)
+        .expect("Invalid default country code of the phone settings");
+
     let state = AppState {
         db_pool: get_database_connection(&config.database).await,
         redis_client: Arc::new(client),
         jwt_secret_key: Secret::new(config.jwt.secret_key),
     };
 
+    // An unknown time zone would only fail the queries of the summaries.
+    sqlx::query("select now() at time zone $1")
+        .bind(&reporting.time_zone)
+        .execute(&state.db_pool)
+        .await
+        .unwrap_or_else(|e| panic!("{} is not a known time zone: {e}", reporting.time_zone));
+
     let customer_repo = PostgresSession::new(state.db_pool.clone())
         .await
         .map(PostgresCustomerRepoImpl::new)
@@ -63,6 +105,13 @@ pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io:
         .expect("Failed to create a customer repository.")
         as Arc<dyn CustomerRepo + Send + Sync>;
 
+    let customer_contact_method_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresCustomerContactMethodRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a customer contact method repository")
+        as Arc<dyn CustomerContactMethodRepo + Send + Sync>;
+
     let user_repo = PostgresSession::new(state.db_pool.clone())
         .await
         .map(PostgresUserRepoImpl::new)
@@ -77,6 +126,48 @@ pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io:
         .expect("Failed to create product repository")
         as Arc<dyn ProductRepository + Send + Sync>;
 
+    let category_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresCategoryRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a category repository")
+        as Arc<dyn CategoryRepo + Send + Sync>;
+
+    let currency_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresCurrencyRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a currency repository")
+        as Arc<dyn CurrencyRepo + Send + Sync>;
+
+    let exchange_rate_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresExchangeRateRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create an exchange rate repository")
+        as Arc<dyn ExchangeRateRepo + Send + Sync>;
+
+    let pricing_rule_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresPricingRuleRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a pricing rule repository")
+        as Arc<dyn PricingRuleRepo + Send + Sync>;
+
+    let product_variant_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresProductVariantRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a product variant repository")
+        as Arc<dyn ProductVariantRepo + Send + Sync>;
+
+    let product_price_history_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresProductPriceHistoryRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a product price history repository")
+        as Arc<dyn ProductPriceHistoryRepo + Send + Sync>;
+
     let order_item_repo = PostgresSession::new(state.db_pool.clone())
         .await
         .map(PostgresOrderItemRepo::new)
@@ -84,8 +175,69 @@ pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io:
         .expect("Failed to create a order item repository")
         as Arc<dyn OrderItemRepo + Send + Sync>;
 
+    let order_item_status_history_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresOrderItemStatusHistoryRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a order item status history repository")
+        as Arc<dyn OrderItemStatusHistoryRepo + Send + Sync>;
+
+    let order_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresOrderRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a order repository")
+        as Arc<dyn OrderRepo + Send + Sync>;
+
+    let shipment_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresShipmentRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a shipment repository")
+        as Arc<dyn ShipmentRepo + Send + Sync>;
+
+    let payment_repo = PostgresSession::new(state.db_pool.clone())
+        .await
+        .map(PostgresPaymentRepo::new)
+        .map(Arc::new)
+        .expect("Failed to create a payment repository")
+        as Arc<dyn PaymentRepo + Send + Sync>;
+
     let customer_routes = Router::new()
+        .route(
+            "/customers/duplicates",
+            get(list_customer_duplicates_handler),
+        )
+        .route("/customers/merge", post(merge_customers_handler))
+        .route(
+            "/customers/normalize_phones",
+            post(normalize_customer_phones_handler),
+        )
+        .route(
+            "/customers/contact_methods",
+            post(create_contact_method_handler),
+        )
+        .route(
+            "/customers/contact_methods",
+            put(update_contact_method_handler),
+        )
+        .route(
+            "/customers/contact_methods",
+            delete(delete_contact_method_handler),
+        )
         .route("/customers/:id", get(get_customer_handler))
+        .route(
+            "/customers/:id/contact_methods",
+            get(list_contact_methods_handler),
+        )
+        .route(
+            "/customers/:id/order_items",
+            get(list_customer_order_items_handler),
+        )
+        .route(
+            "/customers/:id/balances",
+            get(get_customer_balances_handler),
+        )
         .route("/customers", get(list_customers_handler))
         .route("/customers", post(create_customer_handler))
         .route("/customers", put(update_customer_handler))
@@ -93,16 +245,105 @@ pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io:
 
     let product_routes = Router::new()
         .route("/products/:id", get(get_product_handler))
+        .route(
+            "/products/:id/price_histories",
+            get(list_product_price_histories_handler),
+        )
+        .route(
+            "/products/:id/stock_adjustments",
+            get(list_stock_adjustments_handler),
+        )
+        .route(
+            "/products/stock_adjustments",
+            post(create_stock_adjustment_handler),
+        )
+        .route(
+            "/products/:id/images",
+            post(upload_product_image_handler).layer(DefaultBodyLimit::max(MAX_IMAGE_SIZE)),
+        )
+        .route("/products/images", delete(delete_product_image_handler))
+        .route("/products/reprice", post(reprice_products_handler))
+        .route(
+            "/products/reindex_search_texts",
+            post(reindex_product_search_texts_handler),
+        )
+        .route("/products/variants", post(create_product_variant_handler))
+        .route("/products/variants", put(update_product_variant_handler))
+        .route("/products/variants", delete(delete_product_variant_handler))
         .route("/products", get(list_products_handler))
         .route("/products", post(create_product_handler))
         .route("/products", put(update_product_handler))
         .route("/products", delete(delete_product_handler));
 
+    let category_routes = Router::new()
+        .route("/categories/:id", get(get_category_handler))
+        .route("/categories", get(list_categories_handler))
+        .route("/categories", post(create_category_handler))
+        .route("/categories", put(update_category_handler))
+        .route("/categories", delete(delete_category_handler));
+
+    let currency_routes = Router::new()
+        .route("/currencies", get(list_currencies_handler))
+        .route("/currencies", post(create_currency_handler));
+
+    let exchange_rate_routes = Router::new()
+        .route(
+            "/exchange_rates/import",
+            post(import_exchange_rates_handler),
+        )
+        .route("/exchange_rates", get(list_exchange_rates_handler))
+        .route("/exchange_rates", post(create_exchange_rate_handler));
+
+    let pricing_rule_routes = Router::new()
+        .route("/pricing_rules/:id", get(get_pricing_rule_handler))
+        .route("/pricing_rules", get(list_pricing_rules_handler))
+        .route("/pricing_rules", post(create_pricing_rule_handler))
+        .route("/pricing_rules", put(update_pricing_rule_handler));
+
+    let order_item_routes = Router::new()
+        .route("/order_items/:id", get(get_order_item_handler))
+        .route(
+            "/order_items/:id/status_histories",
+            get(list_order_item_status_histories_handler),
+        )
+        .route(
+            "/order_items/status",
+            put(update_order_item_statuses_handler),
+        )
+        .route("/order_items", get(list_order_items_handler))
+        .route("/order_items", post(create_order_item_handler))
+        .route("/order_items", put(update_order_item_handler))
+        .route("/order_items", delete(delete_order_item_handler));
+
+    let order_routes = Router::new()
+        .route("/orders/:id", get(get_order_handler))
+        .route("/orders", get(list_orders_handler))
+        .route("/orders", post(create_order_handler));
+
+    let shipment_routes = Router::new()
+        .route("/shipments/:id", get(get_shipment_handler))
+        .route("/shipments/arrival", put(arrive_shipment_handler))
+        .route("/shipments", get(list_shipments_handler))
+        .route("/shipments", post(create_shipment_handler));
+
+    let payment_routes = Router::new()
+        .route("/payments/void", put(void_payment_handler))
+        .route("/payments", get(list_payments_handler))
+        .route("/payments", post(create_payment_handler));
+
     let change_password_route = Router::new().route("/change_password", post(change_password));
 
     let admin_routes = Router::new()
         .merge(customer_routes)
         .merge(product_routes)
+        .merge(category_routes)
+        .merge(currency_routes)
+        .merge(exchange_rate_routes)
+        .merge(pricing_rule_routes)
+        .merge(order_item_routes)
+        .merge(order_routes)
+        .merge(shipment_routes)
+        .merge(payment_routes)
         .merge(change_password_route);
 
     let authorization_routes = Router::new()
@@ -110,10 +351,17 @@ pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io:
         .route("/logout", post(logout));
 
     // build our application with a route
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/api/v1/health_check", get(health_check))
         .nest("/api/:version/admin", admin_routes)
-        .nest("/api/:version", authorization_routes)
+        .nest("/api/:version", authorization_routes);
+
+    // The files of the local storage are served by the application itself.
+    if let StorageSettings::Local { root, .. } = &config.storage {
+        app = app.nest_service("/uploads", ServeDir::new(root));
+    }
+
+    let app = app
         .layer(
             ServiceBuilder::new()
                 .set_x_request_id(MakeRequestUuid)
@@ -128,9 +376,23 @@ pub async fn run(config: Settings, listener: TcpListener) -> Result<(), std::io:
                 ),
         )
         .layer(Extension(customer_repo))
+        .layer(Extension(customer_contact_method_repo))
         .layer(Extension(user_repo))
         .layer(Extension(product_repo))
+        .layer(Extension(product_variant_repo))
+        .layer(Extension(product_price_history_repo))
+        .layer(Extension(category_repo))
+        .layer(Extension(currency_repo))
+        .layer(Extension(exchange_rate_repo))
+        .layer(Extension(reporting))
+        .layer(Extension(phone))
+        .layer(Extension(pricing_rule_repo))
+        .layer(Extension(blob_storage))
         .layer(Extension(order_item_repo))
+        .layer(Extension(order_item_status_history_repo))
+        .layer(Extension(order_repo))
+        .layer(Extension(shipment_repo))
+        .layer(Extension(payment_repo))
         .layer(CorsLayer::permissive())
         .with_state(state);
 
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::path::Path;
+use object_store::{Attribute, Attributes, ObjectStore, PutOptions, PutPayload};
+use secrecy::ExposeSecret;
+
+use crate::configuration::StorageSettings;
+
+/// A place to keep the uploaded files, the keys are relative paths like `products/1/2.jpg`.
+#[async_trait::async_trait]
+pub trait BlobStorage {
+    async fn put(&self, key: &str, content_type: &str, content: Vec<u8>) -> anyhow::Result<()>;
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// The public URL of the file.
+    fn url(&self, key: &str) -> String;
+}
+
+pub fn get_blob_storage(settings: &StorageSettings) -> Arc<dyn BlobStorage + Send + Sync> {
+    match settings {
+        StorageSettings::Local { root, public_url } => {
+            Arc::new(LocalBlobStorage::new(root.into(), public_url.clone()))
+        }
+        StorageSettings::S3 {
+            endpoint,
+            region,
+            bucket,
+            access_key_id,
+            secret_access_key,
+            public_url,
+        } => {
+            let store = AmazonS3Builder::new()
+                .with_endpoint(endpoint)
+                .with_region(region)
+                .with_bucket_name(bucket)
+                .with_access_key_id(access_key_id)
+                .with_secret_access_key(secret_access_key.expose_secret())
+                .with_allow_http(endpoint.starts_with("http://"))
+                .build()
+                .expect("Failed to create the S3 storage");
+
+            Arc::new(S3BlobStorage::new(store, public_url.clone()))
+        }
+    }
+}
+
+fn join_url(public_url: &str, key: &str) -> String {
+    format!("{}/{}", public_url.trim_end_matches('/'), key)
+}
+
+/// Keep the files in a directory of the local filesystem, which is served by the application.
+pub struct LocalBlobStorage {
+    root: PathBuf,
+    public_url: String,
+}
+
+impl LocalBlobStorage {
+    pub fn new(root: PathBuf, public_url: String) -> Self {
+        Self { root, public_url }
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobStorage for LocalBlobStorage {
+    async fn put(&self, key: &str, _content_type: &str, content: Vec<u8>) -> anyhow::Result<()> {
+        let path = self.root.join(key);
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create the directory of a file")?;
+        }
+
+        tokio::fs::write(&path, content)
+            .await
+            .context("Failed to write a file")
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to delete a file")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        join_url(&self.public_url, key)
+    }
+}
+
+/// Keep the files in a bucket of an S3-compatible service.
+pub struct S3BlobStorage {
+    store: AmazonS3,
+    public_url: String,
+}
+
+impl S3BlobStorage {
+    pub fn new(store: AmazonS3, public_url: String) -> Self {
+        Self { store, public_url }
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobStorage for S3BlobStorage {
+    async fn put(&self, key: &str, content_type: &str, content: Vec<u8>) -> anyhow::Result<()> {
+        let options = PutOptions {
+            attributes: Attributes::from_iter([(Attribute::ContentType, content_type.to_owned())]),
+            ..Default::default()
+        };
+
+        self.store
+            .put_opts(&Path::from(key), PutPayload::from(content), options)
+            .await
+            .context("Failed to upload a file to S3")?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.store
+            .delete(&Path::from(key))
+            .await
+            .context("Failed to delete a file from S3")
+    }
+
+    fn url(&self, key: &str) -> String {
+        join_url(&self.public_url, key)
+    }
+}
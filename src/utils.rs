@@ -1,27 +1,23 @@
-use std::sync::Arc;
-
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use once_cell::sync::OnceCell;
-use regex::Regex;
 use sqlx::pool::PoolConnection;
 use sqlx::{PgPool, Postgres};
-use tokio::sync::{Mutex, MutexGuard};
+use unicode_normalization::UnicodeNormalization;
 
+/// The repositories take a connection of the pool for each call, so they don't hold the
+/// connections while they are idle and the concurrent calls don't wait for each other.
 #[derive(Debug, Clone)]
 pub struct PostgresSession {
-    session: Arc<Mutex<PoolConnection<Postgres>>>,
+    pool: PgPool,
 }
 
 impl PostgresSession {
     pub async fn new(pool: PgPool) -> Result<Self, sqlx::Error> {
-        let session = pool.acquire().await?;
-        Ok(Self {
-            session: Arc::new(Mutex::new(session)),
-        })
+        Ok(Self { pool })
     }
 
-    pub async fn get_session(&self) -> MutexGuard<PoolConnection<Postgres>> {
-        self.session.lock().await
+    pub async fn get_session(&self) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        self.pool.acquire().await
     }
 }
 
@@ -41,9 +37,66 @@ impl JwtKey {
 
 pub static JWT_SECRET_KEY_INSTANCE: OnceCell<JwtKey> = OnceCell::new();
 
-pub fn get_phone_number_regex() -> &'static Regex {
-    static INSTANCE: OnceCell<Regex> = OnceCell::new();
-    INSTANCE.get_or_init(|| {
-        Regex::new(r#"^([\\+]?[(]?[0-9]{3}[)])?[-\s\\.]?[0-9]{3}[-\s\\.]?[0-9]{4,6}$"#).unwrap()
-    })
+/// Escape the wildcards of a `LIKE` pattern, so that they are matched literally.
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('%', r"\%")
+        .replace('_', r"\_")
+}
+
+/// A `LIKE` pattern which matches the texts containing the value.
+pub fn contains_pattern(value: &str) -> String {
+    format!("%{}%", escape_like(value))
+}
+
+/// The forms of a text which are compared when searching, so that "ポケモン", "ﾎﾟｹﾓﾝ" and
+/// "Pokemon" match each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchText {
+    /// The full-width and half-width characters are unified and the letters are lowercased.
+    pub normalized: String,
+    /// The normalized text transliterated into ASCII, e.g. "ポケモン" becomes "pokemon".
+    pub romanized: String,
+}
+
+impl SearchText {
+    pub fn new(value: &str) -> Self {
+        let normalized = value
+            .nfkc()
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        let romanized = deunicode::deunicode(&normalized)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        Self {
+            normalized,
+            romanized,
+        }
+    }
+
+    /// The text stored next to the searched column, which contains both forms.
+    pub fn indexed(&self) -> String {
+        if self.normalized == self.romanized {
+            self.normalized.clone()
+        } else {
+            format!("{} {}", self.normalized, self.romanized)
+        }
+    }
+}
+
+/// Deserialize a field which distinguishes a missing value from an explicit `null`.
+/// Use it with `#[serde(default)]`, so that a missing field is `None` and `null` is `Some(None)`.
+pub fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    <Option<T> as serde::Deserialize>::deserialize(deserializer).map(Some)
 }
@@ -0,0 +1,274 @@
+use crate::helpers::{spawn_app, AuthTestApp};
+use base64::Engine;
+use japonfou::routes::{CreateCategoryResponse, CreateProductResponse, ListProductsResponse};
+
+async fn create_a_category(app: &AuthTestApp, name: &str, parent_id: Option<i64>) -> i64 {
+    let request = serde_json::json!({
+        "name": name,
+        "parent_id": parent_id.map(|e| e.to_string()),
+    });
+
+    let response = app.post("/api/v1/admin/categories", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<CreateCategoryResponse>()
+        .await
+        .expect("Failed to parse the category")
+        .id
+}
+
+async fn create_a_product_with(app: &AuthTestApp, category_id: i64, tags: &[&str]) -> i64 {
+    let request = serde_json::json!({
+        "name": "Pocky",
+        "currency": 344,
+        "price": 20.0,
+        "category_id": category_id.to_string(),
+        "tags": tags,
+    });
+
+    let response = app.post("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<CreateProductResponse>()
+        .await
+        .expect("Failed to parse the product")
+        .id
+}
+
+async fn list_product_ids(app: &AuthTestApp, keyword: serde_json::Value) -> Vec<String> {
+    let keyword = base64::engine::general_purpose::STANDARD.encode(keyword.to_string());
+
+    let mut ids = app
+        .get(&format!("/api/v1/admin/products?keyword={keyword}"))
+        .await
+        .json::<ListProductsResponse>()
+        .await
+        .expect("Failed to parse the products")
+        .data
+        .into_iter()
+        .map(|e| e.id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn create_category_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let food_id = create_a_category(&app, "Food", None).await;
+    let snack_id = create_a_category(&app, "Snack", Some(food_id)).await;
+    let duplicate = app
+        .post(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "name": "Snack", "parent_id": food_id.to_string() }),
+        )
+        .await;
+    let missing_parent = app
+        .post(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "name": "Candy", "parent_id": "1" }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(duplicate.status().as_u16(), 409);
+    assert_eq!(missing_parent.status().as_u16(), 422);
+
+    let snack = app
+        .get(&format!("/api/v1/admin/categories/{snack_id}"))
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse the category");
+    assert_eq!(snack["name"], "Snack");
+    assert_eq!(snack["parent_id"], food_id.to_string());
+}
+
+#[tokio::test]
+async fn category_can_not_be_moved_under_its_descendant() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let food_id = create_a_category(&app, "Food", None).await;
+    let snack_id = create_a_category(&app, "Snack", Some(food_id)).await;
+    let candy_id = create_a_category(&app, "Candy", Some(snack_id)).await;
+
+    // Act
+    let cyclic = app
+        .put(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": food_id.to_string(), "parent_id": candy_id.to_string() }),
+        )
+        .await;
+    let moved = app
+        .put(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": candy_id.to_string(), "parent_id": food_id.to_string() }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(cyclic.status().as_u16(), 422);
+    assert_eq!(moved.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn category_can_be_moved_back_to_the_root() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let food_id = create_a_category(&app, "Food", None).await;
+    let snack_id = create_a_category(&app, "Snack", Some(food_id)).await;
+    let uri = format!("/api/v1/admin/categories/{snack_id}");
+
+    // Act
+    let renamed = app
+        .put(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": snack_id.to_string(), "name": "Snacks" }),
+        )
+        .await;
+    let renamed_parent_id = app
+        .get(&uri)
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse the category")["parent_id"]
+        .clone();
+    let moved = app
+        .put(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": snack_id.to_string(), "parent_id": null }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(renamed.status().as_u16(), 200);
+    assert_eq!(renamed_parent_id, food_id.to_string());
+    assert_eq!(moved.status().as_u16(), 200);
+
+    let snack = app
+        .get(&uri)
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse the category");
+    assert_eq!(snack["name"], "Snacks");
+    assert!(snack["parent_id"].is_null());
+}
+
+#[tokio::test]
+async fn delete_category_returns_a_409_when_it_is_not_empty() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let food_id = create_a_category(&app, "Food", None).await;
+    let snack_id = create_a_category(&app, "Snack", Some(food_id)).await;
+    let candy_id = create_a_category(&app, "Candy", Some(food_id)).await;
+    create_a_product_with(&app, snack_id, &[]).await;
+
+    // Act
+    let with_children = app
+        .delete(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": food_id.to_string() }),
+        )
+        .await;
+    let with_products = app
+        .delete(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": snack_id.to_string() }),
+        )
+        .await;
+    let empty = app
+        .delete(
+            "/api/v1/admin/categories",
+            &serde_json::json!({ "id": candy_id.to_string() }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(with_children.status().as_u16(), 409);
+    assert_eq!(with_products.status().as_u16(), 409);
+    assert_eq!(empty.status().as_u16(), 200);
+
+    let candy = app
+        .get(&format!("/api/v1/admin/categories/{candy_id}"))
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse the category");
+    assert!(!candy["deleted_at"].is_null());
+}
+
+#[tokio::test]
+async fn list_products_works_with_category_and_tags() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let food_id = create_a_category(&app, "Food", None).await;
+    let snack_id = create_a_category(&app, "Snack", Some(food_id)).await;
+    let toy_id = create_a_category(&app, "Toy", None).await;
+
+    let drink = create_a_product_with(&app, food_id, &["Limited", "tokyo"]).await;
+    let chips = create_a_product_with(&app, snack_id, &["limited"]).await;
+    let figure = create_a_product_with(&app, toy_id, &["tokyo", "anime"]).await;
+
+    let ids = |ids: &[i64]| {
+        let mut ids = ids.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    // Act & Assert
+    assert_eq!(
+        list_product_ids(&app, serde_json::json!({ "category_id": food_id })).await,
+        ids(&[drink, chips])
+    );
+    assert_eq!(
+        list_product_ids(&app, serde_json::json!({ "category_id": snack_id })).await,
+        ids(&[chips])
+    );
+    assert_eq!(
+        list_product_ids(
+            &app,
+            serde_json::json!({ "any_tags": ["LIMITED", "anime"] })
+        )
+        .await,
+        ids(&[drink, chips, figure])
+    );
+    assert_eq!(
+        list_product_ids(
+            &app,
+            serde_json::json!({ "all_tags": ["limited", "tokyo"] })
+        )
+        .await,
+        ids(&[drink])
+    );
+    assert_eq!(
+        list_product_ids(
+            &app,
+            serde_json::json!({ "category_id": food_id, "any_tags": ["tokyo"] })
+        )
+        .await,
+        ids(&[drink])
+    );
+
+    let product = app
+        .get(&format!("/api/v1/admin/products/{drink}"))
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse the product");
+    assert_eq!(product["category_id"], food_id.to_string());
+    assert_eq!(product["tags"], serde_json::json!(["limited", "tokyo"]));
+}
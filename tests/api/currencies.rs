@@ -0,0 +1,116 @@
+use crate::helpers::spawn_app;
+use japonfou::routes::{CreateCurrencyResponse, ListCurrenciesResponse};
+
+#[tokio::test]
+async fn list_currencies_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app.get("/api/v1/admin/currencies").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let currencies = response
+        .json::<ListCurrenciesResponse>()
+        .await
+        .expect("Failed to parse the currencies")
+        .data;
+    let hkd = currencies
+        .iter()
+        .find(|e| e.id == 344)
+        .expect("HKD is missing");
+    assert_eq!(hkd.code, "HKD");
+    assert_eq!(hkd.symbol, "HK$");
+    assert_eq!(hkd.minor_units, 2);
+    assert!(currencies.iter().any(|e| e.code == "MOP"));
+}
+
+#[tokio::test]
+async fn create_currency_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let body = serde_json::json!({
+        "id": 840,
+        "code": "usd",
+        "symbol": "US$",
+        "minor_units": 2,
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/currencies", &body).await;
+    let duplicate = app.post("/api/v1/admin/currencies", &body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let id = response
+        .json::<CreateCurrencyResponse>()
+        .await
+        .expect("Failed to parse the currency")
+        .id;
+    assert_eq!(id, 840);
+    assert_eq!(duplicate.status().as_u16(), 409);
+
+    let currencies = app
+        .get("/api/v1/admin/currencies")
+        .await
+        .json::<ListCurrenciesResponse>()
+        .await
+        .expect("Failed to parse the currencies")
+        .data;
+    let usd = currencies
+        .iter()
+        .find(|e| e.id == 840)
+        .expect("USD is missing");
+    assert_eq!(usd.code, "USD");
+
+    let product = app
+        .post(
+            "/api/v1/admin/products",
+            &serde_json::json!({ "name": "Pocky", "currency": 840, "price": 2.5 }),
+        )
+        .await;
+    assert_eq!(product.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn create_currency_return_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "id": 840, "code": "US", "symbol": "$", "minor_units": 2 }),
+            "code isn't 3 letters",
+        ),
+        (
+            serde_json::json!({ "id": 0, "code": "USD", "symbol": "$", "minor_units": 2 }),
+            "id is out of range",
+        ),
+        (
+            serde_json::json!({ "id": 840, "code": "USD", "symbol": " ", "minor_units": 2 }),
+            "symbol is empty",
+        ),
+        (
+            serde_json::json!({ "id": 840, "code": "USD", "symbol": "$", "minor_units": 5 }),
+            "minor units are out of range",
+        ),
+    ];
+
+    for (body, msg) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/currencies", &body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when the payload was {msg}",
+        );
+    }
+}
@@ -3,9 +3,16 @@ use fake::faker::internet::en::SafeEmail;
 use fake::faker::name::en::Name;
 use fake::Fake;
 
-use japonfou::routes::{CreateCustomerResponse, CustomerJson, ListCustomersResponse};
+use japonfou::routes::{
+    ContactMethodJson, ContactMethodKind, CreateContactMethodResponse, CreateCustomerResponse,
+    CreateProductResponse, CustomerBalancesResponse, CustomerDuplicateReason, CustomerJson,
+    CustomerMergeJson, CustomerOrderItemsResponse, ListContactMethodsResponse,
+    ListCustomerDuplicatesResponse, ListCustomersResponse, NormalizePhonesResponse,
+    UnnormalizedPhoneJson,
+};
+use rust_decimal::Decimal;
 
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, AuthTestApp};
 
 #[tokio::test]
 async fn create_customer_works() {
@@ -46,7 +53,65 @@ async fn create_customer_works() {
 
     assert_eq!(data_from_db.email, Some(email));
     assert_eq!(data_from_db.name, name);
-    assert_eq!(data_from_db.phone, Some(phone));
+    assert_eq!(data_from_db.phone.as_deref(), Some("+85312345678"));
+}
+
+#[tokio::test]
+async fn create_customer_stores_phones_in_e164() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let test_cases = vec![
+        ("6123 5678", "+85361235678", "+853 6123 5678"),
+        ("(853) 61235679", "+85361235679", "+853 6123 5679"),
+        ("+852 9123-4567", "+85291234567", "+852 9123 4567"),
+        ("0086 138 1234 5678", "+8613812345678", "+86 138 1234 5678"),
+        ("+886 912 345 678", "+886912345678", "+886 912 345 678"),
+        ("+81 90-1234-5678", "+819012345678", "+81 90 1234 5678"),
+        ("+81 3-1234-5678", "+81312345678", "+81 312345678"),
+    ];
+
+    for (phone, expected, expected_display) in test_cases {
+        let request = serde_json::json!({ "name": "boris", "phone": phone });
+
+        // Act
+        let response = app.post("/api/v1/admin/customers", &request).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200, "Failed to create {phone}");
+        let id = response
+            .json::<CreateCustomerResponse>()
+            .await
+            .expect("Failed to parse the customer")
+            .id;
+        let customer = app
+            .get(&format!("/api/v1/admin/customers/{id}"))
+            .await
+            .json::<CustomerJson>()
+            .await
+            .expect("Failed to parse the customer");
+        assert_eq!(customer.phone.as_deref(), Some(expected));
+        assert_eq!(customer.phone_display.as_deref(), Some(expected_display));
+    }
+}
+
+#[tokio::test]
+async fn create_customer_return_a_409_when_the_phone_is_formatted_differently() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let request = serde_json::json!({ "name": "boris", "phone": "(853) 61235678" });
+    let response = app.post("/api/v1/admin/customers", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act
+    let request = serde_json::json!({ "name": "boris", "phone": "+853 6123-5678" });
+    let response = app.post("/api/v1/admin/customers", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 409);
 }
 
 #[tokio::test]
@@ -72,6 +137,20 @@ async fn create_new_customer_return_a_400_when_data_is_invalid() {
             }),
             "Phone is invalid",
         ),
+        (
+            serde_json::json!({
+                "name": "boris",
+                "phone": "+1 212 555 0100",
+            }),
+            "Phone is in an unsupported country",
+        ),
+        (
+            serde_json::json!({
+                "name": "boris",
+                "phone": "+853 6123 567",
+            }),
+            "Phone is too short",
+        ),
     ];
 
     for (body, msg) in test_case {
@@ -159,7 +238,7 @@ async fn update_customer_works() {
     // Act 2 - Update a customer
     let name: String = Name().fake();
     let email: String = SafeEmail().fake();
-    let phone = "(853) 87654321".to_string();
+    let phone = "(853) 8765 4321".to_string();
 
     let update_request = serde_json::json!({
         "id": id.to_string(),
@@ -183,7 +262,7 @@ async fn update_customer_works() {
 
     assert_eq!(data_from_db.email, Some(email));
     assert_eq!(data_from_db.name, name);
-    assert_eq!(data_from_db.phone, Some(phone));
+    assert_eq!(data_from_db.phone.as_deref(), Some("+85387654321"));
 }
 
 #[tokio::test]
@@ -495,3 +574,706 @@ async fn list_customers_failed_when_keyword_is_invalid() {
     // Assert
     assert_eq!(response.status().as_u16(), 400);
 }
+
+async fn create_a_customer_with(app: &AuthTestApp, name: &str, email: &str, phone: &str) -> i64 {
+    let request = serde_json::json!({ "name": name, "email": email, "phone": phone });
+    let response = app.post("/api/v1/admin/customers", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<CreateCustomerResponse>()
+        .await
+        .expect("Failed to parse the customer")
+        .id
+}
+
+async fn search_customers(app: &AuthTestApp, q: &str) -> Vec<String> {
+    let url =
+        reqwest::Url::parse_with_params("http://localhost/api/v1/admin/customers", [("q", q)])
+            .expect("Failed to build the url");
+    let uri = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+    let response = app.get(&uri).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<ListCustomersResponse>()
+        .await
+        .expect("Failed to parse the customers")
+        .data
+        .into_iter()
+        .map(|e| e.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn search_customers_matches_any_field_and_normalized_phones() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    create_a_customer_with(&app, "Chan Tai Man", "taiman@example.com", "(853) 61235678").await;
+    create_a_customer_with(&app, "Chan Siu Ming", "ming@example.com", "66001234").await;
+    create_a_customer_with(&app, "Wong Ka Yan", "KaYan@Example.com", "66005678").await;
+
+    let test_cases = vec![
+        (
+            "Chan, 5678",
+            vec!["Chan Tai Man"],
+            "the name and phone are given",
+        ),
+        (
+            "CHAN",
+            vec!["Chan Siu Ming", "Chan Tai Man"],
+            "the case is different",
+        ),
+        ("kayan@example", vec!["Wong Ka Yan"], "the email is given"),
+        (
+            "+853 6123-5678",
+            vec!["Chan Tai Man"],
+            "the phone is formatted",
+        ),
+        (
+            "(853)66005678",
+            vec!["Wong Ka Yan"],
+            "the phone has a prefix",
+        ),
+        ("6123 5678", vec!["Chan Tai Man"], "the phone has no prefix"),
+        ("100%", vec![], "the text has a wildcard"),
+    ];
+
+    for (q, expected, msg) in test_cases {
+        // Act
+        let names = search_customers(&app, q).await;
+
+        // Assert
+        assert_eq!(names, expected, "The search didn't match when {msg}");
+    }
+}
+
+#[tokio::test]
+async fn search_customers_orders_exact_matches_first() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    create_a_customer_with(&app, "Au Chan Kei", "kei@example.com", "66001111").await;
+    create_a_customer_with(&app, "Chan Tai Man", "taiman@example.com", "66002222").await;
+    create_a_customer_with(&app, "Chan", "chan@example.com", "66003333").await;
+    create_a_customer_with(&app, "Chan Siu Ming", "ming@example.com", "66004444").await;
+
+    // Act
+    let names = search_customers(&app, "chan").await;
+
+    // Assert
+    assert_eq!(
+        names,
+        vec!["Chan", "Chan Siu Ming", "Chan Tai Man", "Au Chan Kei"]
+    );
+}
+
+async fn list_duplicates(
+    app: &AuthTestApp,
+    uri: &str,
+) -> Vec<(String, String, Vec<CustomerDuplicateReason>)> {
+    let response = app.get(uri).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<ListCustomerDuplicatesResponse>()
+        .await
+        .expect("Failed to parse the duplicates")
+        .data
+        .into_iter()
+        .map(|e| (e.customer.id, e.duplicate.id, e.reasons))
+        .collect()
+}
+
+#[tokio::test]
+async fn list_customer_duplicates_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let a_id = create_a_customer_with(&app, "Chan Tai Man", "taiman@example.com", "61230001").await;
+    let b_id = create_a_customer_with(&app, "Peter Chan", "TaiMan@Example.com", "61230002").await;
+    let c_id = create_a_customer_with(&app, "Chan Tai Man", "chan@example.com", "61230003").await;
+    let d_id = create_a_customer_with(&app, "Wong Ka Yan", "kayan@example.com", "61230004").await;
+    create_a_customer_with(&app, "Lee Siu Lung", "lee@example.com", "61230005").await;
+    // The duplicate phone was typed by another channel before the check.
+    sqlx::query("UPDATE customers SET phone = '+85361230001' WHERE id = $1")
+        .bind(d_id)
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to update the phone");
+
+    // Act
+    let duplicates = list_duplicates(&app, "/api/v1/admin/customers/duplicates").await;
+    let of_d = list_duplicates(
+        &app,
+        &format!("/api/v1/admin/customers/duplicates?customer_id={d_id}"),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(
+        duplicates,
+        vec![
+            (
+                a_id.to_string(),
+                b_id.to_string(),
+                vec![CustomerDuplicateReason::SameEmail]
+            ),
+            (
+                a_id.to_string(),
+                c_id.to_string(),
+                vec![CustomerDuplicateReason::SimilarName]
+            ),
+            (
+                a_id.to_string(),
+                d_id.to_string(),
+                vec![CustomerDuplicateReason::SamePhone]
+            ),
+        ]
+    );
+    assert_eq!(of_d.len(), 1);
+    assert_eq!(of_d[0].0, a_id.to_string());
+}
+
+#[tokio::test]
+async fn merge_customers_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let into_id = app.create_a_new_customer().await;
+    let from_id = app.create_a_new_customer().await;
+    for (id, remark) in [(into_id, "VIP"), (from_id, "Likes Pokemon")] {
+        let request = serde_json::json!({ "id": id.to_string(), "remark": remark });
+        let response = app.put("/api/v1/admin/customers", &request).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let product_id = app.create_a_new_product().await;
+    app.create_an_order_item_with(into_id, product_id, 1).await;
+    app.create_an_order_item_with(from_id, product_id, 1).await;
+    app.create_an_order_item_with(from_id, product_id, 2).await;
+    let request = serde_json::json!({
+        "customer_id": from_id,
+        "amount": "10",
+        "currency": 344,
+        "method": "cash",
+    });
+    let response = app.post("/api/v1/admin/payments", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    create_a_contact_method(&app, into_id, "instagram", "boris.chan", true).await;
+    create_a_contact_method(&app, from_id, "wechat", "boris_chan", true).await;
+
+    // Act
+    let request = serde_json::json!({ "from_customer_id": from_id, "into_customer_id": into_id });
+    let response = app.post("/api/v1/admin/customers/merge", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let record = response
+        .json::<CustomerMergeJson>()
+        .await
+        .expect("Failed to parse the merge");
+    assert_eq!(record.from_customer_id, from_id.to_string());
+    assert_eq!(record.into_customer_id, into_id.to_string());
+    assert_eq!(record.order_items_moved, 2);
+    assert_eq!(record.orders_moved, 0);
+    assert_eq!(record.payments_moved, 1);
+    assert_eq!(record.contact_methods_moved, 1);
+    assert_eq!(record.merged_by, app.test_user.id.to_string());
+
+    let order_items = app
+        .get(&format!("/api/v1/admin/customers/{into_id}/order_items"))
+        .await
+        .json::<CustomerOrderItemsResponse>()
+        .await
+        .expect("Failed to parse the order items");
+    assert_eq!(order_items.order_items.len(), 3);
+    assert_eq!(
+        order_items.customer.remark.as_deref(),
+        Some("VIP\nLikes Pokemon")
+    );
+
+    let contact_methods = list_contact_methods(&app, into_id).await;
+    assert_eq!(contact_methods.len(), 2);
+    assert_eq!(contact_methods[0].value, "boris.chan");
+    assert!(contact_methods[0].preferred);
+    assert_eq!(contact_methods[1].value, "boris_chan");
+    assert!(!contact_methods[1].preferred);
+
+    let merged = app
+        .get(&format!("/api/v1/admin/customers/{from_id}"))
+        .await
+        .json::<CustomerJson>()
+        .await
+        .expect("Failed to parse the customer");
+    assert!(merged.deleted_at.is_some());
+
+    let balances = app
+        .get(&format!("/api/v1/admin/customers/{into_id}/balances"))
+        .await
+        .json::<CustomerBalancesResponse>()
+        .await
+        .expect("Failed to parse the balances");
+    assert_eq!(balances.data[0].paid.amount, Decimal::new(10, 0));
+}
+
+#[tokio::test]
+async fn merge_customers_return_an_error_when_a_customer_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let into_id = app.create_a_new_customer().await;
+    let from_id = app.create_a_new_customer().await;
+
+    // Act
+    let itself = serde_json::json!({ "from_customer_id": into_id, "into_customer_id": into_id });
+    let response = app.post("/api/v1/admin/customers/merge", &itself).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+
+    let missing = serde_json::json!({ "from_customer_id": 1, "into_customer_id": into_id });
+    let response = app.post("/api/v1/admin/customers/merge", &missing).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    let request = serde_json::json!({ "from_customer_id": from_id, "into_customer_id": into_id });
+    let response = app.post("/api/v1/admin/customers/merge", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post("/api/v1/admin/customers/merge", &request).await;
+    assert_eq!(
+        response.status().as_u16(),
+        404,
+        "The merged customer was merged again"
+    );
+}
+
+async fn create_a_contact_method(
+    app: &AuthTestApp,
+    customer_id: i64,
+    kind: &str,
+    value: &str,
+    preferred: bool,
+) -> i64 {
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "kind": kind,
+        "value": value,
+        "preferred": preferred,
+    });
+    let response = app
+        .post("/api/v1/admin/customers/contact_methods", &request)
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<CreateContactMethodResponse>()
+        .await
+        .expect("Failed to parse the contact method")
+        .id
+}
+
+async fn list_contact_methods(app: &AuthTestApp, customer_id: i64) -> Vec<ContactMethodJson> {
+    let response = app
+        .get(&format!(
+            "/api/v1/admin/customers/{customer_id}/contact_methods"
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<ListContactMethodsResponse>()
+        .await
+        .expect("Failed to parse the contact methods")
+        .data
+}
+
+#[tokio::test]
+async fn create_contact_method_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+
+    // Act
+    create_a_contact_method(&app, customer_id, "instagram", "@Boris.Chan", false).await;
+    create_a_contact_method(&app, customer_id, "whatsapp", "6123 5678", true).await;
+    create_a_contact_method(&app, customer_id, "wechat", "Boris_Chan", false).await;
+    create_a_contact_method(&app, customer_id, "email", " Boris@Example.com", false).await;
+
+    // Assert
+    let contact_methods = list_contact_methods(&app, customer_id).await;
+    let values = contact_methods
+        .iter()
+        .map(|e| (e.kind, e.value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        vec![
+            (ContactMethodKind::Whatsapp, "+85361235678"),
+            (ContactMethodKind::Instagram, "boris.chan"),
+            (ContactMethodKind::Wechat, "boris_chan"),
+            (ContactMethodKind::Email, "boris@example.com"),
+        ]
+    );
+    assert!(contact_methods[0].preferred);
+    assert!(contact_methods.iter().all(|e| !e.verified));
+
+    let customer = app
+        .get(&format!("/api/v1/admin/customers/{customer_id}"))
+        .await
+        .json::<CustomerJson>()
+        .await
+        .expect("Failed to parse the customer");
+    assert_eq!(customer.contact_methods.len(), 4);
+    assert_eq!(customer.contact_methods[0].value, "+85361235678");
+}
+
+#[tokio::test]
+async fn create_contact_method_return_a_409_when_value_is_taken() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let other_customer_id = app.create_a_new_customer().await;
+    create_a_contact_method(&app, customer_id, "whatsapp", "6123 5678", false).await;
+
+    // Act
+    let request = serde_json::json!({
+        "customer_id": other_customer_id,
+        "kind": "whatsapp",
+        "value": "+853 6123-5678",
+    });
+    let response = app
+        .post("/api/v1/admin/customers/contact_methods", &request)
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 409);
+
+    // The same number of another kind belongs to its own channel.
+    create_a_contact_method(&app, other_customer_id, "phone", "6123 5678", false).await;
+}
+
+#[tokio::test]
+async fn create_contact_method_return_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "kind": "whatsapp", "value": "123" }),
+            "the phone is too short",
+        ),
+        (
+            serde_json::json!({ "kind": "email", "value": "boris" }),
+            "the email is invalid",
+        ),
+        (
+            serde_json::json!({ "kind": "wechat", "value": "boris" }),
+            "the WeChat ID is too short",
+        ),
+        (
+            serde_json::json!({ "kind": "wechat", "value": "1boris" }),
+            "the WeChat ID starts with a digit",
+        ),
+        (
+            serde_json::json!({ "kind": "instagram", "value": "boris chan" }),
+            "the handle has a space",
+        ),
+        (
+            serde_json::json!({ "kind": "facebook", "value": " " }),
+            "the value is empty",
+        ),
+        (
+            serde_json::json!({ "kind": "line", "value": "boris" }),
+            "the kind is unknown",
+        ),
+    ];
+
+    for (mut body, msg) in test_cases {
+        body["customer_id"] = customer_id.into();
+
+        // Act
+        let response = app
+            .post("/api/v1/admin/customers/contact_methods", &body)
+            .await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when {msg}",
+        );
+    }
+
+    let body = serde_json::json!({ "customer_id": 1, "kind": "wechat", "value": "boris_chan" });
+    let response = app
+        .post("/api/v1/admin/customers/contact_methods", &body)
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn update_contact_method_moves_the_preferred_flag() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let whatsapp_id =
+        create_a_contact_method(&app, customer_id, "whatsapp", "6123 5678", true).await;
+    let facebook_id =
+        create_a_contact_method(&app, customer_id, "facebook", "boris.chan.1", false).await;
+
+    // Act
+    let request = serde_json::json!({
+        "id": facebook_id.to_string(),
+        "value": "Boris.Chan.2",
+        "preferred": true,
+        "verified": true,
+    });
+    let response = app
+        .put("/api/v1/admin/customers/contact_methods", &request)
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let contact_methods = list_contact_methods(&app, customer_id).await;
+    assert_eq!(contact_methods.len(), 2);
+    assert_eq!(contact_methods[0].id, facebook_id.to_string());
+    assert_eq!(contact_methods[0].value, "boris.chan.2");
+    assert!(contact_methods[0].preferred);
+    assert!(contact_methods[0].verified);
+    assert_eq!(contact_methods[1].id, whatsapp_id.to_string());
+    assert!(!contact_methods[1].preferred);
+
+    let request = serde_json::json!({ "id": whatsapp_id.to_string() });
+    let response = app
+        .delete("/api/v1/admin/customers/contact_methods", &request)
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(list_contact_methods(&app, customer_id).await.len(), 1);
+
+    let response = app
+        .delete("/api/v1/admin/customers/contact_methods", &request)
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    let request = serde_json::json!({ "id": whatsapp_id.to_string(), "verified": true });
+    let response = app
+        .put("/api/v1/admin/customers/contact_methods", &request)
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn search_customers_matches_contact_methods() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = create_a_customer_with(&app, "Boris", "boris@example.com", "6123 5678").await;
+    create_a_customer_with(&app, "Chan", "chan@example.com", "6123 5679").await;
+    create_a_contact_method(&app, customer_id, "wechat", "pikachu_fan", false).await;
+    create_a_contact_method(&app, customer_id, "whatsapp", "+852 9123 4567", false).await;
+
+    // Act & Assert
+    assert_eq!(search_customers(&app, "Pikachu").await, vec!["Boris"]);
+    assert_eq!(search_customers(&app, "9123 4567").await, vec!["Boris"]);
+
+    let test_cases = vec![
+        (r#"{ "contact": "pikachu" }"#, 1),
+        (r#"{ "contact": "pikachu", "contact_kind": "wechat" }"#, 1),
+        (
+            r#"{ "contact": "pikachu", "contact_kind": "instagram" }"#,
+            0,
+        ),
+        (
+            r#"{ "contact": "9123-4567", "contact_kind": "whatsapp" }"#,
+            1,
+        ),
+        (r#"{ "contact_kind": "whatsapp" }"#, 1),
+        (r#"{ "contact_kind": "facebook" }"#, 0),
+    ];
+
+    for (keyword, expected) in test_cases {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(keyword);
+        let response = app
+            .get(&format!("/api/v1/admin/customers?keyword={encoded}"))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+        let data = response
+            .json::<ListCustomersResponse>()
+            .await
+            .expect("Failed to parse the customers")
+            .data;
+        assert_eq!(data.len(), expected, "Unexpected customers for {keyword}");
+    }
+}
+
+#[tokio::test]
+async fn list_customer_order_items_works_with_totals_per_currency() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let other_customer_id = app.create_a_new_customer().await;
+    // The price of the helper product is 20 HKD.
+    let hkd_product_id = app.create_a_new_product().await;
+    let request = serde_json::json!({
+        "name": "MOP product",
+        "currency": 446,
+        "price": 15.5,
+    });
+    let response = app.post("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let mop_product_id = response
+        .json::<CreateProductResponse>()
+        .await
+        .expect("Failed to decode json")
+        .id;
+
+    let _ = app
+        .create_an_order_item_with(customer_id, hkd_product_id, 2)
+        .await;
+    let arrived_id = app
+        .create_an_order_item_with(customer_id, hkd_product_id, 1)
+        .await;
+    app.change_order_item_status(arrived_id, &["purchased_in_japan", "in_transit", "arrived"])
+        .await;
+    app.receive_stock(mop_product_id, 3).await;
+    let picked_up_id = app
+        .create_an_order_item_with(customer_id, mop_product_id, 3)
+        .await;
+    app.change_order_item_status(
+        picked_up_id,
+        &["purchased_in_japan", "in_transit", "arrived", "picked_up"],
+    )
+    .await;
+    let cancelled_id = app
+        .create_an_order_item_with(customer_id, hkd_product_id, 1)
+        .await;
+    app.change_order_item_status(cancelled_id, &["cancelled"])
+        .await;
+    let _ = app
+        .create_an_order_item_with(other_customer_id, hkd_product_id, 5)
+        .await;
+
+    // Act
+    let uri = format!("/api/v1/admin/customers/{customer_id}/order_items");
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<CustomerOrderItemsResponse>()
+        .await
+        .expect("Failed to decode json");
+
+    assert_eq!(data.customer.id, customer_id.to_string());
+    assert_eq!(data.order_items.len(), 4);
+    assert!(data
+        .order_items
+        .iter()
+        .all(|e| e.customer.id == customer_id.to_string()));
+
+    assert_eq!(data.totals.len(), 2);
+    let hkd = data.totals.iter().find(|e| e.currency == 344).unwrap();
+    assert_eq!(hkd.currency_name.as_deref(), Some("HKD"));
+    assert_eq!(hkd.ordered.amount, Decimal::new(60, 0));
+    assert_eq!(hkd.arrived.amount, Decimal::new(20, 0));
+    assert_eq!(hkd.picked_up.amount, Decimal::ZERO);
+    let mop = data.totals.iter().find(|e| e.currency == 446).unwrap();
+    assert_eq!(mop.currency_name.as_deref(), Some("MOP"));
+    assert_eq!(mop.ordered.amount, Decimal::new(465, 1));
+    assert_eq!(mop.arrived.amount, Decimal::ZERO);
+    assert_eq!(mop.picked_up.amount, Decimal::new(465, 1));
+}
+
+#[tokio::test]
+async fn list_customer_order_items_return_a_404_when_customer_is_not_exist() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app.get("/api/v1/admin/customers/1/order_items").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn normalize_phones_leaves_the_unsupported_numbers_unchanged() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let mut ids = vec![];
+    for phone in ["(853) 6123 5678", "6234 5678", "(123) 4567890"] {
+        let id = app.create_a_new_customer().await;
+        sqlx::query("UPDATE customers SET phone = $1 WHERE id = $2")
+            .bind(phone)
+            .bind(id)
+            .execute(&app.db_pool)
+            .await
+            .expect("Failed to set the phone");
+        ids.push(id);
+    }
+
+    // Act
+    let response = app
+        .post(
+            "/api/v1/admin/customers/normalize_phones",
+            &serde_json::json!({}),
+        )
+        .await;
+    let again = app
+        .post(
+            "/api/v1/admin/customers/normalize_phones",
+            &serde_json::json!({}),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response = response
+        .json::<NormalizePhonesResponse>()
+        .await
+        .expect("Failed to parse the response");
+    assert_eq!(response.normalized, 2);
+    assert_eq!(
+        response.unchanged,
+        vec![UnnormalizedPhoneJson {
+            customer_id: ids[2].to_string(),
+            phone: "(123) 4567890".to_string(),
+        }]
+    );
+    let again = again
+        .json::<NormalizePhonesResponse>()
+        .await
+        .expect("Failed to parse the response");
+    assert_eq!(again.normalized, 0);
+    let phones = sqlx::query_scalar::<_, String>(
+        "SELECT phone FROM customers WHERE id = ANY($1) ORDER BY id",
+    )
+    .bind(&ids)
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch the phones");
+    assert_eq!(
+        phones,
+        vec!["+85361235678", "+85362345678", "(123) 4567890"]
+    );
+}
@@ -0,0 +1,329 @@
+use rust_decimal::Decimal;
+
+use crate::helpers::{spawn_app, AuthTestApp};
+use japonfou::routes::{
+    CreateExchangeRateResponse, CreateProductResponse, CustomerBalancesResponse,
+    CustomerOrderItemsResponse, ImportExchangeRatesResponse, ListExchangeRatesResponse,
+};
+
+async fn create_a_rate(app: &AuthTestApp, base: i16, quote: i16, rate: &str, valid_from: &str) {
+    let request = serde_json::json!({
+        "base_currency": base,
+        "quote_currency": quote,
+        "rate": rate,
+        "valid_from": valid_from,
+    });
+
+    let response = app.post("/api/v1/admin/exchange_rates", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn create_exchange_rate_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let body = serde_json::json!({
+        "base_currency": 344,
+        "quote_currency": 446,
+        "rate": "1.03",
+        "valid_from": "2024-05-01",
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/exchange_rates", &body).await;
+    let replaced = app
+        .post(
+            "/api/v1/admin/exchange_rates",
+            &serde_json::json!({
+                "base_currency": 344,
+                "quote_currency": 446,
+                "rate": "1.031",
+                "valid_from": "2024-05-01",
+            }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let id = response
+        .json::<CreateExchangeRateResponse>()
+        .await
+        .expect("Failed to parse the exchange rate")
+        .id;
+    assert_eq!(replaced.status().as_u16(), 200);
+    let replaced_id = replaced
+        .json::<CreateExchangeRateResponse>()
+        .await
+        .expect("Failed to parse the exchange rate")
+        .id;
+    assert_eq!(id, replaced_id);
+
+    let rates = app
+        .get("/api/v1/admin/exchange_rates?base_currency=344")
+        .await
+        .json::<ListExchangeRatesResponse>()
+        .await
+        .expect("Failed to parse the exchange rates")
+        .data;
+    assert_eq!(rates.len(), 1);
+    assert_eq!(rates[0].id, id.to_string());
+    assert_eq!(rates[0].quote_currency, 446);
+    assert_eq!(rates[0].rate, Decimal::new(1031, 3));
+    assert!(rates[0].updated_at.is_some());
+}
+
+#[tokio::test]
+async fn create_exchange_rate_return_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "base_currency": 344, "quote_currency": 344, "rate": "1", "valid_from": "2024-05-01" }),
+            "the currencies are the same",
+        ),
+        (
+            serde_json::json!({ "base_currency": 344, "quote_currency": 446, "rate": "0", "valid_from": "2024-05-01" }),
+            "the rate is zero",
+        ),
+        (
+            serde_json::json!({ "base_currency": 344, "quote_currency": 446, "rate": "-1.03", "valid_from": "2024-05-01" }),
+            "the rate is negative",
+        ),
+    ];
+
+    for (body, msg) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/exchange_rates", &body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when {msg}",
+        );
+    }
+
+    let body = serde_json::json!({ "base_currency": 344, "quote_currency": 999, "rate": "1.03", "valid_from": "2024-05-01" });
+    let response = app.post("/api/v1/admin/exchange_rates", &body).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn import_exchange_rates_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let content = "valid_from,base_currency,quote_currency,rate\n\
+                   2024-05-01,JPY,HKD,0.0503\n\
+                   2024-05-01,hkd,446,1.03\n\
+                   2024-05-02,JPY,HKD,0.0501\n";
+    let form = reqwest::multipart::Form::new().part(
+        "file",
+        reqwest::multipart::Part::text(content).file_name("rates.csv"),
+    );
+
+    // Act
+    let response = app
+        .post_multipart("/api/v1/admin/exchange_rates/import", form)
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let imported = response
+        .json::<ImportExchangeRatesResponse>()
+        .await
+        .expect("Failed to parse the import")
+        .imported;
+    assert_eq!(imported, 3);
+
+    let rates = app
+        .get("/api/v1/admin/exchange_rates?base_currency=392")
+        .await
+        .json::<ListExchangeRatesResponse>()
+        .await
+        .expect("Failed to parse the exchange rates")
+        .data;
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[0].rate, Decimal::new(501, 4));
+    assert_eq!(rates[1].rate, Decimal::new(503, 4));
+}
+
+#[tokio::test]
+async fn import_exchange_rates_return_a_400_when_a_line_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let test_cases = vec![
+        ("2024-05-01,JPY,HKD,0.0503\n", "the header is missing"),
+        (
+            "valid_from,base_currency,quote_currency,rate\n2024-05-01,JPY,XXX,0.0503\n",
+            "the currency is unknown",
+        ),
+        (
+            "valid_from,base_currency,quote_currency,rate\n2024-05-01,JPY,HKD,abc\n",
+            "the rate is not a number",
+        ),
+        (
+            "valid_from,base_currency,quote_currency,rate\n2024-05-01,JPY,HKD\n",
+            "a column is missing",
+        ),
+        (
+            "valid_from,base_currency,quote_currency,rate\n",
+            "there is no rate",
+        ),
+    ];
+
+    for (content, msg) in test_cases {
+        let form = reqwest::multipart::Form::new().part(
+            "file",
+            reqwest::multipart::Part::text(content).file_name("rates.csv"),
+        );
+
+        // Act
+        let response = app
+            .post_multipart("/api/v1/admin/exchange_rates/import", form)
+            .await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when {msg}",
+        );
+    }
+
+    let rates = app
+        .get("/api/v1/admin/exchange_rates")
+        .await
+        .json::<ListExchangeRatesResponse>()
+        .await
+        .expect("Failed to parse the exchange rates")
+        .data;
+    assert!(rates.is_empty());
+}
+
+#[tokio::test]
+async fn customer_balances_total_is_converted_into_the_base_currency() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    app.create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "amount": "10",
+        "currency": 446,
+        "method": "cash",
+    });
+    let response = app.post("/api/v1/admin/payments", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let uri = format!("/api/v1/admin/customers/{customer_id}/balances");
+
+    let response = app.get(&uri).await;
+    let response: CustomerBalancesResponse =
+        response.json().await.expect("Failed to parse balances");
+    assert!(response.total.is_none());
+
+    // The inverse rate is used, and the rate from the future is ignored.
+    create_a_rate(&app, 446, 344, "0.5", "2020-01-01").await;
+    create_a_rate(&app, 446, 344, "0.1", "2999-01-01").await;
+
+    // Act
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: CustomerBalancesResponse =
+        response.json().await.expect("Failed to parse balances");
+    let total = response.total.expect("The total is missing");
+    assert_eq!(total.currency, 446);
+    assert_eq!(total.currency_name.as_deref(), Some("MOP"));
+    assert_eq!(total.charged.amount, Decimal::new(80, 0));
+    assert_eq!(total.paid.amount, Decimal::new(10, 0));
+    assert_eq!(total.balance.amount, Decimal::new(-70, 0));
+}
+
+#[tokio::test]
+async fn customer_order_items_total_is_converted_through_an_intermediate_currency() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let hkd_product_id = app.create_a_new_product().await;
+    let request = serde_json::json!({ "name": "Pocky", "currency": 392, "price": 1000 });
+    let response = app.post("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let jpy_product_id = response
+        .json::<CreateProductResponse>()
+        .await
+        .expect("Failed to parse the product")
+        .id;
+    app.create_an_order_item_with(customer_id, hkd_product_id, 1)
+        .await;
+    app.create_an_order_item_with(customer_id, jpy_product_id, 1)
+        .await;
+    create_a_rate(&app, 392, 344, "0.0503", "2020-01-01").await;
+    create_a_rate(&app, 344, 446, "1.03", "2020-01-01").await;
+
+    // Act
+    let uri = format!("/api/v1/admin/customers/{customer_id}/order_items");
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<CustomerOrderItemsResponse>()
+        .await
+        .expect("Failed to decode json");
+    let total = data.total.expect("The total is missing");
+    assert_eq!(total.currency, 446);
+    // 20 * 1.03 + 1000 * 0.0503 * 1.03 = 72.409
+    assert_eq!(total.ordered.amount, Decimal::new(7241, 2));
+    assert_eq!(total.arrived.amount, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn customer_order_items_total_uses_the_rate_of_the_local_date() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let order_item_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    // It's already June 1st in Macau.
+    sqlx::query("update order_items set created_at = '2024-05-31T20:00:00Z' where id = $1")
+        .bind(order_item_id)
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to update the order item");
+    create_a_rate(&app, 344, 446, "2.00", "2020-01-01").await;
+    create_a_rate(&app, 344, 446, "1.00", "2024-05-01").await;
+    create_a_rate(&app, 344, 446, "1.05", "2024-06-01").await;
+    create_a_rate(&app, 344, 446, "3.00", "2024-07-01").await;
+
+    // Act
+    let uri = format!("/api/v1/admin/customers/{customer_id}/order_items");
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<CustomerOrderItemsResponse>()
+        .await
+        .expect("Failed to decode json");
+    let total = data.total.expect("The total is missing");
+    assert_eq!(total.ordered.amount, Decimal::new(21, 0));
+}
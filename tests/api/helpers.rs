@@ -11,8 +11,11 @@ use serde_json::Value;
 use sqlx::types::Uuid;
 use sqlx::{Connection, Executor, PgConnection, PgPool};
 
-use japonfou::configuration::{get_configuration, DatabaseSettings};
-use japonfou::routes::{CreateCustomerResponse, CreateProductResponse, LoginResponse};
+use japonfou::configuration::{get_configuration, DatabaseSettings, StorageSettings};
+use japonfou::routes::{
+    CreateCustomerResponse, CreateOrderItemResponse, CreateOrderResponse, CreateProductResponse,
+    LoginResponse, ProductJson,
+};
 use japonfou::startup::{get_database_connection, run};
 use japonfou::utils::{JwtKey, JWT_SECRET_KEY_INSTANCE};
 
@@ -33,7 +36,7 @@ impl AuthTestApp {
         header_map.append(reqwest::header::AUTHORIZATION, token.parse().unwrap());
         let response = self
             .api_client
-            .post(&format!("{}/api/v1/logout", self.address))
+            .post(format!("{}/api/v1/logout", self.address))
             .headers(header_map)
             .send()
             .await
@@ -55,11 +58,12 @@ impl AuthTestApp {
         let name: String = Name().fake();
         let email: String = SafeEmail().fake();
         let mut rng = rand::thread_rng();
-        let sample = rand::seq::index::sample(&mut rng, 8, 8)
+        let sample = rand::seq::index::sample(&mut rng, 10, 7)
             .into_vec()
             .iter()
             .join("");
-        let phone = dbg!(format!("(853) {sample}"));
+        // A mobile number in Macau starts with 6.
+        let phone = dbg!(format!("(853) 6{sample}"));
 
         let request = serde_json::json!({
             "name": name,
@@ -93,6 +97,84 @@ impl AuthTestApp {
         res.id
     }
 
+    pub async fn get_product(&self, id: i64) -> ProductJson {
+        self.get(&format!("/api/v1/admin/products/{id}"))
+            .await
+            .json()
+            .await
+            .expect("Failed to parse the product")
+    }
+
+    pub async fn create_a_new_order_item(&self) -> i64 {
+        let customer_id = self.create_a_new_customer().await;
+        let product_id = self.create_a_new_product().await;
+
+        self.create_an_order_item_with(customer_id, product_id, 1)
+            .await
+    }
+
+    pub async fn create_an_order_item_with(
+        &self,
+        customer_id: i64,
+        product_id: i64,
+        quantity: u32,
+    ) -> i64 {
+        let req = serde_json::json!({
+            "customer_id": customer_id,
+            "product_id": product_id,
+            "quantity": quantity,
+        });
+
+        let res = self.post("/api/v1/admin/order_items", &req).await;
+        assert_eq!(res.status().as_u16(), 200);
+        let res: Result<CreateOrderItemResponse, reqwest::Error> = res.json().await;
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        res.id
+    }
+
+    pub async fn create_an_order_with(&self, customer_id: i64, order_item_ids: &[i64]) -> i64 {
+        let req = serde_json::json!({
+            "customer_id": customer_id,
+            "order_item_ids": order_item_ids.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        });
+
+        let res = self.post("/api/v1/admin/orders", &req).await;
+        assert_eq!(res.status().as_u16(), 200);
+        let res: Result<CreateOrderResponse, reqwest::Error> = res.json().await;
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        res.id
+    }
+
+    /// Receive the stock of the product, so that its order items can be picked up.
+    pub async fn receive_stock(&self, product_id: i64, quantity: i32) {
+        let req = serde_json::json!({
+            "product_id": product_id,
+            "kind": "received",
+            "quantity": quantity,
+            "reason": "received",
+        });
+
+        let res = self
+            .post("/api/v1/admin/products/stock_adjustments", &req)
+            .await;
+        assert_eq!(res.status().as_u16(), 200);
+    }
+
+    /// Move the order item through the given statuses one by one.
+    pub async fn change_order_item_status(&self, id: i64, statuses: &[&str]) {
+        for status in statuses {
+            let req = serde_json::json!({
+                "id": id.to_string(),
+                "status": status,
+            });
+
+            let res = self.put("/api/v1/admin/order_items", &req).await;
+            assert_eq!(res.status().as_u16(), 200);
+        }
+    }
+
     pub async fn post(&self, uri: &str, body: &Value) -> reqwest::Response {
         send_api_request(
             &self.api_client,
@@ -105,6 +187,20 @@ impl AuthTestApp {
         .await
     }
 
+    pub async fn post_multipart(
+        &self,
+        uri: &str,
+        form: reqwest::multipart::Form,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}{uri}", self.address))
+            .bearer_auth(&self.jwt_token)
+            .multipart(form)
+            .send()
+            .await
+            .expect("Failed to make a request")
+    }
+
     pub async fn put(&self, uri: &str, body: &Value) -> reqwest::Response {
         send_api_request(
             &self.api_client,
@@ -204,8 +300,8 @@ async fn send_api_request(
     token: Option<&str>,
 ) -> reqwest::Response {
     let mut header_map = reqwest::header::HeaderMap::new();
-    if token.is_some() {
-        let token = format!("Bearer {}", token.unwrap());
+    if let Some(token) = token {
+        let token = format!("Bearer {token}");
         header_map.append(reqwest::header::AUTHORIZATION, token.parse().unwrap());
     }
     let uri = format!("{address}{uri}");
@@ -275,7 +371,7 @@ impl TestUser {
 }
 
 pub async fn spawn_app() -> TestApp {
-    let configuration = {
+    let mut configuration = {
         let mut c = get_configuration().expect("Failed to read configuration");
         // Use a random OS port
         c.application.port = 0;
@@ -294,6 +390,15 @@ pub async fn spawn_app() -> TestApp {
         .expect("Can't bind tcp listener");
     let application_port = listener.local_addr().unwrap().port();
 
+    // Keep the uploaded files of each test case in a different directory.
+    configuration.storage = StorageSettings::Local {
+        root: std::env::temp_dir()
+            .join(format!("japonfou-{}", Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned(),
+        public_url: format!("http://127.0.0.1:{application_port}/uploads"),
+    };
+
     let _ = JWT_SECRET_KEY_INSTANCE
         .get_or_init(|| JwtKey::new(configuration.jwt.secret_key.as_bytes()));
 
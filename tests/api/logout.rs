@@ -32,7 +32,7 @@ async fn logout_should_be_login_first() {
     // Act
     let response = app
         .api_client
-        .post(&format!("{}/api/v1/logout", &app.address))
+        .post(format!("{}/api/v1/logout", &app.address))
         .send()
         .await
         .expect("Failed to make a request for logout.");
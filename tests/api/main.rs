@@ -1,7 +1,16 @@
+mod categories;
 mod change_password;
+mod currencies;
 mod customers;
+mod exchange_rates;
 mod health_check;
 mod helpers;
 mod login;
 mod logout;
+mod order_items;
+mod orders;
+mod payments;
+mod pricing_rules;
+mod product_images;
 mod products;
+mod shipments;
@@ -0,0 +1,1016 @@
+use base64::Engine;
+use rust_decimal::Decimal;
+
+use japonfou::routes::{
+    CreateOrderItemResponse, ListOrderItemStatusHistoriesResponse, ListOrderItemsResponse,
+    OrderItemJson, OrderItemStatus, UpdateOrderItemStatusesResponse,
+};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn create_order_item_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "product_id": product_id,
+        "quantity": 2,
+        "status": "requested",
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/order_items", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: Result<CreateOrderItemResponse, reqwest::Error> = response.json().await;
+    assert!(response.is_ok());
+    let id = response.unwrap().id;
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT customer_id, product_id, quantity, status FROM order_items where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved order item");
+
+    assert_eq!(data_from_db.customer_id, customer_id);
+    assert_eq!(data_from_db.product_id, product_id);
+    assert_eq!(data_from_db.quantity, 2);
+    assert_eq!(data_from_db.status, OrderItemStatus::Requested.code());
+}
+
+#[tokio::test]
+async fn order_item_keeps_the_price_of_the_product_when_it_was_created() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+
+    let request = serde_json::json!({
+        "id": product_id.to_string(),
+        "currency": 446,
+        "price": 99.0,
+    });
+    let response = app.put("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act
+    let response = app.get(&format!("/api/v1/admin/order_items/{id}")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let order_item: OrderItemJson = response.json().await.expect("Failed to parse order item");
+    assert_eq!(order_item.unit_price.amount, Decimal::new(20, 0));
+    assert_eq!(order_item.currency, 344);
+    assert_eq!(order_item.product.price.amount, Decimal::new(99, 0));
+}
+
+#[tokio::test]
+async fn create_order_item_with_a_unit_price_override_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "product_id": product_id,
+        "quantity": 2,
+        "unit_price": "15.5",
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/order_items", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let id = response
+        .json::<CreateOrderItemResponse>()
+        .await
+        .expect("Failed to parse the response")
+        .id;
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT unit_price, currency FROM order_items where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved order item");
+
+    assert_eq!(data_from_db.unit_price, Decimal::new(155, 1));
+    assert_eq!(data_from_db.currency, 344);
+
+    let request = serde_json::json!({
+        "id": id.to_string(),
+        "unit_price": "12",
+    });
+    let response = app.put("/api/v1/admin/order_items", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let order_item: serde_json::Value = app
+        .get(&format!("/api/v1/admin/order_items/{id}"))
+        .await
+        .json()
+        .await
+        .expect("Failed to parse order item");
+    assert_eq!(order_item["unit_price"], "12.00");
+    assert_eq!(order_item["currency"], 344);
+}
+
+#[tokio::test]
+async fn create_new_order_item_return_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "product_id": product_id,
+                "quantity": 0,
+                "status": "requested",
+            }),
+            "Quantity is zero",
+        ),
+        (
+            serde_json::json!({
+                "product_id": product_id,
+                "quantity": 1,
+                "status": "requested",
+            }),
+            "Missing customer id",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "quantity": 1,
+                "status": "requested",
+            }),
+            "Missing product id",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "product_id": product_id,
+                "quantity": 1,
+                "unit_price": "-1",
+            }),
+            "Negative unit price",
+        ),
+    ];
+
+    for (body, msg) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/order_items", &body).await;
+
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when the payload was {msg}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_new_order_item_return_a_422_when_customer_or_product_is_not_exist() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let deleted_customer_id = app.create_a_new_customer().await;
+    let deleted_product_id = app.create_a_new_product().await;
+
+    let response = app
+        .delete(
+            "/api/v1/admin/customers",
+            &serde_json::json!({ "id": deleted_customer_id }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app
+        .delete(
+            "/api/v1/admin/products",
+            &serde_json::json!({ "id": deleted_product_id }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let test_cases = vec![
+        (customer_id + 1, product_id, "Customer is not exist"),
+        (customer_id, product_id + 1, "Product is not exist"),
+        (deleted_customer_id, product_id, "Customer is deleted"),
+        (customer_id, deleted_product_id, "Product is deleted"),
+    ];
+
+    for (customer_id, product_id, msg) in test_cases {
+        let body = serde_json::json!({
+            "customer_id": customer_id,
+            "product_id": product_id,
+            "quantity": 1,
+        });
+
+        // Act
+        let response = app.post("/api/v1/admin/order_items", &body).await;
+
+        // Assert
+        assert_eq!(
+            422,
+            response.status().as_u16(),
+            "The API didn't fail with 422 Unprocessable Entity when {msg}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn get_order_item_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+
+    // Act
+    let uri = format!("/api/v1/admin/order_items/{id}");
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data: OrderItemJson = response.json().await.expect("Failed to decode json");
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT customer_id, product_id, quantity FROM order_items where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved order item");
+
+    assert_eq!(data.id, id.to_string());
+    assert_eq!(data.customer.id, data_from_db.customer_id.to_string());
+    assert_eq!(data.product.id, data_from_db.product_id.to_string());
+    assert_eq!(data.quantity, data_from_db.quantity as u32);
+    assert_eq!(data.status, OrderItemStatus::Requested);
+}
+
+#[tokio::test]
+async fn get_order_item_return_a_400_when_id_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app.get("/api/v1/admin/order_items/abc").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn update_order_item_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+
+    let body = serde_json::json!({
+        "id": id.to_string(),
+        "quantity": 5,
+        "status": "purchased_in_japan",
+    });
+
+    // Act
+    let response = app.put("/api/v1/admin/order_items", &body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT quantity, status, updated_at FROM order_items where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved order item");
+
+    assert_eq!(data_from_db.quantity, 5);
+    assert_eq!(
+        data_from_db.status,
+        OrderItemStatus::PurchasedInJapan.code()
+    );
+    assert!(data_from_db.updated_at.is_some());
+}
+
+#[tokio::test]
+async fn update_order_item_return_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "id": id.to_string(),
+                "quantity": 0,
+            }),
+            "Quantity is zero",
+        ),
+        (
+            serde_json::json!({
+                "id": "abc",
+                "quantity": 1,
+            }),
+            "Id is invalid",
+        ),
+        (
+            serde_json::json!({
+                "id": id.to_string(),
+                "status": "lost",
+            }),
+            "Status is unknown",
+        ),
+        (
+            serde_json::json!({
+                "id": id.to_string(),
+                "unit_price": "-1",
+            }),
+            "Unit price is negative",
+        ),
+        (
+            serde_json::json!({
+                "id": id.to_string(),
+                "unit_price": "10.001",
+            }),
+            "Unit price is more precise than the currency",
+        ),
+    ];
+
+    for (body, msg) in test_cases {
+        // Act
+        let response = app.put("/api/v1/admin/order_items", &body).await;
+
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when the payload was {msg}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn update_order_item_return_a_409_when_status_transition_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    app.receive_stock(product_id, 1).await;
+    let id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+
+    for status in ["purchased_in_japan", "in_transit", "arrived", "picked_up"] {
+        let body = serde_json::json!({
+            "id": id.to_string(),
+            "status": status,
+        });
+        let response = app.put("/api/v1/admin/order_items", &body).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let test_cases = vec!["requested", "arrived", "picked_up", "cancelled"];
+
+    for status in test_cases {
+        let body = serde_json::json!({
+            "id": id.to_string(),
+            "status": status,
+        });
+
+        // Act
+        let response = app.put("/api/v1/admin/order_items", &body).await;
+
+        // Assert
+        assert_eq!(
+            409,
+            response.status().as_u16(),
+            "The API didn't fail with 409 Conflict when moving from picked_up to {status}",
+        );
+    }
+
+    let data_from_db = sqlx::query!(r#"SELECT status FROM order_items where id=$1"#, id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved order item");
+
+    assert_eq!(data_from_db.status, OrderItemStatus::PickedUp.code());
+}
+
+#[tokio::test]
+async fn order_items_table_rejects_an_unknown_status() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+
+    for status in [-1_i16, 7] {
+        // Act
+        let result = sqlx::query("UPDATE order_items SET status = $1 WHERE id = $2")
+            .bind(status)
+            .bind(id)
+            .execute(&app.db_pool)
+            .await;
+
+        // Assert
+        assert!(
+            result.is_err(),
+            "The database accepted {status} as an order item status"
+        );
+    }
+}
+
+#[tokio::test]
+async fn update_order_item_return_a_404_when_order_item_is_not_exist() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    let body = serde_json::json!({
+        "id": "1",
+        "quantity": 3,
+    });
+
+    // Act
+    let response = app.put("/api/v1/admin/order_items", &body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn update_order_item_return_a_404_when_order_item_is_deleted() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+    let response = app
+        .delete(
+            "/api/v1/admin/order_items",
+            &serde_json::json!({ "id": id }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = serde_json::json!({
+        "id": id.to_string(),
+        "status": "cancelled",
+    });
+
+    // Act
+    let response = app.put("/api/v1/admin/order_items", &body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+
+    let data_from_db = sqlx::query!(r#"SELECT status FROM order_items where id=$1"#, id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved order item");
+
+    assert_eq!(data_from_db.status, OrderItemStatus::Requested.code());
+}
+
+#[tokio::test]
+async fn list_order_item_status_histories_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+
+    for status in ["purchased_in_japan", "in_transit", "arrived"] {
+        let body = serde_json::json!({
+            "id": id.to_string(),
+            "status": status,
+        });
+        let response = app.put("/api/v1/admin/order_items", &body).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Act
+    let uri = format!("/api/v1/admin/order_items/{id}/status_histories");
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<ListOrderItemStatusHistoriesResponse>()
+        .await
+        .expect("Failed to decode json")
+        .data;
+
+    let expected = vec![
+        (None, OrderItemStatus::Requested),
+        (
+            Some(OrderItemStatus::Requested),
+            OrderItemStatus::PurchasedInJapan,
+        ),
+        (
+            Some(OrderItemStatus::PurchasedInJapan),
+            OrderItemStatus::InTransit,
+        ),
+        (Some(OrderItemStatus::InTransit), OrderItemStatus::Arrived),
+    ];
+
+    assert_eq!(data.len(), expected.len());
+    for (history, (previous_status, next_status)) in data.iter().zip(expected) {
+        assert_eq!(history.order_item_id, id.to_string());
+        assert_eq!(history.previous_status, previous_status);
+        assert_eq!(history.next_status, next_status);
+        assert_eq!(history.changed_by, app.test_user.id.to_string());
+        assert_eq!(
+            history.changed_by_username.as_ref(),
+            Some(&app.test_user.username)
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_order_item_status_histories_return_a_404_when_order_item_is_not_exist() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app
+        .get("/api/v1/admin/order_items/1/status_histories")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn update_order_item_statuses_by_ids_works_and_reports_failures() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+
+    let mut in_transit_ids = vec![];
+    for _ in 0..3 {
+        let id = app
+            .create_an_order_item_with(customer_id, product_id, 1)
+            .await;
+        app.change_order_item_status(id, &["purchased_in_japan", "in_transit"])
+            .await;
+        in_transit_ids.push(id);
+    }
+    let cancelled_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    app.change_order_item_status(cancelled_id, &["cancelled"])
+        .await;
+
+    let mut ids = in_transit_ids
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    ids.push(cancelled_id.to_string());
+    ids.push("1".to_string());
+
+    let body = serde_json::json!({
+        "ids": ids,
+        "status": "arrived",
+    });
+
+    // Act
+    let response = app.put("/api/v1/admin/order_items/status", &body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<UpdateOrderItemStatusesResponse>()
+        .await
+        .expect("Failed to decode json")
+        .data;
+    assert_eq!(data.len(), 5);
+
+    for id in &in_transit_ids {
+        let result = data.iter().find(|e| e.id == id.to_string()).unwrap();
+        assert!(result.success);
+        assert_eq!(result.previous_status, Some(OrderItemStatus::InTransit));
+    }
+    let result = data
+        .iter()
+        .find(|e| e.id == cancelled_id.to_string())
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(result.previous_status, Some(OrderItemStatus::Cancelled));
+    assert!(result.error_message.is_some());
+    let result = data.iter().find(|e| e.id == "1").unwrap();
+    assert!(!result.success);
+    assert_eq!(result.previous_status, None);
+
+    let statuses = sqlx::query!(
+        r#"SELECT id, status FROM order_items where customer_id=$1"#,
+        customer_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved order items");
+
+    for row in statuses {
+        if row.id == cancelled_id {
+            assert_eq!(row.status, OrderItemStatus::Cancelled.code());
+        } else {
+            assert_eq!(row.status, OrderItemStatus::Arrived.code());
+        }
+    }
+
+    let histories = sqlx::query!(
+        r#"SELECT count(*) FROM order_item_status_histories where next_status=$1"#,
+        OrderItemStatus::Arrived.code()
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved status histories");
+    assert_eq!(histories.count, Some(3));
+}
+
+#[tokio::test]
+async fn update_order_item_statuses_by_product_and_current_status_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let other_product_id = app.create_a_new_product().await;
+
+    let requested_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    let purchased_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    app.change_order_item_status(purchased_id, &["purchased_in_japan"])
+        .await;
+    let other_id = app
+        .create_an_order_item_with(customer_id, other_product_id, 1)
+        .await;
+    app.change_order_item_status(other_id, &["purchased_in_japan"])
+        .await;
+
+    let body = serde_json::json!({
+        "product_id": product_id,
+        "current_status": "purchased_in_japan",
+        "status": "in_transit",
+    });
+
+    // Act
+    let response = app.put("/api/v1/admin/order_items/status", &body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<UpdateOrderItemStatusesResponse>()
+        .await
+        .expect("Failed to decode json")
+        .data;
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0].id, purchased_id.to_string());
+    assert!(data[0].success);
+
+    for (id, expected) in [
+        (requested_id, OrderItemStatus::Requested),
+        (purchased_id, OrderItemStatus::InTransit),
+        (other_id, OrderItemStatus::PurchasedInJapan),
+    ] {
+        let row = sqlx::query!(r#"SELECT status FROM order_items where id=$1"#, id)
+            .fetch_one(&app.db_pool)
+            .await
+            .expect("Failed to fetch saved order item");
+        assert_eq!(row.status, expected.code());
+    }
+}
+
+#[tokio::test]
+async fn update_order_item_statuses_return_a_400_when_selector_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "status": "arrived" }),
+            "Missing selector",
+        ),
+        (
+            serde_json::json!({ "ids": [], "status": "arrived" }),
+            "Ids are empty",
+        ),
+        (
+            serde_json::json!({ "ids": ["1"], "product_id": 1, "status": "arrived" }),
+            "Ids with a filter",
+        ),
+        (
+            serde_json::json!({ "ids": ["abc"], "status": "arrived" }),
+            "Id is invalid",
+        ),
+    ];
+
+    for (body, msg) in test_cases {
+        // Act
+        let response = app.put("/api/v1/admin/order_items/status", &body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when the payload was {msg}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn delete_order_item_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+    let request = serde_json::json!({
+        "id": id,
+    });
+
+    // Act
+    let response = app.delete("/api/v1/admin/order_items", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+
+    let data_from_db = sqlx::query!(r#"SELECT deleted_at FROM order_items where id=$1"#, id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved order item");
+
+    assert!(data_from_db.deleted_at.is_some());
+}
+
+#[tokio::test]
+async fn list_order_items_works_with_filter() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    let mut ids = vec![];
+    for _ in 0..5 {
+        ids.push(app.create_a_new_order_item().await);
+    }
+
+    // Act
+    let response = app.get("/api/v1/admin/order_items").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<ListOrderItemsResponse>()
+        .await
+        .expect("Failed to decode json");
+    assert_eq!(data.data.len(), 5);
+
+    let keyword = format!(r#"{{ "id": {} }}"#, ids[0]);
+    let keyword = base64::engine::general_purpose::STANDARD.encode(&keyword);
+    let uri = format!("/api/v1/admin/order_items?keyword={keyword}");
+    let response = app.get(&uri).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<ListOrderItemsResponse>()
+        .await
+        .expect("Failed to decode json");
+    assert_eq!(data.data.len(), 1);
+    assert_eq!(data.data[0].id, ids[0].to_string());
+}
+
+#[tokio::test]
+async fn list_order_items_works_with_status_customer_product_and_deleted_filters() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+
+    let mut ids = vec![];
+    for _ in 0..4 {
+        let body = serde_json::json!({
+            "customer_id": customer_id,
+            "product_id": product_id,
+            "quantity": 1,
+        });
+        let response = app.post("/api/v1/admin/order_items", &body).await;
+        assert_eq!(response.status().as_u16(), 200);
+        let response: CreateOrderItemResponse = response.json().await.unwrap();
+        ids.push(response.id);
+    }
+    // Another customer and product which must be filtered out.
+    let _ = app.create_a_new_order_item().await;
+
+    let body = serde_json::json!({ "id": ids[0].to_string(), "status": "purchased_in_japan" });
+    let response = app.put("/api/v1/admin/order_items", &body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = serde_json::json!({ "id": ids[1].to_string(), "status": "cancelled" });
+    let response = app.put("/api/v1/admin/order_items", &body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app
+        .delete(
+            "/api/v1/admin/order_items",
+            &serde_json::json!({ "id": ids[3] }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let test_cases = vec![
+        (serde_json::json!({}), 4),
+        (serde_json::json!({ "customer_id": customer_id }), 3),
+        (serde_json::json!({ "product_id": product_id }), 3),
+        (
+            serde_json::json!({ "product_id": product_id, "statuses": ["requested"] }),
+            1,
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "statuses": ["purchased_in_japan", "cancelled"]
+            }),
+            2,
+        ),
+        (
+            serde_json::json!({ "customer_id": customer_id, "deleted": true }),
+            1,
+        ),
+        (
+            serde_json::json!({ "customer_id": customer_id, "deleted": false }),
+            3,
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "statuses": ["requested"],
+                "deleted": false
+            }),
+            1,
+        ),
+    ];
+
+    for (keyword, expected) in test_cases {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(keyword.to_string());
+        let uri = format!("/api/v1/admin/order_items?keyword={encoded}");
+
+        // Act
+        let response = app.get(&uri).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+        let data = response
+            .json::<ListOrderItemsResponse>()
+            .await
+            .expect("Failed to decode json");
+        assert_eq!(
+            data.data.len(),
+            expected,
+            "The API returned an unexpected number of order items when the keyword was {keyword}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_order_items_works_with_created_at_range() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_order_item().await;
+
+    sqlx::query!(
+        r#"UPDATE order_items SET created_at = now() - interval '10 days' where id=$1"#,
+        id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to update saved order item");
+
+    for _ in 0..2 {
+        let _ = app.create_a_new_order_item().await;
+    }
+
+    let week_ago = chrono::Utc::now() - chrono::Duration::days(7);
+    let test_cases = vec![
+        (serde_json::json!({ "created_from": week_ago }), 2),
+        (serde_json::json!({ "created_to": week_ago }), 1),
+        (
+            serde_json::json!({
+                "created_from": week_ago - chrono::Duration::days(7),
+                "created_to": week_ago,
+            }),
+            1,
+        ),
+    ];
+
+    for (keyword, expected) in test_cases {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(keyword.to_string());
+        let uri = format!("/api/v1/admin/order_items?keyword={encoded}");
+
+        // Act
+        let response = app.get(&uri).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+        let data = response
+            .json::<ListOrderItemsResponse>()
+            .await
+            .expect("Failed to decode json");
+        assert_eq!(
+            data.data.len(),
+            expected,
+            "The API returned an unexpected number of order items when the keyword was {keyword}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_order_items_with_page_and_page_size_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    for _ in 0..7 {
+        let _ = app.create_a_new_order_item().await;
+    }
+
+    let test_cases = vec![(0, 5, 5), (1, 5, 2), (2, 5, 0)];
+
+    for (page, page_size, expected) in test_cases {
+        let uri = format!("/api/v1/admin/order_items?page={page}&page_size={page_size}");
+
+        // Act
+        let response = app.get(&uri).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+        let data = response
+            .json::<ListOrderItemsResponse>()
+            .await
+            .expect("Failed to decode json");
+        assert_eq!(data.data.len(), expected);
+    }
+}
+
+#[tokio::test]
+async fn list_order_items_failed_when_keyword_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app
+        .get("/api/v1/admin/order_items?keyword=random_string")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,296 @@
+use base64::Engine;
+use rust_decimal::Decimal;
+
+use japonfou::routes::{CreateOrderResponse, ListOrdersResponse, OrderItemStatus, OrderJson};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn create_order_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let first_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    let second_id = app
+        .create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "note": "pick up on Friday",
+        "order_item_ids": [first_id.to_string(), second_id.to_string()],
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/orders", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: Result<CreateOrderResponse, reqwest::Error> = response.json().await;
+    assert!(response.is_ok());
+    let id = response.unwrap().id;
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT customer_id, created_by, note FROM orders where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved order");
+
+    assert_eq!(data_from_db.customer_id, customer_id);
+    assert_eq!(data_from_db.created_by, app.test_user.id);
+    assert_eq!(data_from_db.note.as_deref(), Some("pick up on Friday"));
+
+    let attached = sqlx::query!(r#"SELECT count(*) FROM order_items where order_id=$1"#, id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count attached order items");
+
+    assert_eq!(attached.count, Some(2));
+}
+
+#[tokio::test]
+async fn create_order_returns_a_422_when_order_item_is_not_attachable() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let other_order_item_id = app.create_a_new_order_item().await;
+    let product_id = app.create_a_new_product().await;
+    let attached_order_item_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    app.create_an_order_with(customer_id, &[attached_order_item_id])
+        .await;
+
+    let test_cases = vec![
+        (other_order_item_id, "order item of another customer"),
+        (attached_order_item_id, "order item of another order"),
+        (1, "missing order item"),
+    ];
+
+    for (order_item_id, description) in test_cases {
+        let request = serde_json::json!({
+            "customer_id": customer_id,
+            "order_item_ids": [order_item_id.to_string()],
+        });
+
+        // Act
+        let response = app.post("/api/v1/admin/orders", &request).await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "The API did not fail with 422 when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_order_returns_a_422_when_customer_is_missing() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    let request = serde_json::json!({ "customer_id": 1 });
+
+    // Act
+    let response = app.post("/api/v1/admin/orders", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn get_order_returns_order_items_totals_and_status() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let first_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    let second_id = app
+        .create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+    let cancelled_id = app
+        .create_an_order_item_with(customer_id, product_id, 3)
+        .await;
+    app.change_order_item_status(first_id, &["purchased_in_japan", "in_transit"])
+        .await;
+    app.change_order_item_status(second_id, &["purchased_in_japan"])
+        .await;
+    app.change_order_item_status(cancelled_id, &["cancelled"])
+        .await;
+    let id = app
+        .create_an_order_with(customer_id, &[first_id, second_id, cancelled_id])
+        .await;
+
+    // Act
+    let response = app.get(&format!("/api/v1/admin/orders/{id}")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let order: OrderJson = response.json().await.expect("Failed to parse the order");
+    assert_eq!(order.id, id.to_string());
+    assert_eq!(order.customer.id, customer_id.to_string());
+    assert_eq!(order.created_by, app.test_user.id.to_string());
+    assert_eq!(order.order_items.len(), 3);
+    assert_eq!(order.status, Some(OrderItemStatus::PurchasedInJapan));
+    assert_eq!(order.totals.len(), 1);
+    assert_eq!(order.totals[0].ordered.amount, Decimal::from(60));
+}
+
+#[tokio::test]
+async fn get_order_returns_a_404_when_order_is_missing() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app.get("/api/v1/admin/orders/1").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn create_order_item_attaches_it_to_an_order() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let order_id = app.create_an_order_with(customer_id, &[]).await;
+    let other_customer_id = app.create_a_new_customer().await;
+
+    let request = serde_json::json!({
+        "order_id": order_id.to_string(),
+        "customer_id": customer_id,
+        "product_id": product_id,
+        "quantity": 1,
+    });
+    let mismatch_request = serde_json::json!({
+        "order_id": order_id.to_string(),
+        "customer_id": other_customer_id,
+        "product_id": product_id,
+        "quantity": 1,
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/order_items", &request).await;
+    let mismatch_response = app
+        .post("/api/v1/admin/order_items", &mismatch_request)
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(mismatch_response.status().as_u16(), 422);
+
+    let order: OrderJson = app
+        .get(&format!("/api/v1/admin/orders/{order_id}"))
+        .await
+        .json()
+        .await
+        .expect("Failed to parse the order");
+    assert_eq!(order.order_items.len(), 1);
+    assert_eq!(order.status, Some(OrderItemStatus::Requested));
+}
+
+#[tokio::test]
+async fn list_orders_filters_by_customer() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let other_customer_id = app.create_a_new_customer().await;
+    let first_id = app.create_an_order_with(customer_id, &[]).await;
+    let second_id = app.create_an_order_with(customer_id, &[]).await;
+    app.create_an_order_with(other_customer_id, &[]).await;
+
+    let keyword = base64::engine::general_purpose::STANDARD.encode(
+        serde_json::json!({
+            "customer_id": customer_id,
+        })
+        .to_string(),
+    );
+
+    // Act
+    let response = app
+        .get(&format!("/api/v1/admin/orders?keyword={keyword}"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: ListOrdersResponse = response.json().await.expect("Failed to parse orders");
+    let ids = response
+        .data
+        .iter()
+        .map(|e| e.id.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![second_id.to_string(), first_id.to_string()]);
+    assert!(response.data.iter().all(|e| e.status.is_none()));
+}
+
+#[tokio::test]
+async fn list_orders_returns_the_totals_of_each_order() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let first_item_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+    let second_item_id = app
+        .create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+    let first_id = app
+        .create_an_order_with(customer_id, &[first_item_id])
+        .await;
+    let second_id = app
+        .create_an_order_with(customer_id, &[second_item_id])
+        .await;
+
+    // Act
+    let response = app.get("/api/v1/admin/orders").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: ListOrdersResponse = response.json().await.expect("Failed to parse orders");
+    let totals = response
+        .data
+        .iter()
+        .map(|e| {
+            let ordered = e
+                .totals
+                .iter()
+                .map(|e| e.ordered.amount)
+                .collect::<Vec<_>>();
+            (e.id.clone(), ordered)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        totals,
+        vec![
+            (second_id.to_string(), vec![Decimal::from(40)]),
+            (first_id.to_string(), vec![Decimal::from(20)]),
+        ]
+    );
+}
@@ -0,0 +1,350 @@
+use base64::Engine;
+use rust_decimal::Decimal;
+
+use japonfou::routes::{
+    CreatePaymentResponse, CustomerBalancesResponse, ListPaymentsResponse, PaymentMethod,
+};
+
+use crate::helpers::{spawn_app, AuthTestApp};
+
+async fn record_a_payment(app: &AuthTestApp, customer_id: i64, amount: &str, currency: i16) -> i64 {
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "amount": amount,
+        "currency": currency,
+        "method": "mpay",
+    });
+
+    let response = app.post("/api/v1/admin/payments", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response: CreatePaymentResponse =
+        response.json().await.expect("Failed to parse the payment");
+    response.id
+}
+
+#[tokio::test]
+async fn create_payment_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let order_item_id = app
+        .create_an_order_item_with(customer_id, product_id, 1)
+        .await;
+
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "amount": "15.50",
+        "currency": 344,
+        "method": "bank_transfer",
+        "note": "deposit",
+        "order_item_ids": [order_item_id.to_string()],
+    });
+
+    // Act
+    let response = app.post("/api/v1/admin/payments", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: Result<CreatePaymentResponse, reqwest::Error> = response.json().await;
+    assert!(response.is_ok());
+    let id = response.unwrap().id;
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT customer_id, amount, currency, method, recorded_by FROM payments where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved payment");
+
+    assert_eq!(data_from_db.customer_id, customer_id);
+    assert_eq!(data_from_db.amount, Decimal::new(1550, 2));
+    assert_eq!(data_from_db.currency, 344);
+    assert_eq!(data_from_db.method, PaymentMethod::BankTransfer.code());
+    assert_eq!(data_from_db.recorded_by, app.test_user.id);
+
+    let linked = sqlx::query!(
+        r#"SELECT order_item_id FROM payment_order_items where payment_id=$1"#,
+        id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch linked order items");
+
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].order_item_id, order_item_id);
+}
+
+#[tokio::test]
+async fn create_payment_returns_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "0",
+                "currency": 344,
+                "method": "cash",
+            }),
+            "zero amount",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "-10",
+                "currency": 344,
+                "method": "cash",
+            }),
+            "negative amount",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "10.001",
+                "currency": 344,
+                "method": "cash",
+            }),
+            "too many decimal places",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "10000000000",
+                "currency": 344,
+                "method": "cash",
+            }),
+            "too large amount",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "100.5",
+                "currency": 392,
+                "method": "cash",
+            }),
+            "decimals in JPY",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "10",
+                "currency": 344,
+                "method": "cheque",
+            }),
+            "unknown method",
+        ),
+    ];
+
+    for (request, description) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/payments", &request).await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_payment_returns_a_422_when_reference_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let other_order_item_id = app.create_a_new_order_item().await;
+
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "customer_id": 1,
+                "amount": "10",
+                "currency": 344,
+                "method": "cash",
+            }),
+            "missing customer",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "10",
+                "currency": 1,
+                "method": "cash",
+            }),
+            "unknown currency",
+        ),
+        (
+            serde_json::json!({
+                "customer_id": customer_id,
+                "amount": "10",
+                "currency": 344,
+                "method": "cash",
+                "order_item_ids": [other_order_item_id.to_string()],
+            }),
+            "order item of another customer",
+        ),
+    ];
+
+    for (request, description) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/payments", &request).await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "The API did not fail with 422 when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn void_payment_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let id = record_a_payment(&app, customer_id, "10", 344).await;
+
+    let request = serde_json::json!({ "id": id.to_string() });
+
+    // Act
+    let response = app.put("/api/v1/admin/payments/void", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+
+    let data_from_db = sqlx::query!(
+        r#"SELECT voided_at, voided_by FROM payments where id=$1"#,
+        id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved payment");
+
+    assert!(data_from_db.voided_at.is_some());
+    assert_eq!(data_from_db.voided_by, Some(app.test_user.id));
+
+    let response = app.put("/api/v1/admin/payments/void", &request).await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    let request = serde_json::json!({ "id": "1" });
+    let response = app.put("/api/v1/admin/payments/void", &request).await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn list_payments_filters_by_customer_and_voided() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let other_customer_id = app.create_a_new_customer().await;
+    let first_id = record_a_payment(&app, customer_id, "10", 344).await;
+    let voided_id = record_a_payment(&app, customer_id, "20", 344).await;
+    let second_id = record_a_payment(&app, customer_id, "30", 446).await;
+    record_a_payment(&app, other_customer_id, "40", 344).await;
+
+    let request = serde_json::json!({ "id": voided_id.to_string() });
+    let response = app.put("/api/v1/admin/payments/void", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let keyword = base64::engine::general_purpose::STANDARD.encode(
+        serde_json::json!({
+            "customer_id": customer_id,
+            "voided": false,
+        })
+        .to_string(),
+    );
+
+    // Act
+    let response = app
+        .get(&format!("/api/v1/admin/payments?keyword={keyword}"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: ListPaymentsResponse = response.json().await.expect("Failed to parse payments");
+    let ids = response
+        .data
+        .iter()
+        .map(|e| e.id.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![second_id.to_string(), first_id.to_string()]);
+    assert_eq!(response.data[0].method, PaymentMethod::MPay);
+}
+
+#[tokio::test]
+async fn customer_balances_net_payments_against_order_items() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    app.create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+    let cancelled_id = app
+        .create_an_order_item_with(customer_id, product_id, 5)
+        .await;
+    app.change_order_item_status(cancelled_id, &["cancelled"])
+        .await;
+    record_a_payment(&app, customer_id, "15", 344).await;
+    let voided_id = record_a_payment(&app, customer_id, "100", 344).await;
+    record_a_payment(&app, customer_id, "50", 446).await;
+
+    let request = serde_json::json!({ "id": voided_id.to_string() });
+    let response = app.put("/api/v1/admin/payments/void", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act
+    let response = app
+        .get(&format!("/api/v1/admin/customers/{customer_id}/balances"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: CustomerBalancesResponse =
+        response.json().await.expect("Failed to parse balances");
+    assert_eq!(response.data.len(), 2);
+
+    let hkd = &response.data[0];
+    assert_eq!(hkd.currency, 344);
+    assert_eq!(hkd.charged.amount, Decimal::new(40, 0));
+    assert_eq!(hkd.paid.amount, Decimal::new(15, 0));
+    assert_eq!(hkd.balance.amount, Decimal::new(-25, 0));
+
+    let mop = &response.data[1];
+    assert_eq!(mop.currency, 446);
+    assert_eq!(mop.charged.amount, Decimal::ZERO);
+    assert_eq!(mop.balance.amount, Decimal::new(50, 0));
+}
+
+#[tokio::test]
+async fn customer_balances_returns_a_404_when_customer_is_missing() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app.get("/api/v1/admin/customers/1/balances").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -0,0 +1,337 @@
+use crate::helpers::{spawn_app, AuthTestApp};
+use japonfou::routes::{
+    CreatePricingRuleResponse, CreateProductResponse, ListPricingRulesResponse,
+    ListProductPriceHistoriesResponse, PricingRuleJson, RepriceProductsResponse,
+};
+use rust_decimal::Decimal;
+
+async fn create_a_pricing_rule(app: &AuthTestApp, exchange_rate: &str) -> i64 {
+    let request = serde_json::json!({
+        "name": "JPY to HKD",
+        "currency": 344,
+        "exchange_rate": exchange_rate,
+        "markup_rate": "0.25",
+        "handling_fee": "5",
+    });
+
+    let response = app.post("/api/v1/admin/pricing_rules", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<CreatePricingRuleResponse>()
+        .await
+        .expect("Failed to parse the pricing rule")
+        .id
+}
+
+async fn create_a_priced_product(app: &AuthTestApp, pricing_rule_id: i64) -> i64 {
+    let request = serde_json::json!({
+        "name": "Pocky",
+        "cost_price": "1000",
+        "cost_tax_rate": "0.10",
+        "pricing_rule_id": pricing_rule_id.to_string(),
+    });
+
+    let response = app.post("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<CreateProductResponse>()
+        .await
+        .expect("Failed to parse the product")
+        .id
+}
+
+#[tokio::test]
+async fn create_pricing_rule_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let id = create_a_pricing_rule(&app, "0.052").await;
+    let pricing_rule = app
+        .get(&format!("/api/v1/admin/pricing_rules/{id}"))
+        .await
+        .json::<PricingRuleJson>()
+        .await
+        .expect("Failed to parse the pricing rule");
+    let pricing_rules = app
+        .get("/api/v1/admin/pricing_rules")
+        .await
+        .json::<ListPricingRulesResponse>()
+        .await
+        .expect("Failed to parse the pricing rules");
+
+    // Assert
+    assert_eq!(pricing_rule.currency, 344);
+    assert_eq!(pricing_rule.exchange_rate, Decimal::new(52, 3));
+    assert_eq!(pricing_rule.markup_rate, Decimal::new(25, 2));
+    assert_eq!(pricing_rule.handling_fee, Decimal::new(5, 0));
+    assert_eq!(pricing_rules.data.len(), 1);
+}
+
+#[tokio::test]
+async fn create_pricing_rule_return_a_400_or_422_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "name": "rule", "currency": 344, "exchange_rate": "0", "markup_rate": "0.25"
+            }),
+            400,
+            "zero exchange rate",
+        ),
+        (
+            serde_json::json!({
+                "name": "rule", "currency": 344, "exchange_rate": "0.052", "markup_rate": "-0.1"
+            }),
+            400,
+            "negative markup rate",
+        ),
+        (
+            serde_json::json!({
+                "name": " ", "currency": 344, "exchange_rate": "0.052", "markup_rate": "0.25"
+            }),
+            400,
+            "empty name",
+        ),
+        (
+            serde_json::json!({
+                "name": "rule", "currency": 344, "exchange_rate": "0.052", "markup_rate": "0.25",
+                "handling_fee": "79228162514264337593543950335"
+            }),
+            400,
+            "too large handling fee",
+        ),
+        (
+            serde_json::json!({
+                "name": "rule", "currency": 344, "exchange_rate": "1000000", "markup_rate": "0.25"
+            }),
+            400,
+            "too large exchange rate",
+        ),
+        (
+            serde_json::json!({
+                "name": "rule", "currency": 999, "exchange_rate": "0.052", "markup_rate": "0.25"
+            }),
+            422,
+            "unknown currency",
+        ),
+    ];
+
+    for (body, status, msg) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/pricing_rules", &body).await;
+
+        // Assert
+        assert_eq!(
+            status,
+            response.status().as_u16(),
+            "The API didn't fail with {status} when the payload was {msg}",
+        );
+    }
+}
+
+#[tokio::test]
+async fn product_price_is_computed_by_its_pricing_rule() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let pricing_rule_id = create_a_pricing_rule(&app, "0.052").await;
+
+    // Act
+    let product_id = create_a_priced_product(&app, pricing_rule_id).await;
+    let product = app.get_product(product_id).await;
+
+    // Assert
+    // 1000 * 1.10 * 0.052 = 57.20, 57.20 * 1.25 + 5 = 76.50
+    assert_eq!(product.currency, 344);
+    assert_eq!(product.cost, Some(Decimal::new(5720, 2)));
+    assert_eq!(product.price.amount, Decimal::new(7650, 2));
+    assert_eq!(product.margin, Some(Decimal::new(1930, 2)));
+    assert_eq!(product.pricing_rule_id, Some(pricing_rule_id.to_string()));
+}
+
+#[tokio::test]
+async fn update_cost_price_recomputes_the_price() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let pricing_rule_id = create_a_pricing_rule(&app, "0.052").await;
+    let product_id = create_a_priced_product(&app, pricing_rule_id).await;
+
+    // Act
+    let response = app
+        .put(
+            "/api/v1/admin/products",
+            &serde_json::json!({ "id": product_id.to_string(), "cost_price": "2000" }),
+        )
+        .await;
+    let manual_price = app
+        .put(
+            "/api/v1/admin/products",
+            &serde_json::json!({ "id": product_id.to_string(), "price": "10" }),
+        )
+        .await;
+    let product = app.get_product(product_id).await;
+
+    // Assert
+    // 2000 * 1.10 * 0.052 = 114.40, 114.40 * 1.25 + 5 = 148.00
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(manual_price.status().as_u16(), 400);
+    assert_eq!(product.cost, Some(Decimal::new(11440, 2)));
+    assert_eq!(product.price.amount, Decimal::new(14800, 2));
+}
+
+#[tokio::test]
+async fn product_pricing_rule_can_be_cleared() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let pricing_rule_id = create_a_pricing_rule(&app, "0.052").await;
+    let product_id = create_a_priced_product(&app, pricing_rule_id).await;
+
+    // Act
+    let response = app
+        .put(
+            "/api/v1/admin/products",
+            &serde_json::json!({
+                "id": product_id.to_string(),
+                "pricing_rule_id": null,
+                "price": "10",
+            }),
+        )
+        .await;
+    let product = app.get_product(product_id).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(product.pricing_rule_id, None);
+    assert_eq!(product.cost, None);
+    assert_eq!(product.price.amount, Decimal::new(10, 0));
+}
+
+#[tokio::test]
+async fn reprice_products_applies_the_updated_pricing_rule() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let pricing_rule_id = create_a_pricing_rule(&app, "0.052").await;
+    let product_id = create_a_priced_product(&app, pricing_rule_id).await;
+
+    let response = app
+        .put(
+            "/api/v1/admin/pricing_rules",
+            &serde_json::json!({ "id": pricing_rule_id.to_string(), "exchange_rate": "0.05" }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let unchanged = app.get_product(product_id).await;
+
+    // Act
+    let response = app
+        .post(
+            "/api/v1/admin/products/reprice",
+            &serde_json::json!({ "pricing_rule_id": pricing_rule_id.to_string() }),
+        )
+        .await
+        .json::<RepriceProductsResponse>()
+        .await
+        .expect("Failed to parse the repriced products");
+    let product = app.get_product(product_id).await;
+
+    // Assert
+    // 1000 * 1.10 * 0.05 = 55.00, 55.00 * 1.25 + 5 = 73.75
+    assert_eq!(unchanged.price.amount, Decimal::new(7650, 2));
+    assert_eq!(response.data.len(), 1);
+    assert_eq!(
+        response.data[0].previous_price.amount,
+        Decimal::new(7650, 2)
+    );
+    assert_eq!(response.data[0].price.amount, Decimal::new(7375, 2));
+    assert_eq!(product.cost, Some(Decimal::new(5500, 2)));
+    assert_eq!(product.price.amount, Decimal::new(7375, 2));
+
+    let histories = app
+        .get(&format!(
+            "/api/v1/admin/products/{product_id}/price_histories"
+        ))
+        .await
+        .json::<ListProductPriceHistoriesResponse>()
+        .await
+        .expect("Failed to parse the price histories")
+        .data;
+    assert_eq!(histories.len(), 1);
+    assert_eq!(histories[0].previous_price.amount, Decimal::new(7650, 2));
+    assert_eq!(histories[0].price.amount, Decimal::new(7375, 2));
+}
+
+#[tokio::test]
+async fn create_priced_product_return_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let pricing_rule_id = create_a_pricing_rule(&app, "0.052").await.to_string();
+    let expensive_rule_id = create_a_pricing_rule(&app, "999999").await.to_string();
+    let test_cases = vec![
+        (
+            serde_json::json!({ "name": "Pocky", "pricing_rule_id": pricing_rule_id }),
+            "missing cost price",
+        ),
+        (
+            serde_json::json!({
+                "name": "Pocky", "cost_price": "1000", "price": "10",
+                "pricing_rule_id": pricing_rule_id
+            }),
+            "price given with a pricing rule",
+        ),
+        (
+            serde_json::json!({
+                "name": "Pocky", "cost_price": "1000", "currency": 446,
+                "pricing_rule_id": pricing_rule_id
+            }),
+            "currency mismatch",
+        ),
+        (
+            serde_json::json!({
+                "name": "Pocky", "cost_price": "1000", "cost_tax_rate": "1.5",
+                "pricing_rule_id": pricing_rule_id
+            }),
+            "tax rate greater than 1",
+        ),
+        (
+            serde_json::json!({
+                "name": "Pocky", "cost_price": "79228162514264337593543950335",
+                "pricing_rule_id": pricing_rule_id
+            }),
+            "too large cost price",
+        ),
+        (
+            serde_json::json!({
+                "name": "Pocky", "cost_price": "9999999999",
+                "pricing_rule_id": expensive_rule_id
+            }),
+            "too large computed price",
+        ),
+    ];
+
+    for (body, msg) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/products", &body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API didn't fail with 400 Bad Request when the payload was {msg}",
+        );
+    }
+}
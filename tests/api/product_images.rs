@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::header::{CONTENT_TYPE, ETAG};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::put;
+use axum::Router;
+
+use japonfou::configuration::StorageSettings;
+use japonfou::routes::CreateProductImageResponse;
+use japonfou::storage::get_blob_storage;
+use secrecy::Secret;
+
+use crate::helpers::{spawn_app, AuthTestApp};
+
+fn a_png_image(width: u32, height: u32) -> Vec<u8> {
+    let mut content = vec![];
+    image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]))
+        .write_to(&mut Cursor::new(&mut content), image::ImageFormat::Png)
+        .expect("Failed to encode the image");
+    content
+}
+
+async fn upload_image(app: &AuthTestApp, product_id: i64, content: Vec<u8>) -> reqwest::Response {
+    let part = reqwest::multipart::Part::bytes(content).file_name("photo.png");
+    let form = reqwest::multipart::Form::new().part("image", part);
+
+    app.post_multipart(&format!("/api/v1/admin/products/{product_id}/images"), form)
+        .await
+}
+
+#[tokio::test]
+async fn upload_product_image_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+
+    // Act
+    let response = upload_image(&app, product_id, a_png_image(800, 400)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let id = response
+        .json::<CreateProductImageResponse>()
+        .await
+        .expect("Failed to parse the image")
+        .id;
+
+    let product = app.get_product(product_id).await;
+    assert_eq!(product.images.len(), 1);
+    let image = &product.images[0];
+    assert_eq!(image.id, id.to_string());
+    assert_eq!(image.content_type, "image/png");
+    assert_eq!((image.width, image.height), (800, 400));
+
+    let original = app.api_client.get(&image.url).send().await.unwrap();
+    assert_eq!(original.status().as_u16(), 200);
+
+    let thumbnail = app
+        .api_client
+        .get(&image.thumbnail_url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(thumbnail.status().as_u16(), 200);
+    let thumbnail = image::load_from_memory(&thumbnail.bytes().await.unwrap())
+        .expect("Failed to decode the thumbnail");
+    assert_eq!((thumbnail.width(), thumbnail.height()), (320, 160));
+}
+
+#[tokio::test]
+async fn upload_product_image_returns_a_400_when_file_is_not_an_image() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+
+    // Act
+    let response = upload_image(&app, product_id, b"not an image".to_vec()).await;
+    let missing_product = upload_image(&app, 1, a_png_image(10, 10)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(missing_product.status().as_u16(), 404);
+    assert!(app.get_product(product_id).await.images.is_empty());
+}
+
+#[tokio::test]
+async fn upload_product_image_returns_a_400_when_image_is_too_large() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+
+    // Act
+    let response = upload_image(&app, product_id, a_png_image(8001, 1)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    assert!(app.get_product(product_id).await.images.is_empty());
+}
+
+#[tokio::test]
+async fn delete_product_image_removes_it_from_the_product() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+    let id = upload_image(&app, product_id, a_png_image(20, 20))
+        .await
+        .json::<CreateProductImageResponse>()
+        .await
+        .expect("Failed to parse the image")
+        .id;
+    let url = app.get_product(product_id).await.images[0].url.clone();
+
+    // Act
+    let response = app
+        .delete(
+            "/api/v1/admin/products/images",
+            &serde_json::json!({ "id": id.to_string() }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(app.get_product(product_id).await.images.is_empty());
+    let original = app.api_client.get(&url).send().await.unwrap();
+    assert_eq!(original.status().as_u16(), 404);
+}
+
+type S3Objects = Arc<Mutex<HashMap<String, (String, Vec<u8>)>>>;
+
+/// Serve a bucket which keeps the objects in memory, it only speaks the part of the S3 API used by
+/// the storage and doesn't check the signatures.
+async fn spawn_s3_bucket(bucket: &str) -> (String, S3Objects) {
+    async fn put_object(
+        State(objects): State<S3Objects>,
+        UrlPath(key): UrlPath<String>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> impl IntoResponse {
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|e| e.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        objects
+            .lock()
+            .unwrap()
+            .insert(key, (content_type, body.to_vec()));
+        [(ETAG, "\"etag\"")]
+    }
+
+    async fn delete_object(
+        State(objects): State<S3Objects>,
+        UrlPath(key): UrlPath<String>,
+    ) -> StatusCode {
+        objects.lock().unwrap().remove(&key);
+        StatusCode::NO_CONTENT
+    }
+
+    let objects = S3Objects::default();
+    let router = Router::new()
+        .route(
+            &format!("/{bucket}/*key"),
+            put(put_object).delete(delete_object),
+        )
+        .with_state(objects.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind a random port");
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    (endpoint, objects)
+}
+
+#[tokio::test]
+async fn s3_blob_storage_works() {
+    // Arrange
+    let (endpoint, objects) = spawn_s3_bucket("japonfou").await;
+    let storage = get_blob_storage(&StorageSettings::S3 {
+        endpoint: endpoint.clone(),
+        region: "us-east-1".to_string(),
+        bucket: "japonfou".to_string(),
+        access_key_id: "access".to_string(),
+        secret_access_key: Secret::new("secret".to_string()),
+        public_url: "https://cdn.example.com/".to_string(),
+    });
+    let content = a_png_image(10, 10);
+
+    // Act
+    storage
+        .put("products/1/2.png", "image/png", content.clone())
+        .await
+        .expect("Failed to upload the file");
+    let uploaded = objects.lock().unwrap().get("products/1/2.png").cloned();
+    storage
+        .delete("products/1/2.png")
+        .await
+        .expect("Failed to delete the file");
+
+    // Assert
+    assert_eq!(uploaded, Some(("image/png".to_string(), content)));
+    assert!(objects.lock().unwrap().is_empty());
+    assert_eq!(
+        storage.url("products/1/2.png"),
+        "https://cdn.example.com/products/1/2.png"
+    );
+}
+
+/// Run it against a local MinIO, e.g.
+/// `docker run -p 9000:9000 minio/minio server /data` with a `japonfou` bucket.
+#[tokio::test]
+#[ignore]
+async fn s3_blob_storage_works_with_minio() {
+    let endpoint =
+        std::env::var("MINIO_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:9000".to_string());
+    let storage = get_blob_storage(&StorageSettings::S3 {
+        endpoint: endpoint.clone(),
+        region: "us-east-1".to_string(),
+        bucket: "japonfou".to_string(),
+        access_key_id: "minioadmin".to_string(),
+        secret_access_key: Secret::new("minioadmin".to_string()),
+        public_url: format!("{endpoint}/japonfou"),
+    });
+
+    let key = format!("tests/{}.png", uuid::Uuid::new_v4());
+    storage
+        .put(&key, "image/png", a_png_image(10, 10))
+        .await
+        .expect("Failed to upload the file");
+    storage
+        .delete(&key)
+        .await
+        .expect("Failed to delete the file");
+
+    assert_eq!(storage.url(&key), format!("{endpoint}/japonfou/{key}"));
+}
@@ -1,9 +1,14 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, AuthTestApp};
 use base64::Engine;
 use fake::faker::name::en::Name;
 use fake::Fake;
-use japonfou::routes::{CreateProductResponse, ListProductsResponse, ProductJson};
+use japonfou::routes::{
+    CreateOrderItemResponse, CreateProductResponse, CreateProductVariantResponse,
+    ListProductPriceHistoriesResponse, ListProductsResponse, ListStockAdjustmentsResponse,
+    OrderItemJson, ProductJson, ReindexSearchTextsResponse, StockAdjustmentKind,
+};
 use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 
 #[tokio::test]
 async fn create_product_works() {
@@ -66,6 +71,14 @@ async fn create_new_product_return_a_400_when_data_is_invalid() {
             }),
             "Missing currency",
         ),
+        (
+            serde_json::json!({
+                "name": "product",
+                "currency": 9999,
+                "price": 10.0
+            }),
+            "Unknown currency",
+        ),
         (
             serde_json::json!({
                 "name": "product",
@@ -73,6 +86,38 @@ async fn create_new_product_return_a_400_when_data_is_invalid() {
             }),
             "Missing price",
         ),
+        (
+            serde_json::json!({
+                "name": "product",
+                "currency": 344,
+                "price": "-10"
+            }),
+            "Negative price",
+        ),
+        (
+            serde_json::json!({
+                "name": "product",
+                "currency": 344,
+                "price": "79228162514264337593543950335"
+            }),
+            "Too large price",
+        ),
+        (
+            serde_json::json!({
+                "name": "product",
+                "currency": 344,
+                "price": "10.001"
+            }),
+            "Price is more precise than the currency",
+        ),
+        (
+            serde_json::json!({
+                "name": "product",
+                "currency": 392,
+                "price": "19.5"
+            }),
+            "Price has decimals in JPY",
+        ),
     ];
 
     for (body, msg) in test_cases {
@@ -87,6 +132,35 @@ async fn create_new_product_return_a_400_when_data_is_invalid() {
     }
 }
 
+#[tokio::test]
+async fn product_prices_are_exact_decimal_strings() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let request = serde_json::json!({
+        "name": "Pocky",
+        "currency": 344,
+        "price": "19.99",
+    });
+    let response = app.post("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let id = response
+        .json::<CreateProductResponse>()
+        .await
+        .expect("Failed to parse the product")
+        .id;
+
+    // Act
+    let response = app.get(&format!("/api/v1/admin/products/{id}")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let product: serde_json::Value = response.json().await.expect("Failed to parse the product");
+    assert_eq!(product["price"], "19.99");
+    assert_eq!(product["currency"], 344);
+}
+
 #[tokio::test]
 async fn get_product_works() {
     // Arrange
@@ -102,16 +176,21 @@ async fn get_product_works() {
     assert_eq!(response.status().as_u16(), 200);
     let data: ProductJson = response.json().await.expect("Failed to decode json");
 
-    let data_from_db = sqlx::query_as::<_, ProductJson>(r#"SELECT * FROM products where id=$1; "#)
-        .bind(id)
-        .fetch_one(&app.db_pool)
-        .await
-        .expect("Failed to fetch saved product");
+    let data_from_db = sqlx::query_as::<_, ProductJson>(
+        r#"SELECT products.*, currencies.code, currencies.symbol FROM products
+        LEFT JOIN currencies ON products.currency = currencies.id where products.id=$1; "#,
+    )
+    .bind(id)
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved product");
 
     assert_eq!(data_from_db.id, data.id);
     assert_eq!(data_from_db.name, data.name);
     assert_eq!(data_from_db.currency, data.currency);
-    assert!(data_from_db.price.to_f64().unwrap() - data.price.to_f64().unwrap() <= f64::EPSILON);
+    assert_eq!(data.currency_code.as_deref(), Some("HKD"));
+    assert_eq!(data.currency_symbol.as_deref(), Some("HK$"));
+    assert_eq!(data_from_db.price.amount, data.price.amount);
     assert_eq!(data_from_db.created_at, data.created_at);
     assert_eq!(data_from_db.updated_at, data.updated_at);
     assert_eq!(data_from_db.deleted_at, data.deleted_at);
@@ -187,13 +266,30 @@ async fn update_product_return_a_400_when_data_is_invalid() {
     let login_body = app.login_body();
     let app = app.login(&login_body).await;
     let id = app.create_a_new_product().await;
-    let test_cases = vec![(
-        serde_json::json!({
-            "id": id,
-            "name": "",
-        }),
-        "product name is empty",
-    )];
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "id": id,
+                "name": "",
+            }),
+            "product name is empty",
+        ),
+        (
+            serde_json::json!({
+                "id": id.to_string(),
+                "price": "-1",
+            }),
+            "price is negative",
+        ),
+        (
+            serde_json::json!({
+                "id": id.to_string(),
+                "currency": 392,
+                "price": "19.99",
+            }),
+            "price has decimals in JPY",
+        ),
+    ];
 
     for (body, msg) in test_cases {
         let response = app.put("/api/v1/admin/products", &body).await;
@@ -206,6 +302,72 @@ async fn update_product_return_a_400_when_data_is_invalid() {
     }
 }
 
+#[tokio::test]
+async fn list_product_price_histories_works() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = app.create_a_new_product().await;
+
+    let bodies = vec![
+        serde_json::json!({ "id": id.to_string(), "name": "Pocky" }),
+        serde_json::json!({ "id": id.to_string(), "price": "15" }),
+        serde_json::json!({ "id": id.to_string(), "price": "15.00" }),
+        serde_json::json!({ "id": id.to_string(), "currency": 446 }),
+    ];
+    for body in bodies {
+        let response = app.put("/api/v1/admin/products", &body).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Act
+    let uri = format!("/api/v1/admin/products/{id}/price_histories");
+    let response = app.get(&uri).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let data = response
+        .json::<ListProductPriceHistoriesResponse>()
+        .await
+        .expect("Failed to decode json")
+        .data;
+
+    let expected = vec![
+        ((344, Decimal::new(20, 0)), (344, Decimal::new(15, 0))),
+        ((344, Decimal::new(15, 0)), (446, Decimal::new(15, 0))),
+    ];
+
+    assert_eq!(data.len(), expected.len());
+    for (history, (previous, current)) in data.iter().zip(expected) {
+        assert_eq!(history.product_id, id.to_string());
+        assert_eq!(
+            (history.previous_currency, history.previous_price.amount),
+            previous
+        );
+        assert_eq!((history.currency, history.price.amount), current);
+        assert_eq!(history.changed_by, app.test_user.id.to_string());
+        assert_eq!(
+            history.changed_by_username.as_ref(),
+            Some(&app.test_user.username)
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_product_price_histories_return_a_404_when_product_is_not_exist() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    // Act
+    let response = app.get("/api/v1/admin/products/1/price_histories").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
 #[tokio::test]
 async fn list_products_works() {
     // Arrange
@@ -253,11 +415,14 @@ async fn list_products_works_with_filter() {
     let data = response_json.unwrap();
     assert_eq!(data.data.len(), 20);
 
-    let data_from_db = sqlx::query_as::<_, ProductJson>(r#"SELECT * FROM products where id=$1; "#)
-        .bind(expected_ids[0])
-        .fetch_one(&app.db_pool)
-        .await
-        .expect("Failed to fetch saved products");
+    let data_from_db = sqlx::query_as::<_, ProductJson>(
+        r#"SELECT products.*, currencies.code, currencies.symbol FROM products
+        LEFT JOIN currencies ON products.currency = currencies.id where products.id=$1; "#,
+    )
+    .bind(expected_ids[0])
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved products");
 
     let id = data_from_db.id.to_string();
     let name = data_from_db.name;
@@ -323,3 +488,535 @@ async fn list_products_failed_when_keyword_is_invalid() {
     // Assert
     assert_eq!(response.status().as_u16(), 400);
 }
+
+async fn create_a_product_named(app: &AuthTestApp, name: &str) -> i64 {
+    let request = serde_json::json!({ "name": name, "currency": 344, "price": 20 });
+    let response = app.post("/api/v1/admin/products", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<CreateProductResponse>()
+        .await
+        .expect("Failed to parse the product")
+        .id
+}
+
+async fn search_products(app: &AuthTestApp, parameters: serde_json::Value) -> Vec<String> {
+    let keyword = base64::engine::general_purpose::STANDARD.encode(parameters.to_string());
+    let response = app
+        .get(&format!("/api/v1/admin/products?keyword={keyword}"))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<ListProductsResponse>()
+        .await
+        .expect("Failed to parse the products")
+        .data
+        .into_iter()
+        .map(|e| e.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn search_products_matches_names_across_scripts_and_typos() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    create_a_product_named(&app, "ポケモン カード").await;
+    create_a_product_named(&app, "Pokemon Plush").await;
+    create_a_product_named(&app, "ドラえもん 100%").await;
+    create_a_product_named(&app, "Kit Kat").await;
+
+    let test_cases = vec![
+        ("Pokemon", "the name is in another script"),
+        ("ポケモン", "the name is in another script"),
+        ("ﾎﾟｹﾓﾝ", "the katakana is half-width"),
+        ("ＰＯＫＥＭＯＮ", "the letters are full-width"),
+        ("pokmon", "there is a typo"),
+    ];
+
+    for (search, msg) in test_cases {
+        // Act
+        let mut names = search_products(&app, serde_json::json!({ "search": search })).await;
+
+        // Assert
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["Pokemon Plush", "ポケモン カード"],
+            "The search didn't match when {msg}",
+        );
+    }
+
+    let names = search_products(&app, serde_json::json!({ "search": "doraemon" })).await;
+    assert_eq!(names, vec!["ドラえもん 100%"]);
+    let names = search_products(&app, serde_json::json!({ "search": "%" })).await;
+    assert_eq!(names, vec!["ドラえもん 100%"]);
+    let names = search_products(&app, serde_json::json!({ "name": "kit" })).await;
+    assert_eq!(names, vec!["Kit Kat"]);
+    let names = search_products(&app, serde_json::json!({ "name": "%" })).await;
+    assert_eq!(names, vec!["ドラえもん 100%"]);
+}
+
+#[tokio::test]
+async fn reindex_search_texts_indexes_the_products_without_search_text() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let id = create_a_product_named(&app, "ポケモン カード").await;
+    sqlx::query("UPDATE products SET search_text = NULL WHERE id = $1")
+        .bind(id)
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to clear the search text");
+
+    // Act
+    let response = app
+        .post(
+            "/api/v1/admin/products/reindex_search_texts",
+            &serde_json::json!({}),
+        )
+        .await;
+    let again = app
+        .post(
+            "/api/v1/admin/products/reindex_search_texts",
+            &serde_json::json!({}),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response = response
+        .json::<ReindexSearchTextsResponse>()
+        .await
+        .expect("Failed to parse the response");
+    assert_eq!(response.indexed, 1);
+    let again = again
+        .json::<ReindexSearchTextsResponse>()
+        .await
+        .expect("Failed to parse the response");
+    assert_eq!(again.indexed, 0);
+    let names = search_products(&app, serde_json::json!({ "search": "pokemon" })).await;
+    assert_eq!(names, vec!["ポケモン カード"]);
+}
+
+#[tokio::test]
+async fn search_products_sorts_by_relevance() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let plush_id = create_a_product_named(&app, "Pokemon Plush").await;
+    let card_id = create_a_product_named(&app, "ポケモン カード").await;
+    let game_id = create_a_product_named(&app, "Pokemon Card Game").await;
+
+    // Act
+    let by_relevance =
+        search_products(&app, serde_json::json!({ "search": "pokemon card game" })).await;
+    let by_id = search_products(
+        &app,
+        serde_json::json!({ "search": "pokemon", "sort": "id" }),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(by_relevance[0], "Pokemon Card Game");
+    assert!(plush_id < card_id && card_id < game_id);
+    assert_eq!(
+        by_id,
+        vec!["Pokemon Plush", "ポケモン カード", "Pokemon Card Game"]
+    );
+}
+
+async fn adjust_stock(app: &AuthTestApp, product_id: i64, kind: &str, quantity: i32) -> u16 {
+    let request = serde_json::json!({
+        "product_id": product_id,
+        "kind": kind,
+        "quantity": quantity,
+        "reason": "stock take",
+    });
+
+    app.post("/api/v1/admin/products/stock_adjustments", &request)
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn stock_adjustments_change_the_on_hand_quantity() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+
+    // Act
+    assert_eq!(adjust_stock(&app, product_id, "received", 10).await, 200);
+    assert_eq!(adjust_stock(&app, product_id, "damaged", 3).await, 200);
+    assert_eq!(adjust_stock(&app, product_id, "correction", -2).await, 200);
+    assert_eq!(adjust_stock(&app, product_id, "damaged", 6).await, 409);
+
+    // Assert
+    let product = app.get_product(product_id).await;
+    assert_eq!(product.on_hand, 5);
+    assert_eq!(product.available, 5);
+
+    let response = app
+        .get(&format!(
+            "/api/v1/admin/products/{product_id}/stock_adjustments"
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response: ListStockAdjustmentsResponse = response
+        .json()
+        .await
+        .expect("Failed to parse stock adjustments");
+    let quantities = response
+        .data
+        .iter()
+        .map(|e| (e.kind, e.quantity))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        quantities,
+        vec![
+            (StockAdjustmentKind::Correction, -2),
+            (StockAdjustmentKind::Damaged, -3),
+            (StockAdjustmentKind::Received, 10),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stock_adjustment_returns_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+
+    let test_cases = vec![
+        ("received", 0, "stock take", "zero received quantity"),
+        ("damaged", -1, "stock take", "negative damaged quantity"),
+        ("correction", 0, "stock take", "zero correction"),
+        ("received", 1, " ", "empty reason"),
+    ];
+
+    for (kind, quantity, reason, description) in test_cases {
+        let request = serde_json::json!({
+            "product_id": product_id,
+            "kind": kind,
+            "quantity": quantity,
+            "reason": reason,
+        });
+
+        // Act
+        let response = app
+            .post("/api/v1/admin/products/stock_adjustments", &request)
+            .await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 when the payload was {}.",
+            description
+        );
+    }
+
+    assert_eq!(adjust_stock(&app, 1, "received", 1).await, 404);
+}
+
+#[tokio::test]
+async fn order_items_reserve_and_release_the_stock() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    assert_eq!(adjust_stock(&app, product_id, "received", 5).await, 200);
+
+    // Act
+    let first_id = app
+        .create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+    let second_id = app
+        .create_an_order_item_with(customer_id, product_id, 3)
+        .await;
+    let reserved = app.get_product(product_id).await;
+
+    app.change_order_item_status(second_id, &["cancelled"])
+        .await;
+    let released = app.get_product(product_id).await;
+
+    app.change_order_item_status(
+        first_id,
+        &["purchased_in_japan", "in_transit", "arrived", "picked_up"],
+    )
+    .await;
+    let picked_up = app.get_product(product_id).await;
+
+    // Assert
+    assert_eq!((reserved.on_hand, reserved.reserved), (5, 5));
+    assert_eq!(reserved.available, 0);
+    assert_eq!((released.on_hand, released.reserved), (5, 2));
+    assert_eq!((picked_up.on_hand, picked_up.reserved), (3, 0));
+    assert_eq!(picked_up.available, 3);
+}
+
+#[tokio::test]
+async fn order_item_can_not_be_picked_up_without_on_hand_stock() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let id = app
+        .create_an_order_item_with(customer_id, product_id, 2)
+        .await;
+    app.change_order_item_status(id, &["purchased_in_japan", "in_transit", "arrived"])
+        .await;
+    let request = serde_json::json!({ "id": id.to_string(), "status": "picked_up" });
+
+    // Act
+    let refused = app.put("/api/v1/admin/order_items", &request).await;
+    let product = app.get_product(product_id).await;
+    app.receive_stock(product_id, 2).await;
+    let picked_up = app.put("/api/v1/admin/order_items", &request).await;
+
+    // Assert
+    assert_eq!(refused.status().as_u16(), 409);
+    assert_eq!((product.on_hand, product.reserved), (0, 2));
+    assert_eq!(picked_up.status().as_u16(), 200);
+    let product = app.get_product(product_id).await;
+    assert_eq!((product.on_hand, product.reserved), (0, 0));
+}
+
+#[tokio::test]
+async fn create_order_item_refuses_to_oversell_when_asked() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    assert_eq!(adjust_stock(&app, product_id, "received", 2).await, 200);
+
+    let request = serde_json::json!({
+        "customer_id": customer_id,
+        "product_id": product_id,
+        "quantity": 3,
+        "reject_oversell": true,
+    });
+
+    // Act
+    let refused = app.post("/api/v1/admin/order_items", &request).await;
+    let oversold = app
+        .post(
+            "/api/v1/admin/order_items",
+            &serde_json::json!({
+                "customer_id": customer_id,
+                "product_id": product_id,
+                "quantity": 3,
+            }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(refused.status().as_u16(), 409);
+    assert_eq!(oversold.status().as_u16(), 200);
+    let product = app.get_product(product_id).await;
+    assert_eq!(product.available, -1);
+}
+
+async fn create_a_variant(app: &AuthTestApp, product_id: i64, sku: &str, size: &str) -> u16 {
+    let request = serde_json::json!({
+        "product_id": product_id,
+        "sku": sku,
+        "options": [{ "name": "size", "value": size }, { "name": "colour", "value": "red" }],
+    });
+
+    app.post("/api/v1/admin/products/variants", &request)
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn product_variants_are_listed_with_the_product() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+    let sku = format!("SKU-{product_id}");
+
+    // Act
+    let created = create_a_variant(&app, product_id, &sku, "M").await;
+    let duplicate = create_a_variant(&app, product_id, &sku, "L").await;
+    let other = create_a_variant(&app, product_id, &format!("{sku}-L"), "L").await;
+
+    // Assert
+    assert_eq!(created, 200);
+    assert_eq!(duplicate, 409);
+    assert_eq!(other, 200);
+
+    let product = app.get_product(product_id).await;
+    assert_eq!(product.variants.len(), 2);
+    assert_eq!(product.variants[0].sku, sku);
+    assert_eq!(product.variants[0].options[0].name, "size");
+    assert_eq!(product.variants[0].options[0].value, "M");
+    assert_eq!(product.variants[0].price, None);
+
+    let keyword =
+        base64::engine::general_purpose::STANDARD.encode(format!(r#"{{ "id": {product_id} }}"#));
+    let listed = app
+        .get(&format!("/api/v1/admin/products?keyword={keyword}"))
+        .await
+        .json::<ListProductsResponse>()
+        .await
+        .expect("Failed to parse the products");
+    assert_eq!(listed.data[0].variants.len(), 2);
+
+    let response = app
+        .delete(
+            "/api/v1/admin/products/variants",
+            &serde_json::json!({ "id": product.variants[1].id }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(app.get_product(product_id).await.variants.len(), 1);
+}
+
+#[tokio::test]
+async fn create_product_variant_returns_a_400_when_options_are_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let product_id = app.create_a_new_product().await;
+
+    let test_cases = vec![
+        (serde_json::json!([]), "no option"),
+        (
+            serde_json::json!([{ "name": " ", "value": "M" }]),
+            "empty option name",
+        ),
+        (
+            serde_json::json!([{ "name": "size", "value": "M" }, { "name": "size", "value": "L" }]),
+            "duplicate option name",
+        ),
+    ];
+
+    for (options, error_message) in test_cases {
+        // Act
+        let request = serde_json::json!({
+            "product_id": product_id,
+            "sku": format!("SKU-{product_id}"),
+            "options": options,
+        });
+        let response = app.post("/api/v1/admin/products/variants", &request).await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the payload was {}.",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn order_items_of_a_variant_take_its_stock_and_price() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let customer_id = app.create_a_new_customer().await;
+    let product_id = app.create_a_new_product().await;
+    let other_product_id = app.create_a_new_product().await;
+
+    let response = app
+        .post(
+            "/api/v1/admin/products/variants",
+            &serde_json::json!({
+                "product_id": product_id,
+                "sku": format!("SKU-{product_id}"),
+                "options": [{ "name": "size", "value": "XL" }],
+                "price": "25.50",
+            }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let variant_id = response
+        .json::<CreateProductVariantResponse>()
+        .await
+        .expect("Failed to parse the variant")
+        .id;
+
+    let request = serde_json::json!({
+        "product_id": product_id,
+        "variant_id": variant_id.to_string(),
+        "kind": "received",
+        "quantity": 3,
+        "reason": "stock take",
+    });
+    let response = app
+        .post("/api/v1/admin/products/stock_adjustments", &request)
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act
+    let order_item = |product_id: i64, quantity: u32| {
+        serde_json::json!({
+            "customer_id": customer_id,
+            "product_id": product_id,
+            "variant_id": variant_id.to_string(),
+            "quantity": quantity,
+            "reject_oversell": true,
+        })
+    };
+    let created = app
+        .post("/api/v1/admin/order_items", &order_item(product_id, 2))
+        .await;
+    let oversold = app
+        .post("/api/v1/admin/order_items", &order_item(product_id, 2))
+        .await;
+    let mismatched = app
+        .post(
+            "/api/v1/admin/order_items",
+            &order_item(other_product_id, 1),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(created.status().as_u16(), 200);
+    assert_eq!(oversold.status().as_u16(), 409);
+    assert_eq!(mismatched.status().as_u16(), 422);
+
+    let order_item_id = created
+        .json::<CreateOrderItemResponse>()
+        .await
+        .expect("Failed to parse the order item")
+        .id;
+    let order_item = app
+        .get(&format!("/api/v1/admin/order_items/{order_item_id}"))
+        .await
+        .json::<OrderItemJson>()
+        .await
+        .expect("Failed to parse the order item");
+    assert_eq!(order_item.variant_id, Some(variant_id.to_string()));
+    assert_eq!(order_item.unit_price.amount, Decimal::new(2550, 2));
+
+    let product = app.get_product(product_id).await;
+    assert_eq!((product.on_hand, product.reserved), (0, 0));
+    assert_eq!(
+        (product.variants[0].on_hand, product.variants[0].reserved),
+        (3, 2)
+    );
+}
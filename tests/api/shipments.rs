@@ -0,0 +1,251 @@
+use base64::Engine;
+
+use japonfou::routes::{
+    CreateShipmentResponse, ListShipmentsResponse, OrderItemJson, OrderItemStatus, ShipmentJson,
+    UpdateOrderItemStatusesResponse,
+};
+
+use crate::helpers::{spawn_app, AuthTestApp};
+
+async fn create_a_shipment_with(app: &AuthTestApp, order_item_ids: &[i64]) -> i64 {
+    let request = serde_json::json!({
+        "carrier": "EMS",
+        "tracking_number": "EN123456789JP",
+        "shipped_at": "2024-04-20T00:00:00Z",
+        "expected_arrival_at": "2024-04-25T00:00:00Z",
+        "order_item_ids": order_item_ids.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+    });
+
+    let response = app.post("/api/v1/admin/shipments", &request).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response: CreateShipmentResponse =
+        response.json().await.expect("Failed to parse the shipment");
+    response.id
+}
+
+#[tokio::test]
+async fn create_shipment_moves_purchased_order_items_to_in_transit() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let first_id = app.create_a_new_order_item().await;
+    let second_id = app.create_a_new_order_item().await;
+    app.change_order_item_status(first_id, &["purchased_in_japan"])
+        .await;
+    app.change_order_item_status(second_id, &["purchased_in_japan", "in_transit"])
+        .await;
+
+    // Act
+    let id = create_a_shipment_with(&app, &[first_id, second_id]).await;
+
+    // Assert
+    let shipment: ShipmentJson = app
+        .get(&format!("/api/v1/admin/shipments/{id}"))
+        .await
+        .json()
+        .await
+        .expect("Failed to parse the shipment");
+    assert_eq!(shipment.carrier, "EMS");
+    assert_eq!(shipment.tracking_number.as_deref(), Some("EN123456789JP"));
+    assert!(shipment.arrived_at.is_none());
+    assert_eq!(shipment.order_items.len(), 2);
+    assert!(shipment
+        .order_items
+        .iter()
+        .all(|e| e.status == OrderItemStatus::InTransit
+            && e.shipment_id.as_deref() == Some(id.to_string().as_str())));
+}
+
+#[tokio::test]
+async fn create_shipment_returns_a_422_when_order_item_is_not_shippable() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let requested_id = app.create_a_new_order_item().await;
+    let shipped_id = app.create_a_new_order_item().await;
+    app.change_order_item_status(shipped_id, &["purchased_in_japan"])
+        .await;
+    create_a_shipment_with(&app, &[shipped_id]).await;
+
+    let test_cases = vec![
+        (requested_id, "order item which isn't purchased"),
+        (shipped_id, "order item of another shipment"),
+        (1, "missing order item"),
+    ];
+
+    for (order_item_id, description) in test_cases {
+        let request = serde_json::json!({
+            "carrier": "EMS",
+            "order_item_ids": [order_item_id.to_string()],
+        });
+
+        // Act
+        let response = app.post("/api/v1/admin/shipments", &request).await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "The API did not fail with 422 when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_shipment_returns_a_400_when_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let order_item_id = app.create_a_new_order_item().await.to_string();
+
+    let test_cases = vec![
+        (
+            serde_json::json!({ "carrier": " ", "order_item_ids": [order_item_id] }),
+            "empty carrier",
+        ),
+        (
+            serde_json::json!({ "carrier": "EMS", "order_item_ids": [] }),
+            "no order item",
+        ),
+        (
+            serde_json::json!({ "carrier": "EMS", "order_item_ids": ["abc"] }),
+            "invalid order item id",
+        ),
+        (
+            serde_json::json!({
+                "carrier": "EMS",
+                "shipped_at": "2024-04-20T00:00:00Z",
+                "expected_arrival_at": "2024-04-19T00:00:00Z",
+                "order_item_ids": [order_item_id],
+            }),
+            "arrival earlier than shipped date",
+        ),
+    ];
+
+    for (request, description) in test_cases {
+        // Act
+        let response = app.post("/api/v1/admin/shipments", &request).await;
+
+        // Assert
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn arrive_shipment_moves_order_items_to_arrived() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let first_id = app.create_a_new_order_item().await;
+    let second_id = app.create_a_new_order_item().await;
+    app.change_order_item_status(first_id, &["purchased_in_japan"])
+        .await;
+    app.change_order_item_status(second_id, &["purchased_in_japan"])
+        .await;
+    let id = create_a_shipment_with(&app, &[first_id, second_id]).await;
+    app.change_order_item_status(second_id, &["refunded"]).await;
+
+    let request = serde_json::json!({ "id": id.to_string() });
+
+    // Act
+    let response = app.put("/api/v1/admin/shipments/arrival", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: UpdateOrderItemStatusesResponse =
+        response.json().await.expect("Failed to parse the response");
+    assert_eq!(response.data.len(), 2);
+
+    let first = response
+        .data
+        .iter()
+        .find(|e| e.id == first_id.to_string())
+        .unwrap();
+    assert!(first.success);
+    let second = response
+        .data
+        .iter()
+        .find(|e| e.id == second_id.to_string())
+        .unwrap();
+    assert!(!second.success);
+
+    let order_item: OrderItemJson = app
+        .get(&format!("/api/v1/admin/order_items/{first_id}"))
+        .await
+        .json()
+        .await
+        .expect("Failed to parse the order item");
+    assert_eq!(order_item.status, OrderItemStatus::Arrived);
+
+    let shipment: ShipmentJson = app
+        .get(&format!("/api/v1/admin/shipments/{id}"))
+        .await
+        .json()
+        .await
+        .expect("Failed to parse the shipment");
+    assert!(shipment.arrived_at.is_some());
+
+    let response = app.put("/api/v1/admin/shipments/arrival", &request).await;
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn arrive_shipment_returns_a_404_when_shipment_is_missing() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+
+    let request = serde_json::json!({ "id": "1" });
+
+    // Act
+    let response = app.put("/api/v1/admin/shipments/arrival", &request).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn list_shipments_finds_the_shipment_of_an_order_item() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = app.login_body();
+    let app = app.login(&login_body).await;
+    let first_id = app.create_a_new_order_item().await;
+    let second_id = app.create_a_new_order_item().await;
+    app.change_order_item_status(first_id, &["purchased_in_japan"])
+        .await;
+    app.change_order_item_status(second_id, &["purchased_in_japan"])
+        .await;
+    let id = create_a_shipment_with(&app, &[first_id]).await;
+    create_a_shipment_with(&app, &[second_id]).await;
+
+    let keyword = base64::engine::general_purpose::STANDARD.encode(
+        serde_json::json!({
+            "order_item_id": first_id,
+        })
+        .to_string(),
+    );
+
+    // Act
+    let response = app
+        .get(&format!("/api/v1/admin/shipments?keyword={keyword}"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let response: ListShipmentsResponse = response.json().await.expect("Failed to parse shipments");
+    assert_eq!(response.data.len(), 1);
+    assert_eq!(response.data[0].id, id.to_string());
+    assert_eq!(response.data[0].order_items.len(), 1);
+}